@@ -1,24 +1,381 @@
 use base32::Alphabet;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::io;
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use totp_rs::{Algorithm, TOTP};
 use xdg::BaseDirectories;
-use std::io;
+
+// Exit status 2 is taken by clap for usage errors
+
+/// Exit status when a launcher could not be connected to
+const EXIT_UNREACHABLE: u8 = 3;
+/// Exit status when a launcher answered with a non-2xx status
+const EXIT_HTTP_STATUS: u8 = 4;
+/// Exit status when a request failed for any other reason, e.g. a timeout
+const EXIT_REQUEST_FAILED: u8 = 5;
+
+/// Delay between attempts to reach a launcher that refused the connection
+const RETRY_DELAY: Duration = Duration::from_secs(1);
 
 #[derive(Parser)]
-#[command(author, version, about)]
+#[command(
+    author,
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
-    nickname: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Host nicknames or group names to send the code to
+    #[arg(required_unless_present = "all")]
+    nicknames: Vec<String>,
+
+    /// Send the code to every configured host
+    #[arg(long, conflicts_with = "nicknames")]
+    all: bool,
+
+    /// Output template replacing the default messages. Placeholders:
+    /// {code}, {nickname}, {status}. Templates using {nickname} or {status}
+    /// are printed once per host, others once per run.
+    #[arg(long)]
+    format: Option<String>,
+
+    /// Launcher port to use for every host, overriding the config
+    #[arg(long)]
+    port: Option<u16>,
+
+    /// Times to retry a launcher that refuses the connection, e.g. because
+    /// it isn't listening yet, a second apart
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+
+    /// Wait for the next code if the current one expires in fewer than
+    /// this many seconds, e.g. 5, so it can't lapse on its way to a launcher
+    #[arg(long, default_value_t = 0, value_name = "SECS")]
+    min_validity: u64,
+
+    /// Print just the code and exit without sending it anywhere. Hosts
+    /// with different secrets get a "<nickname> <code>" line each.
+    #[arg(long, conflicts_with_all = ["format", "clipboard"])]
+    code_only: bool,
+
+    /// Also copy the code to the clipboard. Failing to is only a warning.
+    #[arg(long)]
+    clipboard: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the configured host nicknames and hostnames
+    List,
+}
+
+/// Print each configured host, sorted by nickname
+fn list_hosts(config: &Config) {
+    let mut hosts: Vec<(&String, &Host)> = config.hosts.iter().collect();
+    hosts.sort_by(|a, b| a.0.cmp(b.0));
+    let width = hosts
+        .iter()
+        .map(|(nickname, _)| nickname.len())
+        .max()
+        .unwrap_or(0);
+    for (nickname, host) in hosts {
+        println!("{nickname:width$}  {}", host.hostname);
+    }
+}
+
+/// Fill in an output template's placeholders
+fn render(template: &str, code: &str, nickname: &str, status: &str) -> String {
+    template
+        .replace("{code}", code)
+        .replace("{nickname}", nickname)
+        .replace("{status}", status)
 }
 
 #[derive(Deserialize)]
 struct Config {
-    totp_secret: String,
-    hosts: HashMap<String, String>,
+    /// Default secret for hosts that don't set their own
+    #[serde(default)]
+    totp_secret: Option<String>,
+    /// Default launcher port for hosts that don't set their own
+    #[serde(default = "default_port")]
+    port: u16,
+    /// TOTP hash algorithm: SHA1, SHA256 or SHA512
+    #[serde(default = "default_algorithm")]
+    algorithm: String,
+    /// Number of digits in each code, 6 to 8
+    #[serde(default = "default_digits")]
+    digits: usize,
+    /// Seconds each code stays valid
+    #[serde(default = "default_period")]
+    period: u64,
+    /// Launcher URL scheme: http, or https behind a TLS-terminating proxy
+    #[serde(default = "default_scheme")]
+    scheme: String,
+    /// Launcher URL path, with {code} replaced by the code
+    #[serde(default = "default_path")]
+    path: String,
+    hosts: HashMap<String, Host>,
+    #[serde(default)]
+    groups: HashMap<String, Vec<String>>,
+}
+
+fn default_port() -> u16 {
+    4646
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn default_algorithm() -> String {
+    "SHA1".to_string()
+}
+
+fn default_digits() -> usize {
+    6
+}
+
+fn default_period() -> u64 {
+    30
+}
+
+fn default_scheme() -> String {
+    "http".to_string()
+}
+
+fn default_path() -> String {
+    "/ffxivlauncher/{code}".to_string()
+}
+
+/// Check the configured launcher URL scheme and path template
+fn check_launcher_url(config: &Config) -> Result<(), String> {
+    if !matches!(config.scheme.as_str(), "http" | "https") {
+        return Err(format!(
+            "Unsupported launcher scheme: {} (expected http or https)",
+            config.scheme
+        ));
+    }
+    if !config.path.starts_with('/') {
+        return Err(format!(
+            "Launcher path must start with '/': {}",
+            config.path
+        ));
+    }
+    if !config.path.contains("{code}") {
+        return Err(format!(
+            "Launcher path must contain {{code}}: {}",
+            config.path
+        ));
+    }
+    Ok(())
+}
+
+/// The URL sending a code to the launcher at `address` ("host:port")
+fn launcher_url(config: &Config, address: &str, code: &str) -> String {
+    format!(
+        "{}://{address}{}",
+        config.scheme,
+        config.path.replace("{code}", code)
+    )
+}
+
+/// Parse a config algorithm name, accepting e.g. "sha256" or "SHA-256"
+fn parse_algorithm(name: &str) -> Result<Algorithm, String> {
+    match name.to_ascii_uppercase().replace('-', "").as_str() {
+        "SHA1" => Ok(Algorithm::SHA1),
+        "SHA256" => Ok(Algorithm::SHA256),
+        "SHA512" => Ok(Algorithm::SHA512),
+        _ => Err(format!(
+            "Unsupported TOTP algorithm: {name} (expected SHA1, SHA256 or SHA512)"
+        )),
+    }
+}
+
+/// A launcher host, written either as a bare hostname or as a table
+#[derive(Deserialize)]
+#[serde(from = "HostEntry")]
+struct Host {
+    hostname: String,
+    totp_secret: Option<String>,
+    port: Option<u16>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HostEntry {
+    Hostname(String),
+    Full {
+        hostname: String,
+        #[serde(default)]
+        totp_secret: Option<String>,
+        #[serde(default)]
+        port: Option<u16>,
+    },
+}
+
+impl From<HostEntry> for Host {
+    fn from(entry: HostEntry) -> Self {
+        match entry {
+            HostEntry::Hostname(hostname) => Host {
+                hostname,
+                totp_secret: None,
+                port: None,
+            },
+            HostEntry::Full {
+                hostname,
+                totp_secret,
+                port,
+            } => Host {
+                hostname,
+                totp_secret,
+                port,
+            },
+        }
+    }
+}
+
+/// Keys shorter than the 80 bits most issuers use are likely a truncated paste
+const MIN_SECRET_BYTES: usize = 10;
+
+/// Decode a base32 TOTP secret as pasted from an authenticator export:
+/// spaces, dashes and padding are dropped and case is ignored
+fn decode_secret(raw: &str) -> Result<Vec<u8>, String> {
+    let mut secret = String::with_capacity(raw.len());
+    for (position, c) in raw.chars().enumerate() {
+        match c.to_ascii_uppercase() {
+            c if c.is_whitespace() || c == '-' || c == '=' => {}
+            c @ ('A'..='Z' | '2'..='7') => secret.push(c),
+            _ => {
+                return Err(format!(
+                    "invalid base32 character {c:?} at position {}",
+                    position + 1
+                ))
+            }
+        }
+    }
+    if secret.is_empty() {
+        return Err("secret is empty".to_string());
+    }
+    base32::decode(Alphabet::Rfc4648 { padding: false }, &secret)
+        .ok_or_else(|| format!("{} base32 characters is not a valid length", secret.len()))
+}
+
+/// Generate the code for a decoded TOTP secret at `time`, in seconds
+/// since the Unix epoch
+fn generate_code(algorithm: Algorithm, config: &Config, secret: &[u8], time: u64) -> String {
+    // Digits are checked up front and short keys only warn, so skip
+    // totp-rs's own checks, which would reject common 80-bit secrets
+    let totp = TOTP::new_unchecked(algorithm, config.digits, 1, config.period, secret.to_vec());
+    totp.generate(time)
+}
+
+/// Seconds until a code generated at `time` is replaced by the next one
+fn seconds_left(period: u64, time: u64) -> u64 {
+    period - time % period
+}
+
+/// Copy the code to the clipboard, warning instead of failing if there is
+/// none. The clipboard is returned to be kept until exit: on X11 the code
+/// is served by this process and handed to a clipboard manager on drop.
+fn copy_to_clipboard(code: &str) -> Option<arboard::Clipboard> {
+    let copied = arboard::Clipboard::new().and_then(|mut clipboard| {
+        clipboard.set_text(code)?;
+        Ok(clipboard)
+    });
+    match copied {
+        Ok(clipboard) => Some(clipboard),
+        Err(e) => {
+            eprintln!("Warning: could not copy the code to the clipboard: {e}");
+            None
+        }
+    }
+}
+
+/// Whether a request failed because nothing was listening on the port yet
+fn is_refused(e: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(e);
+    while let Some(cause) = source {
+        if let Some(io) = cause.downcast_ref::<io::Error>() {
+            return io.kind() == io::ErrorKind::ConnectionRefused;
+        }
+        source = cause.source();
+    }
+    false
+}
+
+/// Send the code to a launcher, retrying up to `retries` times while it
+/// refuses the connection. Any answer, successful or not, is final.
+fn send_code(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    retries: u32,
+    nickname: &str,
+) -> reqwest::Result<reqwest::blocking::Response> {
+    let mut attempt = 0;
+    loop {
+        match client.get(url).send() {
+            Err(e) if is_refused(&e) && attempt < retries => {
+                attempt += 1;
+                eprintln!(
+                    "{nickname}: launcher refused the connection, retrying ({attempt}/{retries})"
+                );
+                std::thread::sleep(RETRY_DELAY);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Resolve nicknames and group names to (nickname, host) pairs,
+/// dropping duplicates while keeping the order given
+fn resolve_hosts<'a>(
+    config: &'a Config,
+    args: &'a Args,
+) -> Result<Vec<(&'a str, &'a Host)>, String> {
+    let mut nicknames: Vec<&str> = if args.all {
+        let mut all: Vec<&str> = config.hosts.keys().map(String::as_str).collect();
+        all.sort();
+        all
+    } else {
+        let mut expanded = Vec::new();
+        for name in &args.nicknames {
+            match config.groups.get(name) {
+                Some(members) => expanded.extend(members.iter().map(String::as_str)),
+                None => expanded.push(name.as_str()),
+            }
+        }
+        expanded
+    };
+
+    let mut seen = std::collections::HashSet::new();
+    nicknames.retain(|nickname| seen.insert(*nickname));
+
+    nicknames
+        .into_iter()
+        .map(|nickname| {
+            config
+                .hosts
+                .get(nickname)
+                .map(|host| (nickname, host))
+                .ok_or_else(|| format!("Nickname not found in config: {nickname}"))
+        })
+        .collect()
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<ExitCode, Box<dyn std::error::Error>> {
     let args = Args::parse();
 
     // Set up XDG paths
@@ -34,31 +391,339 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let config_str = std::fs::read_to_string(config_path)?;
     let config: Config = toml::from_str(&config_str)?;
 
-    // Look up hostname
-    let hostname = config
-        .hosts
-        .get(&args.nickname)
-        .ok_or("Nickname not found in config")?;
-
-    // Generate TOTP
-    let totp = TOTP::new(
-        Algorithm::SHA1,
-        6,
-        1,
-        30,
-        base32::decode(Alphabet::Rfc4648 { padding: false }, &config.totp_secret).ok_or(
-            io::Error::new(io::ErrorKind::InvalidData, "Invalid TOTP secret"),
-        )?,
-    )?;
-
-    let code = totp.generate_current()?;
-    println!("Code is {code}");
-    // Make HTTP request
-    let url = format!("http://{}:4646/ffxivlauncher/{}", hostname, code);
+    if let Some(Command::List) = args.command {
+        list_hosts(&config);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Check the TOTP parameters before generating anything
+    let algorithm = parse_algorithm(&config.algorithm)?;
+    if !(6..=8).contains(&config.digits) {
+        return Err(format!("TOTP digits must be between 6 and 8, got {}", config.digits).into());
+    }
+    if config.period == 0 {
+        return Err("TOTP period must be at least 1 second".into());
+    }
+    if args.min_validity >= config.period {
+        return Err(format!(
+            "--min-validity must be less than the {}s TOTP period",
+            config.period
+        )
+        .into());
+    }
+    check_launcher_url(&config)?;
+
+    // Validate every configured secret, not just the ones in use, so a bad
+    // paste shows up on the first run
+    let mut secrets: Vec<(Option<&str>, &str)> = config
+        .totp_secret
+        .as_deref()
+        .map(|secret| (None, secret))
+        .into_iter()
+        .collect();
+    let mut nicknames: Vec<&String> = config.hosts.keys().collect();
+    nicknames.sort();
+    for nickname in nicknames {
+        if let Some(secret) = config.hosts[nickname].totp_secret.as_deref() {
+            secrets.push((Some(nickname), secret));
+        }
+    }
+    let mut decoded: HashMap<Option<&str>, Vec<u8>> = HashMap::new();
+    for (owner, secret) in secrets {
+        let owner_name = match owner {
+            Some(nickname) => format!("totp_secret for {nickname}"),
+            None => "totp_secret".to_string(),
+        };
+        let key = decode_secret(secret).map_err(|e| format!("Invalid {owner_name}: {e}"))?;
+        if key.len() < MIN_SECRET_BYTES {
+            eprintln!(
+                "Warning: {owner_name} is only {} bits long; check it was copied in full",
+                key.len() * 8
+            );
+        }
+        decoded.insert(owner, key);
+    }
+
+    // Look up hostnames
+    let targets = resolve_hosts(&config, &args)?;
+
+    // Wait out a code about to expire rather than race the launcher with it
+    let mut now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    let mut valid = seconds_left(config.period, now.as_secs());
+    if valid < args.min_validity {
+        eprintln!("Code expires in {valid}s, waiting for the next one");
+        std::thread::sleep(
+            Duration::from_secs(valid) - Duration::from_nanos(now.subsec_nanos().into()),
+        );
+        now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        valid = seconds_left(config.period, now.as_secs());
+    }
+
+    // Generate one TOTP code per distinct secret
+    let mut codes: HashMap<&[u8], String> = HashMap::new();
+    let mut host_codes = Vec::with_capacity(targets.len());
+    for (nickname, host) in &targets {
+        let owner = host.totp_secret.as_ref().map(|_| *nickname);
+        let secret = decoded
+            .get(&owner)
+            .ok_or_else(|| format!("No TOTP secret configured for {nickname}"))?;
+        if !codes.contains_key(secret.as_slice()) {
+            codes.insert(
+                secret,
+                generate_code(algorithm, &config, secret, now.as_secs()),
+            );
+        }
+        host_codes.push(codes[secret.as_slice()].clone());
+    }
+
+    // With several secrets in play there is no single code to print per run
+    let shared_code = (codes.len() == 1).then(|| host_codes[0].clone());
+    if args.code_only {
+        match &shared_code {
+            Some(code) => println!("{code}"),
+            None => {
+                for ((nickname, _), code) in targets.iter().zip(&host_codes) {
+                    println!("{nickname} {code}");
+                }
+            }
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    let per_host_format = args
+        .format
+        .as_deref()
+        .filter(|f| shared_code.is_none() || f.contains("{nickname}") || f.contains("{status}"));
+    match (&args.format, per_host_format, &shared_code) {
+        (None, _, Some(code)) => println!("Code is {code} (valid for {valid}s)"),
+        (Some(template), None, Some(code)) if !template.is_empty() => {
+            println!("{}", render(template, code, "", ""))
+        }
+        _ => {}
+    }
+
+    let _clipboard = match (&shared_code, args.clipboard) {
+        (Some(code), true) => copy_to_clipboard(code),
+        (None, true) => {
+            eprintln!(
+                "Warning: hosts use different secrets, so no code was copied to the clipboard"
+            );
+            None
+        }
+        (_, false) => None,
+    };
+
+    // Make HTTP requests, one per host
     let client = reqwest::blocking::Client::new();
-    let response = client.get(&url).send()?;
+    let mut failures = 0;
+    let mut exit_code = None;
+    for ((nickname, host), code) in targets.iter().zip(&host_codes) {
+        if args.format.is_none() && shared_code.is_none() {
+            println!("{nickname}: code is {code} (valid for {valid}s)");
+        }
+        let address = format!(
+            "{}:{}",
+            host.hostname,
+            args.port.or(host.port).unwrap_or(config.port)
+        );
+        let url = launcher_url(&config, &address, code);
+        let (status, failure) = match send_code(&client, &url, args.retries, nickname) {
+            Ok(response) if response.status().is_success() => {
+                if args.format.is_none() {
+                    println!("{nickname}: response status: {}", response.status());
+                }
+                (response.status().as_u16().to_string(), None)
+            }
+            Ok(response) => {
+                eprintln!(
+                    "{nickname}: FFXIV launcher at {address} rejected the code: {}",
+                    response.status()
+                );
+                (
+                    response.status().as_u16().to_string(),
+                    Some(EXIT_HTTP_STATUS),
+                )
+            }
+            Err(e) if e.is_connect() => {
+                eprintln!("{nickname}: FFXIV launcher not reachable at {address} — is it running?");
+                ("error".to_string(), Some(EXIT_UNREACHABLE))
+            }
+            Err(e) => {
+                eprintln!("{nickname}: request to {address} failed: {e}");
+                ("error".to_string(), Some(EXIT_REQUEST_FAILED))
+            }
+        };
+        if let Some(failure) = failure {
+            failures += 1;
+            exit_code.get_or_insert(failure);
+        }
 
-    println!("Response status: {}", response.status());
+        if let Some(template) = per_host_format {
+            println!("{}", render(template, code, nickname, &status));
+        }
+    }
 
-    Ok(())
+    // Exit with the first failure's status so scripts can tell causes apart
+    if let Some(exit_code) = exit_code {
+        eprintln!("{failures} of {} host(s) failed", targets.len());
+        return Ok(ExitCode::from(exit_code));
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        toml::from_str(
+            r#"
+            [hosts]
+            desktop = "desktop.lan"
+            laptop = "laptop.lan"
+            steamdeck = "deck.lan"
+
+            [groups]
+            home = ["desktop", "laptop"]
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn secret_separators_and_case_are_ignored() {
+        let plain = decode_secret("JBSWY3DPEHPK3PXP").unwrap();
+        assert_eq!(decode_secret("jbsw y3dp-ehpk 3pxp==").unwrap(), plain);
+        assert_eq!(plain, b"Hello!\xde\xad\xbe\xef");
+    }
+
+    #[test]
+    fn bad_secret_character_reports_its_position() {
+        assert_eq!(
+            decode_secret("JBSW Y3D1").unwrap_err(),
+            "invalid base32 character '1' at position 9"
+        );
+        assert_eq!(decode_secret(" - ").unwrap_err(), "secret is empty");
+    }
+
+    #[test]
+    fn algorithm_names_are_forgiving() {
+        assert!(matches!(parse_algorithm("sha1"), Ok(Algorithm::SHA1)));
+        assert!(matches!(parse_algorithm("SHA-256"), Ok(Algorithm::SHA256)));
+        assert!(matches!(parse_algorithm("sha-512"), Ok(Algorithm::SHA512)));
+        assert!(parse_algorithm("MD5").is_err());
+    }
+
+    #[test]
+    fn groups_expand_without_duplicates() {
+        let config = config();
+        let args = Args::parse_from(["ffxiv-otp", "laptop", "home", "steamdeck", "desktop"]);
+        let targets = resolve_hosts(&config, &args).unwrap();
+        let nicknames: Vec<&str> = targets.iter().map(|(nickname, _)| *nickname).collect();
+        assert_eq!(nicknames, ["laptop", "desktop", "steamdeck"]);
+
+        let args = Args::parse_from(["ffxiv-otp", "home", "nas"]);
+        match resolve_hosts(&config, &args) {
+            Err(e) => assert_eq!(e, "Nickname not found in config: nas"),
+            Ok(_) => panic!("unknown nickname was resolved"),
+        }
+    }
+
+    #[test]
+    fn all_hosts_are_sorted() {
+        let config = config();
+        let args = Args::parse_from(["ffxiv-otp", "--all"]);
+        let targets = resolve_hosts(&config, &args).unwrap();
+        let hostnames: Vec<&str> = targets
+            .iter()
+            .map(|(_, host)| host.hostname.as_str())
+            .collect();
+        assert_eq!(hostnames, ["desktop.lan", "laptop.lan", "deck.lan"]);
+    }
+
+    #[test]
+    fn launcher_url_defaults_to_the_standard_endpoint() {
+        let config = config();
+        check_launcher_url(&config).unwrap();
+        assert_eq!(
+            launcher_url(&config, "desktop.lan:4646", "123456"),
+            "http://desktop.lan:4646/ffxivlauncher/123456"
+        );
+
+        let proxied = Config {
+            scheme: "https".to_string(),
+            path: "/otp?code={code}".to_string(),
+            ..config
+        };
+        check_launcher_url(&proxied).unwrap();
+        assert_eq!(
+            launcher_url(&proxied, "proxy.lan:443", "123456"),
+            "https://proxy.lan:443/otp?code=123456"
+        );
+    }
+
+    #[test]
+    fn launcher_path_needs_a_code_placeholder() {
+        let config = Config {
+            path: "/ffxivlauncher/".to_string(),
+            ..config()
+        };
+        assert_eq!(
+            check_launcher_url(&config).unwrap_err(),
+            "Launcher path must contain {code}: /ffxivlauncher/"
+        );
+        let config = Config {
+            scheme: "ftp".to_string(),
+            ..config
+        };
+        assert!(check_launcher_url(&config).is_err());
+    }
+
+    #[test]
+    fn closed_port_is_refused_and_retried() {
+        // Find a port nothing is listening on
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let url = format!("http://127.0.0.1:{port}/ffxivlauncher/123456");
+        let client = reqwest::blocking::Client::new();
+
+        let started = std::time::Instant::now();
+        let e = send_code(&client, &url, 1, "desktop").unwrap_err();
+        assert!(is_refused(&e), "{e}");
+        assert!(started.elapsed() >= RETRY_DELAY);
+    }
+
+    #[test]
+    fn codes_expire_at_the_end_of_their_window() {
+        assert_eq!(seconds_left(30, 60), 30);
+        assert_eq!(seconds_left(30, 82), 8);
+        assert_eq!(seconds_left(30, 89), 1);
+
+        // RFC 6238's SHA1 test vector at T = 59
+        let config = Config {
+            digits: 8,
+            ..config()
+        };
+        assert_eq!(
+            generate_code(Algorithm::SHA1, &config, b"12345678901234567890", 59),
+            "94287082"
+        );
+    }
+
+    #[test]
+    fn templates_fill_every_placeholder() {
+        assert_eq!(
+            render(
+                "{nickname}: {code} ({status}) {code}",
+                "123456",
+                "desktop",
+                "200"
+            ),
+            "desktop: 123456 (200) 123456"
+        );
+        assert_eq!(render("{other}", "123456", "", ""), "{other}");
+    }
 }
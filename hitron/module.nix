@@ -12,7 +12,7 @@ in
     webhookFile = mkOption {
       type = types.path;
       example = "/var/secrets/discord-webhook";
-      description = "Path to file containing DISCORD_WEBHOOK environment variable";
+      description = "Path to file containing the DISCORD_WEBHOOK environment variable (a Discord or Slack webhook URL; or DISCORD_BOT_TOKEN, with channelId set), optionally DISCORD_DIGEST_WEBHOOK for a quieter Discord webhook taking everything short of critical, plus MODEM_USERNAME and MODEM_PASSWORD for firmware that requires a login";
     };
 
     pollInterval = mkOption {
@@ -22,6 +22,13 @@ in
       description = "Poll interval in seconds for checking modem events";
     };
 
+    pollJitter = mkOption {
+      type = types.int;
+      default = 0;
+      example = 10;
+      description = "Shift each poll by a random amount of up to this many seconds either way";
+    };
+
     roleId = mkOption {
       type = types.nullOr types.int;
       default = null;
@@ -29,6 +36,15 @@ in
       description = "Discord role ID to ping when events occur (optional)";
     };
 
+    userId = mkOption {
+      type = types.nullOr types.int;
+      default = null;
+      example = 123456789012345678;
+      description = "Discord user ID to ping when events occur, alongside the role if both are set (optional)";
+    };
+
+    pingCriticalOnly = mkEnableOption "pinging the role and user only for critical events and alerts";
+
     downstreamSnrMin = mkOption {
       type = types.float;
       default = 33.0;
@@ -59,11 +75,406 @@ in
       description = "Maximum acceptable upstream signal strength in dBmV";
     };
 
+    upstreamSnrMin = mkOption {
+      type = types.float;
+      default = 30.0;
+      description = "Minimum acceptable upstream SNR (MER) in dB, on firmware that reports one";
+    };
+
+    ofdmSnrMin = mkOption {
+      type = types.float;
+      default = 34.0;
+      description = "Minimum acceptable SNR (MER) in dB on DOCSIS 3.1 OFDM downstream receivers";
+    };
+
+    ofdmPowerMin = mkOption {
+      type = types.float;
+      default = -15.0;
+      description = "Minimum acceptable OFDM downstream PLC power in dBmV";
+    };
+
+    ofdmPowerMax = mkOption {
+      type = types.float;
+      default = 15.0;
+      description = "Maximum acceptable OFDM downstream PLC power in dBmV";
+    };
+
+    ofdmaPowerMin = mkOption {
+      type = types.float;
+      default = 35.0;
+      description = "Minimum acceptable OFDMA upstream transmit power in dBmV";
+    };
+
+    ofdmaPowerMax = mkOption {
+      type = types.float;
+      default = 51.0;
+      description = "Maximum acceptable OFDMA upstream transmit power in dBmV";
+    };
+
     errorRateThreshold = mkOption {
       type = types.float;
       default = 0.01;
       description = "Alert if error rate (uncorrected/(corrected+uncorrected)) exceeds this threshold (0.01 = 1%)";
     };
+
+    correctedRateThreshold = mkOption {
+      type = types.nullOr types.float;
+      default = null;
+      example = 1000.0;
+      description = "Alert if a downstream channel gains corrected codewords faster than this many per minute, however few are uncorrectable (optional)";
+    };
+
+    absoluteErrorRateThreshold = mkOption {
+      type = types.nullOr types.float;
+      default = null;
+      example = 0.05;
+      description = "Alert if a downstream channel's lifetime error counters give an error rate above this threshold, even on the first poll (optional)";
+    };
+
+    urgentPatterns = mkOption {
+      type = types.listOf types.str;
+      default = [ ];
+      example = [ "T3" "SYNC Timing" ];
+      description = "Regexes matched against event text that force a ping and critical embed regardless of priority";
+    };
+
+    mutePatterns = mkOption {
+      type = types.listOf types.str;
+      default = [ ];
+      example = [ "DHCP RENEW WARNING" ];
+      description = "Regexes matched against event text; matching events are never sent";
+    };
+
+    onlyPatterns = mkOption {
+      type = types.listOf types.str;
+      default = [ ];
+      description = "Regexes matched against event text; when set, only matching events are sent";
+    };
+
+    rebootPatterns = mkOption {
+      type = types.listOf types.str;
+      default = [ "System startup" "Honoring MDD" ];
+      description = "Regexes matched against new event text that mark a modem reboot";
+    };
+
+    connectRetries = mkOption {
+      type = types.int;
+      default = 1;
+      description = "Immediate retries when connecting to the modem fails, before the poll is counted as failed";
+    };
+
+    httpTimeout = mkOption {
+      type = types.ints.positive;
+      default = 5;
+      description = "Seconds to wait for the modem to answer a request";
+    };
+
+    eventLogTimeout = mkOption {
+      type = types.nullOr types.ints.positive;
+      default = null;
+      example = 15;
+      description = "Seconds to wait for the event log in place of httpTimeout, for firmware that is slow to render it";
+    };
+
+    maxRetries = mkOption {
+      type = types.int;
+      default = 0;
+      example = 3;
+      description = "Retries, with exponential backoff, for requests that time out or lose their connection";
+    };
+
+    collapseAnomalies = mkOption {
+      type = types.bool;
+      default = false;
+      description = "Collapse anomalies of the same kind across channels into a single alert";
+    };
+
+    embedIdentity = mkOption {
+      type = types.bool;
+      default = false;
+      description = "Show the modem model, MAC and serial number in Discord embed footers";
+    };
+
+    embedFooter = mkOption {
+      type = types.nullOr types.str;
+      default = null;
+      example = "Home · https://grafana.example.com/d/modem";
+      description = "Text shown in the footer of every notification, e.g. a site name or dashboard link";
+    };
+
+    embedTitlePrefix = mkOption {
+      type = types.nullOr types.str;
+      default = null;
+      example = "🏠 Home";
+      description = "Text put in front of every notification title";
+    };
+
+    signalScale = mkOption {
+      type = types.float;
+      default = 1.0;
+      description = "Multiplier applied to reported signal strength and SNR, for firmware reporting in other units (e.g. 0.1 for tenths of a dB)";
+    };
+
+    signalOffset = mkOption {
+      type = types.float;
+      default = 0.0;
+      description = "Offset in dB added to reported signal strength after scaling";
+    };
+
+    minPriority = mkOption {
+      type = types.enum [ "critical" "warning" "notice" ];
+      default = "warning";
+      description = "Least severe event priority that is sent as a notification; events matching urgentPatterns are always sent";
+    };
+
+    priorityMap = mkOption {
+      type = types.attrsOf (types.enum [ "critical" "warning" "notice" ]);
+      default = { };
+      example = { emergency = "critical"; };
+      description = "Map non-standard event priority strings reported by the firmware to a known priority";
+    };
+
+    probeOnStart = mkOption {
+      type = types.bool;
+      default = false;
+      description = "Hit every modem endpoint once at startup and log whether it parsed";
+    };
+
+    snrMarginDropRate = mkOption {
+      type = types.nullOr types.float;
+      default = null;
+      example = 0.5;
+      description = "Alert when a downstream channel SNR margin (SNR above downstreamSnrMin) shrinks faster than this many dB per minute";
+    };
+
+    degradationRate = mkOption {
+      type = types.nullOr types.float;
+      default = null;
+      example = 0.2;
+      description = "Alert when a downstream channel's SNR or signal has been falling faster than this many dB per minute over the last degradationWindow polls";
+    };
+
+    degradationWindow = mkOption {
+      type = types.ints.between 2 1000;
+      default = 5;
+      description = "Polls the degradationRate slope is fitted over";
+    };
+
+    snrSmoothing = mkOption {
+      type = types.nullOr types.float;
+      default = null;
+      example = 0.3;
+      description = "Check an exponentially smoothed downstream SNR, weighting each new reading by this factor (0 to 1), so brief dips don't alert";
+    };
+
+    stalledUiPolls = mkOption {
+      type = types.nullOr types.ints.positive;
+      default = null;
+      example = 5;
+      description = "Alert when neither the event log nor any downstream reading has changed for this many polls in a row, a sign the modem's web server has hung";
+    };
+
+    stalledTrafficPolls = mkOption {
+      type = types.nullOr types.ints.positive;
+      default = null;
+      example = 3;
+      description = "Alert when the WAN octet counters show no traffic either way for this many polls in a row while the modem has network access";
+    };
+
+    expectedDownstreamChannels = mkOption {
+      type = types.nullOr types.int;
+      default = null;
+      example = 32;
+      description = "Alert when fewer downstream channels are locked; by default the count of the first healthy poll";
+    };
+
+    onlyChannels = mkOption {
+      type = types.nullOr (types.listOf types.ints.unsigned);
+      default = null;
+      example = [ 1 2 3 4 ];
+      description = "Only check these downstream and upstream channel IDs for anomalies; the rest are still exported";
+    };
+
+    ignoreChannels = mkOption {
+      type = types.listOf types.ints.unsigned;
+      default = [ ];
+      example = [ 33 ];
+      description = "Never check these downstream or upstream channel IDs for anomalies; they are still exported";
+    };
+
+    expectedUpstreamChannels = mkOption {
+      type = types.nullOr types.int;
+      default = null;
+      example = 4;
+      description = "Alert when fewer upstream channels are locked; by default the count of the first healthy poll";
+    };
+
+    alertLog = mkEnableOption "a human-readable alert log in /var/log/hitron-monitor/alerts.log";
+
+    alertLogMaxSize = mkOption {
+      type = types.nullOr types.int;
+      default = null;
+      example = 10485760;
+      description = "Rotate the alert log to alerts.log.1 once it would exceed this many bytes";
+    };
+
+    jsonLog = mkEnableOption "a JSON-lines log of every event sent and anomaly detected in /var/log/hitron-monitor/alerts.jsonl";
+
+    statusAddr = mkOption {
+      type = types.nullOr types.str;
+      default = null;
+      example = "127.0.0.1:9090";
+      description = "Address to serve the JSON status endpoint (/status) on";
+    };
+
+    dashboard = mkOption {
+      type = types.bool;
+      default = false;
+      description = "Also serve an HTML dashboard at / on statusAddr";
+    };
+
+    metricsAddr = mkOption {
+      type = types.nullOr types.str;
+      default = null;
+      example = "127.0.0.1:9091";
+      description = "Address to serve Prometheus metrics (/metrics) on";
+    };
+
+    healthAddr = mkOption {
+      type = types.nullOr types.str;
+      default = null;
+      example = "127.0.0.1:9092";
+      description = "Address to serve a health check (/healthz) on, failing once a poll hasn't succeeded within two intervals";
+    };
+
+    downstreamWeights = mkOption {
+      type = types.attrsOf types.float;
+      default = { };
+      example = { "33" = 4.0; };
+      description = "Health score weight per downstream channel ID";
+    };
+
+    upstreamWeights = mkOption {
+      type = types.attrsOf types.float;
+      default = { };
+      description = "Health score weight per upstream channel ID";
+    };
+
+    modulationWeights = mkOption {
+      type = types.attrsOf types.float;
+      default = { };
+      example = { OFDM = 4.0; };
+      description = "Health score weight per modulation; per-channel weights take precedence";
+    };
+
+    timeFormat = mkOption {
+      type = types.nullOr types.str;
+      default = null;
+      example = "%d.%m.%Y %H:%M";
+      description = "strftime-style format of event log timestamps, tried before the known firmware formats";
+    };
+
+    channelListKey = mkOption {
+      type = types.nullOr types.str;
+      default = null;
+      example = "chanInfo";
+      description = "Key the firmware wraps channel lists in, for modems that do not return a bare JSON array";
+    };
+
+    spectrumExport = mkEnableOption "a per-poll JSON lines spectrum export in the state directory (spectrum.jsonl)";
+
+    csvExport = mkEnableOption "a per-poll CSV export of channel metrics in the state directory (channels.csv)";
+
+    exportEvery = mkOption {
+      type = types.ints.positive;
+      default = 1;
+      description = "Only write every Nth poll to the spectrum and CSV exports; polls with anomalies are always written";
+    };
+
+    exportDelta = mkOption {
+      type = types.nullOr types.float;
+      default = null;
+      description = "Also write a poll to the spectrum and CSV exports when a channel's signal or SNR moved by more than this many dB";
+    };
+
+    escalateAfter = mkOption {
+      type = types.nullOr types.ints.positive;
+      default = null;
+      example = 3;
+      description = "Escalate a channel alert to critical and start pinging once the same anomaly persists for more than this many consecutive polls. When null, every channel alert pings.";
+    };
+
+    channelId = mkOption {
+      type = types.nullOr types.str;
+      default = null;
+      example = "123456789012345678";
+      description = "Discord channel ID to post to as a bot, using DISCORD_BOT_TOKEN from webhookFile instead of a webhook";
+    };
+
+    threadId = mkOption {
+      type = types.nullOr types.str;
+      default = null;
+      example = "123456789012345678";
+      description = "Discord thread ID for the webhook to post into, instead of the webhook's channel";
+    };
+
+    modemHost = mkOption {
+      type = with types; coercedTo str singleton (listOf str);
+      default = [ "https://192.168.100.1" ];
+      example = [ "primary=192.168.100.1" "failover=192.168.0.1" ];
+      description = "Modem host name, IP address or URL, optionally labelled as LABEL=HOST. Give a list to monitor several modems from one service.";
+    };
+
+    confirmPolls = mkOption {
+      type = types.ints.positive;
+      default = 1;
+      description = "Consecutive polls an anomaly must hold for before it is reported";
+    };
+
+    digestHour = mkOption {
+      type = types.nullOr (types.ints.between 0 23);
+      default = null;
+      example = 8;
+      description = "Local hour at which to post a daily summary of readings and anomalies";
+    };
+
+    channelOverrides = mkOption {
+      type = types.attrsOf (types.attrsOf types.float);
+      default = { };
+      example = { "1" = { signal_max = 18.0; }; };
+      description = "Per-channel downstream limits (snr_min, signal_min, signal_max) that replace the global ones, keyed by channel ID";
+    };
+
+    insecure = mkOption {
+      type = types.bool;
+      default = true;
+      description = "Accept any TLS certificate from the modem, as its own is self-signed. Ignored when caCert is set.";
+    };
+
+    caCert = mkOption {
+      type = types.nullOr types.path;
+      default = null;
+      description = "Certificate (PEM or DER) to verify the modem against, e.g. its own self-signed one";
+    };
+
+    startupGrace = mkOption {
+      type = types.ints.unsigned;
+      default = 0;
+      example = 300;
+      description = "Seconds after startup during which new events are logged but not sent (0 disables)";
+    };
+
+    alertCooldown = mkOption {
+      type = types.ints.unsigned;
+      default = 900;
+      description = "Minimum seconds between repeated alerts for the same anomaly on the same channel (0 disables)";
+    };
+
+    logLevel = mkOption {
+      type = types.enum [ "error" "warn" "info" "debug" "trace" ];
+      default = "info";
+      description = "Most verbose log level written to the journal; debug includes per-endpoint timings";
+    };
   };
 
   config = mkIf cfg.enable {
@@ -81,19 +492,85 @@ in
         ExecStart =
           let
             args = [ "--interval=${toString cfg.pollInterval}" ]
+              ++ [ "--interval-jitter=${toString cfg.pollJitter}" ]
               ++ (optionals (cfg.roleId != null) [ "--role=${toString cfg.roleId}" ])
+              ++ (optionals (cfg.userId != null) [ "--user=${toString cfg.userId}" ])
+              ++ (optionals cfg.pingCriticalOnly [ "--ping-critical-only" ])
               ++ [ "--state-file=%S/hitron-monitor/last-index" ]
               ++ [ "--downstream-snr-min=${toString cfg.downstreamSnrMin}" ]
               ++ [ "--downstream-signal-min=${toString cfg.downstreamSignalMin}" ]
               ++ [ "--downstream-signal-max=${toString cfg.downstreamSignalMax}" ]
               ++ [ "--upstream-signal-min=${toString cfg.upstreamSignalMin}" ]
               ++ [ "--upstream-signal-max=${toString cfg.upstreamSignalMax}" ]
-              ++ [ "--error-rate-threshold=${toString cfg.errorRateThreshold}" ];
+              ++ [ "--upstream-snr-min=${toString cfg.upstreamSnrMin}" ]
+              ++ [ "--ofdm-snr-min=${toString cfg.ofdmSnrMin}" ]
+              ++ [ "--ofdm-power-min=${toString cfg.ofdmPowerMin}" ]
+              ++ [ "--ofdm-power-max=${toString cfg.ofdmPowerMax}" ]
+              ++ [ "--ofdma-power-min=${toString cfg.ofdmaPowerMin}" ]
+              ++ [ "--ofdma-power-max=${toString cfg.ofdmaPowerMax}" ]
+              ++ [ "--error-rate-threshold=${toString cfg.errorRateThreshold}" ]
+              ++ (optionals (cfg.correctedRateThreshold != null) [ "--corrected-rate-threshold=${toString cfg.correctedRateThreshold}" ])
+              ++ (optionals (cfg.absoluteErrorRateThreshold != null) [ "--absolute-error-rate-threshold=${toString cfg.absoluteErrorRateThreshold}" ])
+              ++ (map (p: escapeShellArg "--urgent-pattern=${p}") cfg.urgentPatterns)
+              ++ (map (p: escapeShellArg "--mute-pattern=${p}") cfg.mutePatterns)
+              ++ (map (p: escapeShellArg "--only-pattern=${p}") cfg.onlyPatterns)
+              ++ (map (p: escapeShellArg "--reboot-pattern=${p}") cfg.rebootPatterns)
+              ++ [ "--connect-retries=${toString cfg.connectRetries}" ]
+              ++ [ "--http-timeout=${toString cfg.httpTimeout}" ]
+              ++ (optionals (cfg.eventLogTimeout != null) [ "--event-log-timeout=${toString cfg.eventLogTimeout}" ])
+              ++ [ "--max-retries=${toString cfg.maxRetries}" ]
+              ++ (optionals cfg.collapseAnomalies [ "--collapse-anomalies" ])
+              ++ (optionals cfg.embedIdentity [ "--embed-identity" ])
+              ++ (optionals (cfg.embedFooter != null) [ (escapeShellArg "--embed-footer=${cfg.embedFooter}") ])
+              ++ (optionals (cfg.embedTitlePrefix != null) [ (escapeShellArg "--embed-title-prefix=${cfg.embedTitlePrefix}") ])
+              ++ [ "--signal-scale=${toString cfg.signalScale}" ]
+              ++ [ "--signal-offset=${toString cfg.signalOffset}" ]
+              ++ [ "--min-priority=${cfg.minPriority}" ]
+              ++ (mapAttrsToList (raw: priority: escapeShellArg "--priority-map=${raw}=${priority}") cfg.priorityMap)
+              ++ (optionals cfg.probeOnStart [ "--probe-on-start" ])
+              ++ (optionals (cfg.snrMarginDropRate != null) [ "--snr-margin-drop-rate=${toString cfg.snrMarginDropRate}" ])
+              ++ (optionals (cfg.degradationRate != null) [ "--degradation-rate=${toString cfg.degradationRate}" "--degradation-window=${toString cfg.degradationWindow}" ])
+              ++ (optionals (cfg.snrSmoothing != null) [ "--snr-smoothing=${toString cfg.snrSmoothing}" ])
+              ++ (optionals (cfg.stalledUiPolls != null) [ "--stalled-ui-polls=${toString cfg.stalledUiPolls}" ])
+              ++ (optionals (cfg.stalledTrafficPolls != null) [ "--stalled-traffic-polls=${toString cfg.stalledTrafficPolls}" ])
+              ++ (optionals (cfg.expectedDownstreamChannels != null) [ "--expected-downstream-channels=${toString cfg.expectedDownstreamChannels}" ])
+              ++ (optionals (cfg.expectedUpstreamChannels != null) [ "--expected-upstream-channels=${toString cfg.expectedUpstreamChannels}" ])
+              ++ (optionals (cfg.onlyChannels != null) [ "--only-channels=${concatMapStringsSep "," toString cfg.onlyChannels}" ])
+              ++ (optionals (cfg.ignoreChannels != [ ]) [ "--ignore-channels=${concatMapStringsSep "," toString cfg.ignoreChannels}" ])
+              ++ (optionals cfg.alertLog [ "--alert-log=%L/hitron-monitor/alerts.log" ])
+              ++ (optionals (cfg.alertLog && cfg.alertLogMaxSize != null) [ "--alert-log-max-size=${toString cfg.alertLogMaxSize}" ])
+              ++ (optionals cfg.jsonLog [ "--log-json=%L/hitron-monitor/alerts.jsonl" ])
+              ++ (optionals (cfg.statusAddr != null) [ "--status-addr=${cfg.statusAddr}" ])
+              ++ (optionals (cfg.statusAddr != null && cfg.dashboard) [ "--dashboard" ])
+              ++ (optionals (cfg.metricsAddr != null) [ "--metrics-addr=${cfg.metricsAddr}" ])
+              ++ (optionals (cfg.healthAddr != null) [ "--health-addr=${cfg.healthAddr}" ])
+              ++ (mapAttrsToList (id: w: "--downstream-weight=${id}=${toString w}") cfg.downstreamWeights)
+              ++ (mapAttrsToList (id: w: "--upstream-weight=${id}=${toString w}") cfg.upstreamWeights)
+              ++ (mapAttrsToList (m: w: escapeShellArg "--modulation-weight=${m}=${toString w}") cfg.modulationWeights)
+              ++ (optionals (cfg.channelListKey != null) [ (escapeShellArg "--channel-list-key=${cfg.channelListKey}") ])
+              ++ (optionals (cfg.timeFormat != null) [ (escapeShellArg "--time-format=${replaceStrings [ "%" ] [ "%%" ] cfg.timeFormat}") ])
+              ++ (optionals cfg.spectrumExport [ "--spectrum-export=%S/hitron-monitor/spectrum.jsonl" ])
+              ++ (optionals cfg.csvExport [ "--csv=%S/hitron-monitor/channels.csv" ])
+              ++ (optionals (cfg.spectrumExport || cfg.csvExport) [ "--export-every=${toString cfg.exportEvery}" ])
+              ++ (optionals ((cfg.spectrumExport || cfg.csvExport) && cfg.exportDelta != null) [ "--export-delta=${toString cfg.exportDelta}" ])
+              ++ (optionals (cfg.escalateAfter != null) [ "--escalate-after=${toString cfg.escalateAfter}" ])
+              ++ (optionals (cfg.channelId != null) [ "--channel-id=${cfg.channelId}" ])
+              ++ (optionals (cfg.threadId != null) [ "--thread-id=${cfg.threadId}" ])
+              ++ (map (host: escapeShellArg "--modem-host=${host}") cfg.modemHost)
+              ++ [ "--confirm-polls=${toString cfg.confirmPolls}" ]
+              ++ (optionals (cfg.digestHour != null) [ "--digest-hour=${toString cfg.digestHour}" ])
+              ++ (optionals (cfg.channelOverrides != { }) [ "--channel-overrides=${(pkgs.formats.toml { }).generate "channel-overrides.toml" { downstream = cfg.channelOverrides; }}" ])
+              ++ (optionals (!cfg.insecure) [ "--no-insecure" ])
+              ++ (optionals (cfg.caCert != null) [ "--ca-cert=${cfg.caCert}" ])
+              ++ [ "--alert-cooldown=${toString cfg.alertCooldown}" ]
+              ++ [ "--startup-grace=${toString cfg.startupGrace}" ]
+              ++ [ "--log-level=${cfg.logLevel}" ];
             argString = concatStringsSep " " args;
           in
           "${hitron-monitor}/bin/hitron-monitor ${argString}";
         DynamicUser = true;
         StateDirectory = "hitron-monitor";
+        LogsDirectory = "hitron-monitor";
 
         # Security hardening
         NoNewPrivileges = true;
@@ -0,0 +1,159 @@
+use crate::api::EventLog;
+use crate::monitor::ChannelAnomaly;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// Append-only, human-readable log of every alert, kept locally as an
+/// audit trail independent of Discord. Clones share a lock, so two modems
+/// never rotate the file out from under each other.
+#[derive(Clone)]
+pub struct AlertLog {
+    path: PathBuf,
+    max_size: Option<u64>,
+    /// Which modem the alerts are about, when monitoring several
+    label: Option<String>,
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl AlertLog {
+    /// Log to `path`, rotating it to `<path>.1` once it would grow past
+    /// `max_size` bytes
+    pub fn new(path: PathBuf, max_size: Option<u64>) -> Self {
+        Self {
+            path,
+            max_size,
+            label: None,
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Tag every line with the modem it concerns
+    pub fn set_label(&mut self, label: String) {
+        self.label = Some(label);
+    }
+
+    /// Record a forwarded modem event
+    pub async fn append_event(&self, event: &EventLog) -> Result<()> {
+        self.append(&format!(
+            "EVENT [{}] {} {} - {}",
+            event.priority, event.time, event.event_type, event.event
+        ))
+        .await
+    }
+
+    /// Record a channel anomaly, flattened onto one line
+    pub async fn append_anomaly(&self, anomaly: &ChannelAnomaly) -> Result<()> {
+        self.append(&format!("ANOMALY {}", flatten(anomaly))).await
+    }
+
+    /// Record a channel condition clearing
+    pub async fn append_recovery(&self, anomaly: &ChannelAnomaly) -> Result<()> {
+        self.append(&format!("RECOVERED {}", flatten(anomaly))).await
+    }
+
+    async fn append(&self, message: &str) -> Result<()> {
+        let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+        let line = match &self.label {
+            Some(label) => format!("{} [{}] {}\n", timestamp, label, message),
+            None => format!("{} {}\n", timestamp, message),
+        };
+
+        let _guard = self.write_lock.lock().await;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        if let Some(max_size) = self.max_size {
+            self.rotate_if_needed(max_size, line.len() as u64).await?;
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path).await?;
+        file.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+
+    /// Move the log aside if appending `incoming` bytes would exceed the limit
+    async fn rotate_if_needed(&self, max_size: u64, incoming: u64) -> Result<()> {
+        let size = match fs::metadata(&self.path).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => return Ok(()),
+        };
+        if size > 0 && size + incoming > max_size {
+            fs::rename(&self.path, rotated_path(&self.path)).await?;
+        }
+        Ok(())
+    }
+}
+
+/// An anomaly's description on one line
+fn flatten(anomaly: &ChannelAnomaly) -> String {
+    let message = anomaly.to_string();
+    let lines: Vec<&str> = message.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    lines.join("; ")
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn full_log_is_rotated_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alerts.log");
+        let log = AlertLog::new(path.clone(), Some(200));
+        let anomaly = ChannelAnomaly::DownstreamLowSNR { channel_id: 1, snr: 30.0, threshold: 33.0 };
+
+        log.append_anomaly(&anomaly).await.unwrap();
+        log.append_anomaly(&anomaly).await.unwrap();
+        assert!(!rotated_path(&path).exists());
+        log.append_recovery(&anomaly).await.unwrap();
+
+        let rotated = fs::read_to_string(rotated_path(&path)).await.unwrap();
+        let current = fs::read_to_string(&path).await.unwrap();
+        assert_eq!(rotated.lines().count(), 2);
+        assert!(rotated.lines().all(|l| l.contains("ANOMALY")));
+        assert_eq!(current.lines().count(), 1);
+        assert!(current.contains("RECOVERED"));
+    }
+
+    #[tokio::test]
+    async fn concurrent_modems_keep_the_rotated_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alerts.log");
+        let log = AlertLog::new(path.clone(), Some(1024));
+        let anomaly = ChannelAnomaly::DownstreamLowSNR { channel_id: 1, snr: 30.0, threshold: 33.0 };
+
+        let tasks: Vec<_> = (0..8)
+            .map(|modem| {
+                let mut log = log.clone();
+                log.set_label(format!("modem{}", modem));
+                let anomaly = anomaly.clone();
+                tokio::spawn(async move {
+                    for _ in 0..10 {
+                        log.append_anomaly(&anomaly).await.unwrap();
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        // Every rotation happened under the lock, so neither file went past
+        // the limit and the newest lines were never lost
+        let rotated = fs::read_to_string(rotated_path(&path)).await.unwrap();
+        let current = fs::read_to_string(&path).await.unwrap();
+        assert!(rotated.len() <= 1024 && current.len() <= 1024);
+        assert!(!current.is_empty());
+        assert!(current.lines().chain(rotated.lines()).all(|l| l.contains("ANOMALY")));
+    }
+}
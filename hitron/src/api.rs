@@ -1,18 +1,560 @@
-use anyhow::Result;
-use reqwest::Client;
-use serde::{Deserialize, Deserializer};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::Datelike;
+use rand::Rng;
+use reqwest::{Client, Response};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::error::Error as _;
 use std::fmt;
-use tracing::debug;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{debug, field, info, info_span, warn, Instrument, Span};
 
-const BASE_URL: &str = "https://192.168.100.1/data";
+/// Modem address used when none is configured
+pub const DEFAULT_MODEM_HOST: &str = "https://192.168.100.1";
 
-/// Create a reqwest client that accepts self-signed certificates
-pub fn create_client() -> Result<Client> {
-    let client = Client::builder()
-        .danger_accept_invalid_certs(true)
-        .timeout(std::time::Duration::from_secs(5))
+/// Immediate retries for requests whose TCP connect fails, so a single
+/// dropped connection attempt doesn't lose the whole poll
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectRetry {
+    pub attempts: u32,
+    pub delay: Duration,
+}
+
+/// Retries with exponential backoff for requests that fail transiently
+/// (timeouts, dropped connections), after any connect retries are used up
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// Delay before the first retry; it doubles for each one after
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `attempt` (from zero), randomized by up to
+    /// half either way so clients that failed together don't retry together
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self.base_delay.saturating_mul(2u32.saturating_pow(attempt));
+        delay.mul_f64(rand::thread_rng().gen_range(0.5..1.5))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            base_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Errors talking to the modem, classified so callers can tell a broken
+/// resolver from a modem that is down
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("could not resolve modem host '{host}' (check the DNS resolver): {source}")]
+    Dns {
+        host: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("response from {url} was truncated after {received} bytes")]
+    Truncated { url: String, received: usize },
+    #[error("{url} returned a web page instead of JSON; the modem may be rebooting or asking for a login")]
+    NotJson { url: String },
+    #[error("login to the modem failed: {0}")]
+    Login(String),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+}
+
+impl ApiError {
+    /// Whether the failure is likely to clear on its own, so retrying the
+    /// request (or simply waiting for the next poll) is the right response
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ApiError::Dns { .. } => false,
+            ApiError::Truncated { .. } => true,
+            ApiError::NotJson { .. } => true,
+            ApiError::Login(_) => false,
+            ApiError::Http(e) => e.is_connect() || e.is_timeout() || e.is_body(),
+        }
+    }
+
+    /// Classify a request error, picking out DNS resolution failures
+    fn from_request(err: reqwest::Error) -> Self {
+        if !is_dns_error(&err) {
+            return ApiError::Http(err);
+        }
+        let host = err
+            .url()
+            .and_then(|url| url.host_str())
+            .unwrap_or("<unknown>")
+            .to_string();
+        ApiError::Dns { host, source: err }
+    }
+}
+
+/// Whether a failed fetch is worth retrying
+fn is_transient(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<ApiError>().is_some_and(ApiError::is_transient)
+}
+
+/// Whether a request failed while resolving the host name. reqwest exposes
+/// no dedicated kind for this, so look for the resolver's error in the chain.
+fn is_dns_error(err: &reqwest::Error) -> bool {
+    let mut source = err.source();
+    while let Some(e) = source {
+        let msg = e.to_string();
+        if msg.contains("dns error") || msg.contains("failed to lookup address") {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
+
+/// Normalization for firmware that reports signal levels in units other
+/// than dB/dBmV (e.g. tenths of a dB). Both SNR and signal strength are
+/// multiplied by `factor`; `offset` is then added to signal strength.
+#[derive(Debug, Clone, Copy)]
+pub struct SignalScale {
+    pub factor: f64,
+    pub offset: f64,
+}
+
+impl SignalScale {
+    fn signal(&self, raw: f64) -> f64 {
+        raw * self.factor + self.offset
+    }
+
+    fn snr(&self, raw: f64) -> f64 {
+        raw * self.factor
+    }
+}
+
+/// Parse a JSON body, telling a body cut off mid-document (a flaky link)
+/// apart from JSON that is genuinely malformed (a firmware change)
+fn parse_json<T: serde::de::DeserializeOwned>(url: &str, text: &str) -> Result<T> {
+    // An HTML page, e.g. the login form or a "please wait" page while the
+    // modem reboots
+    if is_html(text) {
+        let start: String = text.chars().take(200).collect();
+        debug!("{} returned a non-JSON body starting: {}", url, start);
+        return Err(ApiError::NotJson { url: url.to_string() }.into());
+    }
+    serde_json::from_str(text).map_err(|e| match e.classify() {
+        serde_json::error::Category::Eof if !text.trim().is_empty() => ApiError::Truncated {
+            url: url.to_string(),
+            received: text.len(),
+        }
+        .into(),
+        _ => e.into(),
+    })
+}
+
+/// Whether a body is a web page rather than data
+fn is_html(text: &str) -> bool {
+    text.trim_start().starts_with('<')
+}
+
+/// Keys some firmware wraps channel lists in, e.g. `{"chanInfo":[...]}`
+const KNOWN_CHANNEL_LIST_KEYS: &[&str] = &["chanInfo"];
+
+/// Parse a channel list that is either a bare JSON array or an array
+/// wrapped in an object under `wrapper_key` or one of the known keys
+fn parse_channel_list<T: serde::de::DeserializeOwned>(
+    url: &str,
+    text: &str,
+    wrapper_key: Option<&str>,
+) -> Result<Vec<T>> {
+    let value: serde_json::Value = parse_json(url, text)?;
+    let list = match value {
+        serde_json::Value::Array(_) => value,
+        serde_json::Value::Object(mut fields) => {
+            let found = wrapper_key
+                .into_iter()
+                .chain(KNOWN_CHANNEL_LIST_KEYS.iter().copied())
+                .find_map(|key| fields.remove(key));
+            match found {
+                Some(list) => list,
+                None => anyhow::bail!(
+                    "{} returned an object without a known channel list key (keys: {})",
+                    url,
+                    fields.keys().cloned().collect::<Vec<_>>().join(", ")
+                ),
+            }
+        }
+        other => anyhow::bail!("{} returned unexpected JSON instead of a channel list: {}", url, other),
+    };
+    Ok(serde_json::from_value(list)?)
+}
+
+/// Where the data endpoints' bodies come from: the modem itself, or
+/// responses recorded from it to be replayed
+#[async_trait]
+pub trait Source: Send + Sync {
+    /// Where an endpoint, e.g. "dsinfo.asp", is read from, for messages
+    fn location(&self, endpoint: &str) -> String;
+
+    /// Read an endpoint's body
+    async fn get(&self, endpoint: &str) -> Result<String>;
+
+    /// Move on to the next poll's data, returning false once there is none
+    /// left. A live modem always has more.
+    fn next_poll(&self) -> bool {
+        true
+    }
+}
+
+/// Client for the modem's data endpoints, parsing what its source returns
+pub struct ApiClient {
+    source: Box<dyn Source>,
+    retry: RetryPolicy,
+    signal_scale: SignalScale,
+    priority_map: HashMap<String, EventPriority>,
+    channel_list_key: Option<String>,
+    time_formats: TimeFormats,
+}
+
+/// The event log endpoint, which some firmware is much slower to serve
+/// than the channel endpoints
+const EVENT_LOG_ENDPOINT: &str = "status_log.asp";
+
+impl ApiClient {
+    /// A client reading the data endpoints from `source`
+    pub fn new(source: Box<dyn Source>, signal_scale: SignalScale) -> Self {
+        Self {
+            source,
+            retry: RetryPolicy::default(),
+            signal_scale,
+            priority_map: HashMap::new(),
+            channel_list_key: None,
+            time_formats: TimeFormats::default(),
+        }
+    }
+
+    /// Reclassify events whose priority string (compared case-insensitively)
+    /// is not one of the standard ones
+    pub fn set_priority_map(&mut self, priority_map: HashMap<String, EventPriority>) {
+        self.priority_map = priority_map;
+    }
+
+    /// Look for channel lists wrapped in an object under this key, for
+    /// firmware that doesn't return a bare array
+    pub fn set_channel_list_key(&mut self, key: Option<String>) {
+        self.channel_list_key = key;
+    }
+
+    /// Try this strftime-style format first when parsing event timestamps,
+    /// before the known firmware formats
+    pub fn set_time_format(&mut self, format: Option<String>) {
+        self.time_formats = TimeFormats::new(format);
+    }
+
+    /// Retry requests that fail transiently according to this policy
+    pub fn set_retry(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    /// Move on to the next poll's data, returning false once a replay has
+    /// run out of recorded polls
+    pub fn next_poll(&self) -> bool {
+        self.source.next_poll()
+    }
+
+    /// Where a data endpoint, e.g. "dsinfo.asp", is read from
+    fn url(&self, endpoint: &str) -> String {
+        self.source.location(endpoint)
+    }
+
+    /// Read a data endpoint and parse its body, given with where it was read
+    /// from, in a span recording how long it took, retries included, and how
+    /// big the body was
+    async fn fetch<T>(&self, endpoint: &str, parse: impl Fn(&str, &str) -> Result<T>) -> Result<T> {
+        let span = info_span!("fetch", endpoint, elapsed_ms = field::Empty, bytes = field::Empty);
+        let started = Instant::now();
+        let result = self.fetch_retrying(endpoint, &parse).instrument(span.clone()).await;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        span.record("elapsed_ms", elapsed_ms);
+        span.in_scope(|| debug!("Fetching {} took {} ms", endpoint, elapsed_ms));
+        result
+    }
+
+    /// Retry transient failures with backoff. A body cut off mid-document is
+    /// retried like a failed request; one that arrives whole but doesn't
+    /// parse is a firmware problem another attempt won't fix.
+    async fn fetch_retrying<T>(&self, endpoint: &str, parse: &impl Fn(&str, &str) -> Result<T>) -> Result<T> {
+        let mut attempt = 0;
+        loop {
+            match self.fetch_once(endpoint, parse).await {
+                Err(e) if is_transient(&e) && attempt < self.retry.max_retries => {
+                    let delay = self.retry.backoff(attempt);
+                    attempt += 1;
+                    warn!(
+                        "Request to {} failed ({}), retrying in {:.1}s ({}/{})",
+                        self.url(endpoint),
+                        e,
+                        delay.as_secs_f64(),
+                        attempt,
+                        self.retry.max_retries
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn fetch_once<T>(&self, endpoint: &str, parse: &impl Fn(&str, &str) -> Result<T>) -> Result<T> {
+        let url = self.url(endpoint);
+        let body = self.source.get(endpoint).await?;
+        debug!("Received {} bytes from {}", body.len(), url);
+        Span::current().record("bytes", body.len());
+        parse(&url, &body)
+    }
+}
+
+/// The modem's data endpoints, over HTTP
+pub struct HttpSource {
+    client: Client,
+    /// Base of the data endpoints, e.g. "https://192.168.100.1/data"
+    base_url: String,
+    /// Credentials for firmware that only serves data after a login
+    credentials: Option<Credentials>,
+    connect_retry: ConnectRetry,
+    /// Timeout for event log requests in place of the client's own, for
+    /// firmware that is slow to render the log
+    event_log_timeout: Option<Duration>,
+}
+
+impl HttpSource {
+    /// Give event log requests their own timeout instead of the client's
+    pub fn set_event_log_timeout(&mut self, timeout: Option<Duration>) {
+        self.event_log_timeout = timeout;
+    }
+
+    /// Log in again with these credentials whenever a request is bounced
+    /// to the login page
+    pub fn set_credentials(&mut self, credentials: Credentials) {
+        self.credentials = Some(credentials);
+    }
+
+    /// Full URL of a data endpoint, e.g. "dsinfo.asp"
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}/{}", self.base_url, endpoint)
+    }
+
+    /// URL of the login form, beside the data endpoints
+    fn login_url(&self) -> String {
+        let root = self.base_url.strip_suffix("/data").unwrap_or(&self.base_url);
+        format!("{}/goform/login", root)
+    }
+
+    /// GET a data endpoint's body, logging in again and retrying once if
+    /// the session has expired
+    async fn get_once(&self, url: &str) -> Result<String, ApiError> {
+        let response = self.send_get(url).await?;
+        let Some(credentials) = &self.credentials else {
+            return read_body(response).await;
+        };
+        let (body, redirected) = read_login_redirect(url, response).await?;
+        if !redirected {
+            return Ok(body);
+        }
+
+        info!("Modem session expired, logging in again");
+        login(self, &credentials.username, &credentials.password).await?;
+        let response = self.send_get(url).await?;
+        let (body, redirected) = read_login_redirect(url, response).await?;
+        if redirected {
+            return Err(ApiError::Login(format!("still redirected to the login page for {}", url)));
+        }
+        Ok(body)
+    }
+
+    /// GET a URL, retrying only failures in the connect phase
+    async fn send_get(&self, url: &str) -> Result<Response, ApiError> {
+        let mut attempt = 0;
+        loop {
+            let mut request = self.client.get(url);
+            if let Some(timeout) = self.event_log_timeout.filter(|_| url.ends_with(EVENT_LOG_ENDPOINT)) {
+                request = request.timeout(timeout);
+            }
+            match request.send().await {
+                Err(e) if e.is_connect() && attempt < self.connect_retry.attempts => {
+                    attempt += 1;
+                    warn!(
+                        "Connect to {} failed ({}), retrying ({}/{})",
+                        url, e, attempt, self.connect_retry.attempts
+                    );
+                    tokio::time::sleep(self.connect_retry.delay).await;
+                }
+                result => return result.map_err(ApiError::from_request),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Source for HttpSource {
+    fn location(&self, endpoint: &str) -> String {
+        self.url(endpoint)
+    }
+
+    async fn get(&self, endpoint: &str) -> Result<String> {
+        Ok(self.get_once(&self.url(endpoint)).await?)
+    }
+}
+
+/// Username and password for the modem's web interface
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+async fn read_body(response: Response) -> Result<String, ApiError> {
+    let bytes = response.bytes().await?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Read a data request's body, and whether it was bounced to the login
+/// page: redirected elsewhere, or answered with a web page instead of JSON.
+/// The Content-Type can't tell, since firmware serves its JSON as text/html.
+async fn read_login_redirect(url: &str, response: Response) -> Result<(String, bool), ApiError> {
+    let redirected = response.url().as_str() != url;
+    let body = read_body(response).await?;
+    let redirected = redirected || is_html(&body);
+    Ok((body, redirected))
+}
+
+/// Log in to the modem's web interface. The session cookie it sets is kept
+/// in the client's cookie store, so later `get_*` calls are authenticated.
+pub async fn login(source: &HttpSource, username: &str, password: &str) -> Result<(), ApiError> {
+    let url = source.login_url();
+    debug!("Logging in to {}", url);
+
+    let response = source
+        .client
+        .post(&url)
+        .form(&[("usr", username), ("pwd", password), ("forcelogoff", "1")])
+        .send()
+        .await
+        .map_err(ApiError::from_request)?;
+
+    if !response.status().is_success() {
+        return Err(ApiError::Login(format!("{} returned {}", url, response.status())));
+    }
+    // A rejected login lands back on the login page
+    if response.url().path().contains("login.html") {
+        return Err(ApiError::Login("credentials were rejected".to_string()));
+    }
+
+    info!("Logged in to the modem as {}", username);
+    Ok(())
+}
+
+/// Base URL of the modem's data endpoints, from a host name, IP address
+/// or URL. Bare hosts are assumed to be served over HTTPS; older units
+/// that only serve plain HTTP need an explicit `http://`.
+pub fn modem_base_url(host: &str) -> Result<String> {
+    let with_scheme = if host.contains("://") {
+        host.to_string()
+    } else {
+        format!("https://{}", host)
+    };
+    let url = reqwest::Url::parse(&with_scheme).with_context(|| format!("Invalid modem host '{}'", host))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        bail!("Invalid modem host '{}': scheme must be http or https", host);
+    }
+    if url.host_str().is_none() {
+        bail!("Invalid modem host '{}': no host name", host);
+    }
+    Ok(format!("{}/data", url.as_str().trim_end_matches('/')))
+}
+
+/// How the modem's TLS certificate is checked
+#[derive(Debug, Clone)]
+pub enum TlsVerification {
+    /// Accept any certificate, since the modem's own is self-signed
+    AcceptInvalid,
+    /// Verify against the system's root certificates
+    System,
+    /// Verify against this certificate, e.g. the modem's own
+    Pinned(reqwest::Certificate),
+}
+
+/// Load a certificate to pin, PEM or DER encoded
+pub fn load_certificate(path: &std::path::Path) -> Result<reqwest::Certificate> {
+    let bytes = std::fs::read(path)?;
+    let cert = if bytes.starts_with(b"-----BEGIN") {
+        reqwest::Certificate::from_pem(&bytes)?
+    } else {
+        reqwest::Certificate::from_der(&bytes)?
+    };
+    Ok(cert)
+}
+
+/// Create an API client for the modem at `host`, checking its certificate
+/// as `tls` says and giving up on requests after `timeout`
+pub fn create_client(
+    host: &str,
+    timeout: Duration,
+    connect_retry: ConnectRetry,
+    signal_scale: SignalScale,
+    tls: &TlsVerification,
+) -> Result<ApiClient> {
+    let source = create_http_source(host, timeout, connect_retry, tls)?;
+    Ok(ApiClient::new(Box::new(source), signal_scale))
+}
+
+/// Create the HTTP source behind [`create_client`], for setting it up
+/// further, e.g. with credentials, before handing it to [`ApiClient::new`]
+pub fn create_http_source(
+    host: &str,
+    timeout: Duration,
+    connect_retry: ConnectRetry,
+    tls: &TlsVerification,
+) -> Result<HttpSource> {
+    let base_url = modem_base_url(host)?;
+    let builder = match tls {
+        // Nothing to verify without TLS
+        _ if base_url.starts_with("http://") => Client::builder(),
+        TlsVerification::AcceptInvalid => Client::builder().danger_accept_invalid_certs(true),
+        TlsVerification::System => Client::builder(),
+        TlsVerification::Pinned(cert) => Client::builder()
+            .tls_built_in_root_certs(false)
+            .add_root_certificate(cert.clone()),
+    };
+    let client = builder
+        .timeout(timeout)
+        .cookie_store(true)
         .build()?;
-    Ok(client)
+    Ok(HttpSource {
+        client,
+        base_url,
+        credentials: None,
+        connect_retry,
+        event_log_timeout: None,
+    })
+}
+
+/// Read a number the firmware sends as a (possibly space-padded) string.
+/// Plain JSON numbers are accepted too, as written by the channel state file.
+fn deserialize_number<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: fmt::Display,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => s.trim().parse().map_err(serde::de::Error::custom),
+        serde_json::Value::Number(n) => n.to_string().parse().map_err(serde::de::Error::custom),
+        other => Err(serde::de::Error::custom(format!("expected a number, found {}", other))),
+    }
 }
 
 /// Custom deserializer for converting string to f64
@@ -20,8 +562,7 @@ fn deserialize_string_to_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let s: String = Deserialize::deserialize(deserializer)?;
-    s.parse().map_err(serde::de::Error::custom)
+    deserialize_number(deserializer)
 }
 
 /// Custom deserializer for converting string to i64
@@ -29,8 +570,7 @@ fn deserialize_string_to_i64<'de, D>(deserializer: D) -> Result<i64, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let s: String = Deserialize::deserialize(deserializer)?;
-    s.parse().map_err(serde::de::Error::custom)
+    deserialize_number(deserializer)
 }
 
 /// Custom deserializer for converting string to u32
@@ -38,8 +578,190 @@ fn deserialize_string_to_u32<'de, D>(deserializer: D) -> Result<u32, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let s: String = Deserialize::deserialize(deserializer)?;
-    s.parse().map_err(serde::de::Error::custom)
+    deserialize_number(deserializer)
+}
+
+/// Custom deserializer for flags the firmware reports as a bool, "1"/"0",
+/// or "true"/"yes"
+fn deserialize_flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Bool(b) => Ok(b),
+        serde_json::Value::Number(n) => Ok(n.as_i64() == Some(1)),
+        serde_json::Value::String(s) => Ok(matches!(
+            s.trim().to_ascii_lowercase().as_str(),
+            "1" | "true" | "yes"
+        )),
+        _ => Ok(false),
+    }
+}
+
+/// Custom deserializer for free-text fields, which the firmware leaves
+/// blank when it has nothing to report
+fn deserialize_text<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let text = Option::<String>::deserialize(deserializer)?;
+    Ok(text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty()))
+}
+
+/// Custom deserializer for numbers the firmware may leave blank or omit
+fn deserialize_optional_number<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: fmt::Display,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Null => Ok(None),
+        serde_json::Value::String(s) if s.trim().is_empty() => Ok(None),
+        value => deserialize_number(value).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Parse the firmware's uptime format, e.g. "01 Days,02 Hours,03 Minutes,04
+/// Seconds"; older firmware stops at hours. Returns `None` for anything else.
+pub fn parse_uptime(text: &str) -> Option<Duration> {
+    let mut seconds = 0;
+    for part in text.split(',') {
+        let mut words = part.split_whitespace();
+        let amount: u64 = words.next()?.parse().ok()?;
+        let unit = words.next()?.to_ascii_lowercase();
+        seconds += amount
+            * match unit.trim_end_matches('s') {
+                "day" => 86400,
+                "hour" => 3600,
+                "minute" | "min" => 60,
+                "second" | "sec" => 1,
+                _ => return None,
+            };
+    }
+    Some(Duration::from_secs(seconds))
+}
+
+/// Custom deserializer for the uptime string; an unrecognized format is
+/// treated as unreported rather than failing the whole parse
+fn deserialize_uptime<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let text = Option::<String>::deserialize(deserializer)?;
+    Ok(text.as_deref().and_then(parse_uptime))
+}
+
+/// Custom deserializer for addresses, which the firmware leaves blank (or
+/// reports as all zeroes) when unassigned
+fn deserialize_address<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let address = deserialize_text(deserializer)?;
+    Ok(address.filter(|a| a != "0.0.0.0" && a != "::"))
+}
+
+/// Custom deserializer for lease durations, either a number of seconds or
+/// the firmware's "D: 6 H: 23 M: 59 S: 41" form
+fn deserialize_lease<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    match &value {
+        serde_json::Value::Null => return Ok(None),
+        serde_json::Value::String(s) if s.trim().is_empty() => return Ok(None),
+        serde_json::Value::String(s) if s.contains(':') => {
+            let mut seconds = 0;
+            let mut parts = s.split_whitespace();
+            while let (Some(unit), Some(amount)) = (parts.next(), parts.next()) {
+                let amount: u64 = amount.parse().map_err(serde::de::Error::custom)?;
+                seconds += amount
+                    * match unit {
+                        "D:" => 86400,
+                        "H:" => 3600,
+                        "M:" => 60,
+                        "S:" => 1,
+                        other => return Err(serde::de::Error::custom(format!("unknown lease unit '{}'", other))),
+                    };
+            }
+            return Ok(Some(seconds));
+        }
+        _ => {}
+    }
+    deserialize_number(value).map(Some).map_err(serde::de::Error::custom)
+}
+
+/// Custom deserializer for the network access setting, reported as
+/// "Permitted"/"Denied" (or a plain flag, as written by the channel state file)
+fn deserialize_network_access<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => Ok(matches!(
+            s.trim().to_ascii_lowercase().as_str(),
+            "permitted" | "enabled" | "allowed" | "1" | "true" | "yes"
+        )),
+        other => deserialize_flag(other).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Custom deserializer for a port's link state, reported as "Up"/"Down"
+/// (or "Connected", or a plain flag)
+fn deserialize_link_up<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => Ok(matches!(
+            s.trim().to_ascii_lowercase().as_str(),
+            "up" | "connected" | "link up" | "1" | "true" | "yes"
+        )),
+        other => deserialize_flag(other).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Parse a link speed such as "1000Mbps", "1 Gbps", "2.5G" or a bare "100"
+/// (taken as Mbps) into megabits per second
+pub fn parse_link_speed(text: &str) -> Option<u32> {
+    let text = text.trim().to_ascii_lowercase();
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    let amount: f64 = text[..split].parse().ok()?;
+    let multiplier = match text[split..].trim() {
+        "" | "m" | "mb" | "mbps" | "mbit" | "mbit/s" | "mb/s" => 1.0,
+        "g" | "gb" | "gbps" | "gbit" | "gbit/s" | "gb/s" => 1000.0,
+        _ => return None,
+    };
+    let mbps = amount * multiplier;
+    (mbps > 0.0).then_some(mbps.round() as u32)
+}
+
+/// Custom deserializer for link speeds; blank or unrecognized speeds are
+/// treated as unreported
+fn deserialize_link_speed<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => Ok(parse_link_speed(&s)),
+        serde_json::Value::Number(n) => Ok(n.as_u64().and_then(|n| u32::try_from(n).ok()).filter(|n| *n > 0)),
+        _ => Ok(None),
+    }
+}
+
+/// Custom deserializer for a link's duplex mode
+fn deserialize_duplex<'de, D>(deserializer: D) -> Result<Option<Duplex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let text = deserialize_text(deserializer)?.map(|t| t.to_ascii_lowercase());
+    Ok(match text.as_deref() {
+        Some(t) if t.starts_with("full") => Some(Duplex::Full),
+        Some(t) if t.starts_with("half") => Some(Duplex::Half),
+        _ => None,
+    })
 }
 
 // Endpoint structs
@@ -50,25 +772,152 @@ pub struct SystemModel {
     pub fields: serde_json::Value,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Hardware and software details of the modem, and how long it has been up
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct SystemInfo {
-    #[serde(flatten)]
-    pub fields: serde_json::Value,
+    #[serde(rename = "hwVersion", default, deserialize_with = "deserialize_text")]
+    pub hw_version: Option<String>,
+    #[serde(rename = "swVersion", default, deserialize_with = "deserialize_text")]
+    pub sw_version: Option<String>,
+    #[serde(rename = "serialNumber", default, deserialize_with = "deserialize_text")]
+    pub serial_number: Option<String>,
+    /// Cable-side MAC address
+    #[serde(rename = "rfMac", default, deserialize_with = "deserialize_text")]
+    pub rf_mac: Option<String>,
+    #[serde(rename = "systemUptime", default, deserialize_with = "deserialize_uptime")]
+    pub system_uptime: Option<Duration>,
+    /// Internal temperature in °C, on firmware that reports one
+    #[serde(
+        rename = "temperature",
+        alias = "Temperature",
+        alias = "cmTemperature",
+        default,
+        deserialize_with = "deserialize_optional_number"
+    )]
+    pub temperature: Option<f64>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Identifying details of a cable modem, for telling identical units apart
+#[derive(Debug, Clone, Default)]
+pub struct ModemIdentity {
+    pub model: Option<String>,
+    pub mac: Option<String>,
+    pub serial: Option<String>,
+    /// Software version, which changes when the ISP pushes a firmware update
+    pub firmware: Option<String>,
+}
+
+impl fmt::Display for ModemIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = [
+            self.model.clone(),
+            self.mac.as_ref().map(|mac| format!("MAC {}", mac)),
+            self.serial.as_ref().map(|serial| format!("S/N {}", serial)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if parts.is_empty() {
+            write!(f, "unknown modem")
+        } else {
+            write!(f, "{}", parts.join(" · "))
+        }
+    }
+}
+
+/// Read a string field out of an untyped endpoint object
+fn string_field(fields: &serde_json::Value, key: &str) -> Option<String> {
+    fields
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// Duplex mode of an Ethernet link
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Duplex {
+    Full,
+    Half,
+}
+
+impl fmt::Display for Duplex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Duplex::Full => write!(f, "full"),
+            Duplex::Half => write!(f, "half"),
+        }
+    }
+}
+
+/// The state of one of the modem's Ethernet ports
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct LinkStatus {
-    #[serde(flatten)]
-    pub fields: serde_json::Value,
+    /// Port name as the firmware words it, e.g. "LAN1" or "WAN"
+    #[serde(rename = "port", alias = "Port", alias = "portName", default, deserialize_with = "deserialize_text")]
+    pub port: Option<String>,
+    #[serde(
+        rename = "linkStatus",
+        alias = "LinkStatus",
+        alias = "status",
+        default,
+        deserialize_with = "deserialize_link_up"
+    )]
+    pub up: bool,
+    /// Negotiated speed in Mbps, while the link is up
+    #[serde(
+        rename = "speed",
+        alias = "linkSpeed",
+        alias = "LinkSpeed",
+        default,
+        deserialize_with = "deserialize_link_speed"
+    )]
+    pub speed_mbps: Option<u32>,
+    #[serde(
+        rename = "duplex",
+        alias = "linkDuplex",
+        alias = "LinkDuplex",
+        default,
+        deserialize_with = "deserialize_duplex"
+    )]
+    pub duplex: Option<Duplex>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// The modem's cable-side IP configuration and DOCSIS provisioning
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct DocsisWan {
-    #[serde(flatten)]
-    pub fields: serde_json::Value,
+    #[serde(rename = "CmIpAddress", default, deserialize_with = "deserialize_address")]
+    pub ipv4_addr: Option<String>,
+    #[serde(rename = "CmIpv6Address", default, deserialize_with = "deserialize_address")]
+    pub ipv6_addr: Option<String>,
+    /// DHCP lease length in seconds
+    #[serde(rename = "CmIpLeaseDuration", default, deserialize_with = "deserialize_lease")]
+    pub lease_duration: Option<u64>,
+    /// Whether the CMTS lets the modem pass traffic; the firmware reports
+    /// "Permitted" or "Denied"
+    #[serde(rename = "NetworkAccess", default, deserialize_with = "deserialize_network_access")]
+    pub network_access: bool,
+    /// Provisioning state as the firmware words it, e.g. "Online"
+    #[serde(rename = "ProvState", default)]
+    pub provisioning_state: String,
+    /// Octets sent and received since boot, on firmware that counts them
+    #[serde(rename = "CmTxOctets", alias = "TxOctets", default, deserialize_with = "deserialize_optional_number")]
+    pub tx_octets: Option<u64>,
+    #[serde(rename = "CmRxOctets", alias = "RxOctets", default, deserialize_with = "deserialize_optional_number")]
+    pub rx_octets: Option<u64>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl DocsisWan {
+    /// The modem's WAN addresses, for spotting a change between polls
+    pub fn addresses(&self) -> (Option<&str>, Option<&str>) {
+        (self.ipv4_addr.as_deref(), self.ipv6_addr.as_deref())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DownstreamChannel {
     #[serde(rename = "portId", deserialize_with = "deserialize_string_to_u32")]
     pub port_id: u32,
@@ -85,15 +934,45 @@ pub struct DownstreamChannel {
     pub uncorrect: i64,
     #[serde(rename = "channelId", deserialize_with = "deserialize_string_to_u32")]
     pub channel_id: u32,
+    /// Whether this is the primary downstream channel; firmware that doesn't
+    /// report the flag leaves every channel non-primary
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub primary: bool,
 }
 
+/// A DOCSIS 3.1 OFDM downstream receiver. Receivers not in use report
+/// zeroes and no lock.
 #[derive(Debug, Deserialize, Clone)]
 pub struct DownstreamOfdm {
-    #[serde(flatten)]
-    pub fields: serde_json::Value,
+    #[serde(rename = "receive", deserialize_with = "deserialize_string_to_u32")]
+    pub receiver: u32,
+    /// FFT size, e.g. "4K", or "NA" when unused
+    #[serde(rename = "ffttype", default)]
+    pub fft_type: String,
+    /// Frequency of subcarrier 0, in Hz
+    #[serde(rename = "Subcarr0freqFreq", deserialize_with = "deserialize_string_to_f64")]
+    pub subcarr0_freq_freq: f64,
+    #[serde(rename = "plclock", default, deserialize_with = "deserialize_flag")]
+    pub plc_locked: bool,
+    #[serde(rename = "ncplock", default, deserialize_with = "deserialize_flag")]
+    pub ncp_locked: bool,
+    #[serde(rename = "mdc1lock", default, deserialize_with = "deserialize_flag")]
+    pub mdc1_locked: bool,
+    /// Power of the PLC (PHY link channel), in dBmV
+    #[serde(rename = "plcpower", deserialize_with = "deserialize_string_to_f64")]
+    pub plc_power: f64,
+    #[serde(rename = "SNR", deserialize_with = "deserialize_string_to_f64")]
+    pub snr: f64,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl DownstreamOfdm {
+    /// Whether the receiver is fully locked onto an OFDM channel
+    pub fn is_locked(&self) -> bool {
+        self.plc_locked && self.ncp_locked && self.mdc1_locked
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UpstreamChannel {
     #[serde(rename = "portId", deserialize_with = "deserialize_string_to_u32")]
     pub port_id: u32,
@@ -107,22 +986,72 @@ pub struct UpstreamChannel {
     pub signal_strength: f64,
     #[serde(rename = "channelId", deserialize_with = "deserialize_string_to_u32")]
     pub channel_id: u32,
+    /// Upstream SNR (MER) in dB, on firmware that reports one
+    #[serde(alias = "mer", alias = "MER", default, deserialize_with = "deserialize_optional_number")]
+    pub snr: Option<f64>,
 }
 
+/// A DOCSIS 3.1 OFDMA upstream channel. Channels not in use report
+/// "DISABLED" and zero power.
 #[derive(Debug, Deserialize, Clone)]
 pub struct UpstreamOfdm {
-    #[serde(flatten)]
-    pub fields: serde_json::Value,
+    #[serde(rename = "uschindex", deserialize_with = "deserialize_string_to_u32")]
+    pub channel_index: u32,
+    /// Channel state, e.g. "OPERATE" or "DISABLED"; padded with spaces
+    #[serde(default)]
+    pub state: String,
+    /// Reported transmit power, in dBmV
+    #[serde(rename = "repPower", deserialize_with = "deserialize_string_to_f64")]
+    pub power: f64,
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
+impl UpstreamOfdm {
+    /// Whether the channel is carrying traffic
+    pub fn is_active(&self) -> bool {
+        !self.state.trim().eq_ignore_ascii_case("DISABLED")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum EventPriority {
     Critical,
     Warning,
     Notice,
-    #[serde(other)]
-    Other,
+    /// A priority string outside the standard set, kept verbatim so it can
+    /// be remapped to a known priority
+    Other(String),
+}
+
+impl EventPriority {
+    /// Classify a priority string as reported by the modem
+    pub fn from_raw(raw: &str) -> Self {
+        match raw.to_ascii_lowercase().as_str() {
+            "critical" => EventPriority::Critical,
+            "warning" => EventPriority::Warning,
+            "notice" => EventPriority::Notice,
+            _ => EventPriority::Other(raw.to_string()),
+        }
+    }
+
+    /// Rank for filtering, higher being more severe. Priorities outside the
+    /// standard set rank as warnings, so they are surfaced unless remapped.
+    pub fn severity(&self) -> u8 {
+        match self {
+            EventPriority::Notice => 0,
+            EventPriority::Warning | EventPriority::Other(_) => 1,
+            EventPriority::Critical => 2,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for EventPriority {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw: String = Deserialize::deserialize(deserializer)?;
+        Ok(EventPriority::from_raw(&raw))
+    }
 }
 
 impl fmt::Display for EventPriority {
@@ -131,131 +1060,547 @@ impl fmt::Display for EventPriority {
             EventPriority::Critical => write!(f, "critical"),
             EventPriority::Warning => write!(f, "warning"),
             EventPriority::Notice => write!(f, "notice"),
-            EventPriority::Other => write!(f, "other"),
+            EventPriority::Other(raw) => write!(f, "{}", raw),
         }
     }
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct EventLog {
+    /// Position in the modem's rolling log; orders events logged in the
+    /// same second, but not part of an event's identity, as it shifts when
+    /// old entries roll off
     pub index: u32,
     pub time: String,
     #[serde(rename = "type")]
     pub event_type: String,
     pub priority: EventPriority,
     pub event: String,
+    /// `time` as parsed when the log was fetched, with the client's formats
+    #[serde(skip)]
+    pub timestamp: Option<chrono::NaiveDateTime>,
 }
 
 impl EventLog {
-    /// Parse the timestamp from the event
-    /// Format: "MM/DD/YY HH:MM:SS"
+    /// The event's timestamp, as parsed when it was fetched or else in any
+    /// of the known firmware formats
     pub fn parse_timestamp(&self) -> Result<chrono::NaiveDateTime> {
-        chrono::NaiveDateTime::parse_from_str(&self.time, "%m/%d/%y %H:%M:%S")
-            .map_err(|e| anyhow::anyhow!("Failed to parse timestamp '{}': {}", self.time, e))
+        match self.timestamp {
+            Some(timestamp) => Ok(timestamp),
+            None => TimeFormats::default().parse(&self.time),
+        }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct Menu {
-    #[serde(flatten)]
-    pub fields: serde_json::Value,
+/// Timestamp formats used by Hitron firmware in the event log, tried in
+/// order when none is configured or the configured one doesn't match. %Y
+/// also accepts a two-digit year, so each %y format comes before its %Y
+/// twin.
+const KNOWN_TIME_FORMATS: &[&str] = &[
+    // CODA series, e.g. "10/16/26 14:03:07"
+    "%m/%d/%y %H:%M:%S",
+    "%Y/%m/%d %H:%M:%S",
+    "%m/%d/%y %I:%M:%S %p",
+    "%m/%d/%Y %I:%M:%S %p",
+    "%Y-%m-%d %H:%M:%S",
+    "%m/%d/%Y %H:%M:%S",
+];
+
+/// Parses event timestamps, trying a configured format and then the known
+/// ones, and remembering whichever matched last so the rest of a log is
+/// parsed on the first try
+#[derive(Debug, Default)]
+struct TimeFormats {
+    configured: Option<String>,
+    /// Index into the configured format followed by the known ones
+    last_matched: AtomicUsize,
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct SubMenu {
-    #[serde(flatten)]
-    pub fields: serde_json::Value,
+impl TimeFormats {
+    fn new(configured: Option<String>) -> Self {
+        Self {
+            configured,
+            last_matched: AtomicUsize::new(0),
+        }
+    }
+
+    fn formats(&self) -> impl Iterator<Item = &str> {
+        self.configured.as_deref().into_iter().chain(KNOWN_TIME_FORMATS.iter().copied())
+    }
+
+    fn parse(&self, time: &str) -> Result<chrono::NaiveDateTime> {
+        let time = time.trim();
+        let last = self.last_matched.load(Ordering::Relaxed);
+        // A year below 100 is a two-digit year read by a %Y format, e.g. the
+        // remembered one after the log switched formats
+        let parse = |format: &str| {
+            chrono::NaiveDateTime::parse_from_str(time, format)
+                .ok()
+                .filter(|timestamp| timestamp.year() >= 100)
+        };
+        if let Some(timestamp) = self.formats().nth(last).and_then(parse) {
+            return Ok(timestamp);
+        }
+        for (i, format) in self.formats().enumerate().filter(|(i, _)| *i != last) {
+            if let Some(timestamp) = parse(format) {
+                self.last_matched.store(i, Ordering::Relaxed);
+                return Ok(timestamp);
+            }
+        }
+        bail!("Failed to parse timestamp '{}': no known format matches", time)
+    }
 }
 
 // API functions
 
-pub async fn get_system_model(client: &Client) -> Result<SystemModel> {
-    let url = format!("{}/system_model.asp", BASE_URL);
-    let response = client.get(&url).send().await?;
-    Ok(response.json().await?)
+pub async fn get_system_model(client: &ApiClient) -> Result<SystemModel> {
+    client.fetch("system_model.asp", parse_json).await
 }
 
-pub async fn get_system_info(client: &Client) -> Result<Vec<SystemInfo>> {
-    let url = format!("{}/getSysInfo.asp", BASE_URL);
-    let response = client.get(&url).send().await?;
-    Ok(response.json().await?)
+pub async fn get_system_info(client: &ApiClient) -> Result<Vec<SystemInfo>> {
+    client.fetch("getSysInfo.asp", parse_json).await
 }
 
-pub async fn get_link_status(client: &Client) -> Result<Vec<LinkStatus>> {
-    let url = format!("{}/getLinkStatus.asp", BASE_URL);
-    let response = client.get(&url).send().await?;
-    Ok(response.json().await?)
+/// Fetch the modem's model, cable-side MAC, serial number and firmware version
+pub async fn get_modem_identity(client: &ApiClient) -> Result<ModemIdentity> {
+    let model = get_system_model(client).await?;
+    let info = get_system_info(client).await?.into_iter().next().unwrap_or_default();
+
+    Ok(ModemIdentity {
+        model: string_field(&model.fields, "modelName"),
+        mac: info.rf_mac,
+        serial: info.serial_number,
+        firmware: info.sw_version,
+    })
 }
 
-pub async fn get_docsis_wan(client: &Client) -> Result<Vec<DocsisWan>> {
-    let url = format!("{}/getCmDocsisWan.asp", BASE_URL);
-    let response = client.get(&url).send().await?;
-    Ok(response.json().await?)
+/// Fetch the state of the modem's Ethernet ports
+pub async fn get_link_status(client: &ApiClient) -> Result<Vec<LinkStatus>> {
+    client.fetch("getLinkStatus.asp", parse_json).await
 }
 
-pub async fn get_downstream_info(client: &Client) -> Result<Vec<DownstreamChannel>> {
-    let url = format!("{}/dsinfo.asp", BASE_URL);
-    debug!("Fetching downstream info from: {}", url);
+pub async fn get_docsis_wan(client: &ApiClient) -> Result<Vec<DocsisWan>> {
+    client.fetch("getCmDocsisWan.asp", parse_json).await
+}
 
-    let response = client.get(&url).send().await?;
-    let bytes = response.bytes().await?;
-    let text = String::from_utf8_lossy(&bytes);
-    let channels: Vec<DownstreamChannel> = serde_json::from_str(&text)?;
+pub async fn get_downstream_info(client: &ApiClient) -> Result<Vec<DownstreamChannel>> {
+    debug!("Fetching downstream info from: {}", client.url("dsinfo.asp"));
+
+    let mut channels: Vec<DownstreamChannel> = client
+        .fetch("dsinfo.asp", |url, text| parse_channel_list(url, text, client.channel_list_key.as_deref()))
+        .await?;
+    for channel in &mut channels {
+        channel.signal_strength = client.signal_scale.signal(channel.signal_strength);
+        channel.snr = client.signal_scale.snr(channel.snr);
+    }
 
     debug!("Parsed {} downstream channels", channels.len());
     Ok(channels)
 }
 
-pub async fn get_downstream_ofdm(client: &Client) -> Result<Vec<DownstreamOfdm>> {
-    let url = format!("{}/dsofdminfo.asp", BASE_URL);
-    let response = client.get(&url).send().await?;
-    Ok(response.json().await?)
+pub async fn get_downstream_ofdm(client: &ApiClient) -> Result<Vec<DownstreamOfdm>> {
+    debug!("Fetching downstream OFDM info from: {}", client.url("dsofdminfo.asp"));
+
+    let mut receivers: Vec<DownstreamOfdm> = client
+        .fetch("dsofdminfo.asp", |url, text| parse_channel_list(url, text, client.channel_list_key.as_deref()))
+        .await?;
+    for receiver in &mut receivers {
+        receiver.plc_power = client.signal_scale.signal(receiver.plc_power);
+        receiver.snr = client.signal_scale.snr(receiver.snr);
+    }
+
+    debug!("Parsed {} downstream OFDM receivers", receivers.len());
+    Ok(receivers)
 }
 
-pub async fn get_upstream_info(client: &Client) -> Result<Vec<UpstreamChannel>> {
-    let url = format!("{}/usinfo.asp", BASE_URL);
-    debug!("Fetching upstream info from: {}", url);
+pub async fn get_upstream_info(client: &ApiClient) -> Result<Vec<UpstreamChannel>> {
+    debug!("Fetching upstream info from: {}", client.url("usinfo.asp"));
 
-    let response = client.get(&url).send().await?;
-    let bytes = response.bytes().await?;
-    let text = String::from_utf8_lossy(&bytes);
-    let channels: Vec<UpstreamChannel> = serde_json::from_str(&text)?;
+    let mut channels: Vec<UpstreamChannel> = client
+        .fetch("usinfo.asp", |url, text| parse_channel_list(url, text, client.channel_list_key.as_deref()))
+        .await?;
+    for channel in &mut channels {
+        channel.signal_strength = client.signal_scale.signal(channel.signal_strength);
+        channel.snr = channel.snr.map(|snr| client.signal_scale.snr(snr));
+    }
 
     debug!("Parsed {} upstream channels", channels.len());
     Ok(channels)
 }
 
-pub async fn get_upstream_ofdm(client: &Client) -> Result<Vec<UpstreamOfdm>> {
-    let url = format!("{}/usofdminfo.asp", BASE_URL);
-    let response = client.get(&url).send().await?;
-    Ok(response.json().await?)
-}
+pub async fn get_upstream_ofdm(client: &ApiClient) -> Result<Vec<UpstreamOfdm>> {
+    debug!("Fetching upstream OFDM info from: {}", client.url("usofdminfo.asp"));
 
-pub async fn get_event_log(client: &Client) -> Result<Vec<EventLog>> {
-    let url = format!("{}/status_log.asp", BASE_URL);
-    debug!("Fetching event log from: {}", url);
+    let mut channels: Vec<UpstreamOfdm> = client
+        .fetch("usofdminfo.asp", |url, text| parse_channel_list(url, text, client.channel_list_key.as_deref()))
+        .await?;
+    for channel in &mut channels {
+        channel.power = client.signal_scale.signal(channel.power);
+    }
 
-    let response = client.get(&url).send().await?;
-    debug!("Response status: {}", response.status());
+    debug!("Parsed {} upstream OFDM channels", channels.len());
+    Ok(channels)
+}
 
-    let bytes = response.bytes().await?;
-    debug!("Received {} bytes", bytes.len());
+pub async fn get_event_log(client: &ApiClient) -> Result<Vec<EventLog>> {
+    debug!("Fetching event log from: {}", client.url(EVENT_LOG_ENDPOINT));
 
-    let text = String::from_utf8_lossy(&bytes);
-    let events: Vec<EventLog> = serde_json::from_str(&text)?;
+    let mut events: Vec<EventLog> = client.fetch(EVENT_LOG_ENDPOINT, parse_json).await?;
+    for event in &mut events {
+        if let EventPriority::Other(raw) = &event.priority {
+            if let Some(mapped) = client.priority_map.get(&raw.to_ascii_lowercase()) {
+                event.priority = mapped.clone();
+            }
+        }
+        event.timestamp = client.time_formats.parse(&event.time).ok();
+    }
 
     debug!("Parsed {} events", events.len());
     Ok(events)
 }
 
-pub async fn get_main_menu(client: &Client) -> Result<Vec<Menu>> {
-    let url = format!("{}/getMenu.asp", BASE_URL);
-    let response = client.get(&url).send().await?;
-    Ok(response.json().await?)
+/// Every data endpoint read above, by the short name the `dump` command
+/// takes
+pub const ENDPOINTS: &[(&str, &str)] = &[
+    ("model", "system_model.asp"),
+    ("sysinfo", "getSysInfo.asp"),
+    ("link", "getLinkStatus.asp"),
+    ("wan", "getCmDocsisWan.asp"),
+    ("dsinfo", "dsinfo.asp"),
+    ("dsofdm", "dsofdminfo.asp"),
+    ("usinfo", "usinfo.asp"),
+    ("usofdm", "usofdminfo.asp"),
+    ("status_log", EVENT_LOG_ENDPOINT),
+];
+
+/// Fetch a data endpoint's body without parsing it, to see what the
+/// firmware actually sends
+pub async fn get_raw(client: &ApiClient, endpoint: &str) -> Result<String> {
+    client.fetch(endpoint, |_, text| Ok(text.to_string())).await
 }
 
-pub async fn get_submenu(client: &Client) -> Result<Vec<SubMenu>> {
-    let url = format!("{}/getSubMenu.asp", BASE_URL);
-    let response = client.get(&url).send().await?;
-    Ok(response.json().await?)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://192.168.100.1/data/dsinfo.asp";
+
+    #[test]
+    fn known_firmware_time_formats_parse() {
+        let formats = TimeFormats::default();
+        let expected = chrono::NaiveDate::from_ymd_opt(2026, 6, 27).unwrap().and_hms_opt(15, 23, 34).unwrap();
+        for time in [
+            "06/27/26 15:23:34",
+            "2026/06/27 15:23:34",
+            "06/27/26 03:23:34 PM",
+            "06/27/2026 03:23:34 PM",
+            "2026-06-27 15:23:34",
+        ] {
+            assert_eq!(formats.parse(time).unwrap(), expected, "{}", time);
+        }
+        assert!(formats.parse("27 June, 3pm").is_err());
+    }
+
+    #[test]
+    fn two_digit_year_is_not_read_as_four_digits() {
+        let formats = TimeFormats::default();
+        let expected = chrono::NaiveDate::from_ymd_opt(2026, 6, 27).unwrap().and_hms_opt(15, 23, 34).unwrap();
+        // Remember the four-digit AM/PM format, then switch to two digits
+        formats.parse("06/27/2026 03:23:34 PM").unwrap();
+        assert_eq!(formats.parse("06/27/26 03:23:34 PM").unwrap(), expected);
+        assert_eq!(formats.parse("06/27/26 15:23:34").unwrap(), expected);
+    }
+
+    #[test]
+    fn configured_time_format_is_tried_first_and_matches_are_remembered() {
+        let formats = TimeFormats::new(Some("%d.%m.%Y %H:%M".to_string()));
+        let expected = chrono::NaiveDate::from_ymd_opt(2026, 6, 27).unwrap().and_hms_opt(15, 23, 0).unwrap();
+        assert_eq!(formats.parse("27.06.2026 15:23").unwrap(), expected);
+        assert_eq!(formats.last_matched.load(Ordering::Relaxed), 0);
+
+        formats.parse("2026/06/27 15:23:00").unwrap();
+        assert_eq!(formats.last_matched.load(Ordering::Relaxed), 2);
+        // Still parses the configured format after switching
+        assert_eq!(formats.parse("27.06.2026 15:23").unwrap(), expected);
+    }
+
+    #[test]
+    fn modem_host_becomes_data_base_url() {
+        assert_eq!(modem_base_url(DEFAULT_MODEM_HOST).unwrap(), "https://192.168.100.1/data");
+        assert_eq!(modem_base_url("192.168.0.1").unwrap(), "https://192.168.0.1/data");
+        assert_eq!(modem_base_url("http://modem.lan:8080/").unwrap(), "http://modem.lan:8080/data");
+        assert!(modem_base_url("not a host").is_err());
+        assert!(modem_base_url("ftp://192.168.100.1").is_err());
+    }
+
+    #[test]
+    fn priorities_rank_by_severity() {
+        assert!(EventPriority::Critical.severity() > EventPriority::Warning.severity());
+        assert!(EventPriority::Warning.severity() > EventPriority::Notice.severity());
+        assert_eq!(EventPriority::Other("debug".into()).severity(), EventPriority::Warning.severity());
+    }
+
+    #[test]
+    fn truncated_body_is_transient() {
+        let text = r#"[{"portId":"1","frequency":"#;
+        let err = parse_json::<Vec<DownstreamChannel>>(URL, text).unwrap_err();
+        let api_error = err.downcast_ref::<ApiError>().expect("classified as ApiError");
+        assert!(matches!(api_error, ApiError::Truncated { received, .. } if *received == text.len()));
+        assert!(api_error.is_transient());
+    }
+
+    #[test]
+    fn malformed_body_is_a_parse_error() {
+        let err = parse_json::<Vec<DownstreamChannel>>(URL, r#"[{"portId":}]"#).unwrap_err();
+        assert!(err.downcast_ref::<ApiError>().is_none());
+    }
+
+    #[test]
+    fn html_page_is_not_json() {
+        let page = "\n<!DOCTYPE html><html><head><title>Login</title></head></html>";
+        let err = parse_json::<Vec<EventLog>>(URL, page).unwrap_err();
+        let api_error = err.downcast_ref::<ApiError>().unwrap();
+        assert!(matches!(api_error, ApiError::NotJson { .. }));
+        assert!(api_error.is_transient());
+    }
+
+    #[tokio::test]
+    async fn truncated_body_is_fetched_again() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        // Cut the first response off mid-document, then serve it whole
+        let requests = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&requests);
+        let app = axum::Router::new().route(
+            "/data/dsinfo.asp",
+            axum::routing::get(move || async move {
+                let body = include_str!("../tests/fixtures/dsinfo.json");
+                match counter.fetch_add(1, Ordering::SeqCst) {
+                    0 => &body[..body.len() / 2],
+                    _ => body,
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let connect_retry = ConnectRetry { attempts: 0, delay: Duration::ZERO };
+        let scale = SignalScale { factor: 1.0, offset: 0.0 };
+        let mut client =
+            create_client(&host, Duration::from_secs(5), connect_retry, scale, &TlsVerification::System).unwrap();
+        client.set_retry(RetryPolicy { max_retries: 2, base_delay: Duration::from_millis(1) });
+
+        let channels = get_downstream_info(&client).await.unwrap();
+        assert_eq!(channels.len(), 3);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn slow_event_log_gets_its_own_timeout() {
+        let app = axum::Router::new().route(
+            "/data/status_log.asp",
+            axum::routing::get(|| async {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                "[]"
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let connect_retry = ConnectRetry { attempts: 0, delay: Duration::ZERO };
+        let scale = SignalScale { factor: 1.0, offset: 0.0 };
+        let client =
+            create_client(&host, Duration::from_millis(100), connect_retry, scale, &TlsVerification::System).unwrap();
+        let err = get_event_log(&client).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<ApiError>(), Some(ApiError::Http(e)) if e.is_timeout()));
+
+        let mut source =
+            create_http_source(&host, Duration::from_millis(100), connect_retry, &TlsVerification::System).unwrap();
+        source.set_event_log_timeout(Some(Duration::from_secs(5)));
+        let client = ApiClient::new(Box::new(source), scale);
+        assert!(get_event_log(&client).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn json_served_as_html_keeps_the_session() {
+        use std::sync::atomic::AtomicU32;
+        use std::sync::Arc;
+
+        // Firmware labels its JSON text/html; only the login page is HTML
+        let logins = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&logins);
+        let app = axum::Router::new()
+            .route(
+                "/data/dsinfo.asp",
+                axum::routing::get(|| async {
+                    ([(reqwest::header::CONTENT_TYPE, "text/html")], include_str!("../tests/fixtures/dsinfo.json"))
+                }),
+            )
+            .route(
+                "/data/usinfo.asp",
+                axum::routing::get(|| async { "<html><body>Please log in</body></html>" }),
+            )
+            .route(
+                "/goform/login",
+                axum::routing::post(move || async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let connect_retry = ConnectRetry { attempts: 0, delay: Duration::ZERO };
+        let mut source =
+            create_http_source(&host, Duration::from_secs(5), connect_retry, &TlsVerification::System).unwrap();
+        source.set_credentials(Credentials {
+            username: "admin".to_string(),
+            password: "password".to_string(),
+        });
+        let mut client = ApiClient::new(Box::new(source), SignalScale { factor: 1.0, offset: 0.0 });
+        client.set_retry(RetryPolicy { max_retries: 0, base_delay: Duration::ZERO });
+
+        assert_eq!(get_downstream_info(&client).await.unwrap().len(), 3);
+        assert_eq!(logins.load(Ordering::SeqCst), 0);
+
+        let err = get_upstream_info(&client).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<ApiError>(), Some(ApiError::Login(_))), "{:?}", err);
+        assert_eq!(logins.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn bare_and_wrapped_downstream_lists_match() {
+        let bare: Vec<DownstreamChannel> =
+            parse_channel_list(URL, include_str!("../tests/fixtures/dsinfo.json"), None).unwrap();
+        let wrapped: Vec<DownstreamChannel> =
+            parse_channel_list(URL, include_str!("../tests/fixtures/dsinfo_wrapped.json"), None).unwrap();
+        assert_eq!(bare.len(), 3);
+        assert_eq!(wrapped.len(), 3);
+        assert_eq!(bare[2].channel_id, wrapped[2].channel_id);
+        assert_eq!(wrapped[2].uncorrect, 2);
+    }
+
+    #[test]
+    fn retry_backoff_doubles_with_jitter() {
+        let retry = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_secs(2),
+        };
+        for (attempt, nominal) in [(0, 2.0), (1, 4.0), (2, 8.0)] {
+            let delay = retry.backoff(attempt).as_secs_f64();
+            assert!(delay >= nominal * 0.5 && delay < nominal * 1.5, "attempt {}: {}", attempt, delay);
+        }
+    }
+
+    #[test]
+    fn system_info_parses() {
+        let info: Vec<SystemInfo> = parse_json(URL, include_str!("../tests/fixtures/sysinfo.json")).unwrap();
+        assert_eq!(info[0].hw_version.as_deref(), Some("1A"));
+        assert_eq!(info[0].sw_version.as_deref(), Some("7.1.1.2.4b5"));
+        assert_eq!(info[0].serial_number.as_deref(), Some("361812032175"));
+        assert_eq!(info[0].system_uptime, Some(Duration::from_secs(12 * 86400 + 5 * 3600 + 41 * 60 + 7)));
+        assert_eq!(info[0].temperature, None);
+    }
+
+    #[test]
+    fn uptime_formats_parse() {
+        assert_eq!(parse_uptime("01 Days,02 Hours"), Some(Duration::from_secs(86400 + 2 * 3600)));
+        assert_eq!(parse_uptime("00 Days,00 Hours,01 Minutes,05 Seconds"), Some(Duration::from_secs(65)));
+        assert_eq!(parse_uptime("1 Day,1 Hour"), Some(Duration::from_secs(90000)));
+        assert_eq!(parse_uptime("a while"), None);
+    }
+
+    #[test]
+    fn link_status_parses() {
+        let links: Vec<LinkStatus> = parse_json(URL, include_str!("../tests/fixtures/linkstatus.json")).unwrap();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].port.as_deref(), Some("LAN1"));
+        assert!(links[0].up);
+        assert_eq!(links[0].speed_mbps, Some(1000));
+        assert_eq!(links[0].duplex, Some(Duplex::Full));
+        assert!(!links[1].up);
+        assert_eq!(links[1].speed_mbps, None);
+        assert_eq!(links[1].duplex, None);
+
+        assert_eq!(parse_link_speed("1 Gbps"), Some(1000));
+        assert_eq!(parse_link_speed("2.5G"), Some(2500));
+        assert_eq!(parse_link_speed("100"), Some(100));
+        assert_eq!(parse_link_speed("auto"), None);
+    }
+
+    #[test]
+    fn docsis_wan_parses() {
+        let wan: Vec<DocsisWan> = parse_json(URL, include_str!("../tests/fixtures/docsiswan.json")).unwrap();
+        assert_eq!(wan.len(), 1);
+        assert_eq!(wan[0].ipv4_addr.as_deref(), Some("10.132.48.211"));
+        assert_eq!(wan[0].ipv6_addr.as_deref(), Some("2001:db8:4:2::1c1"));
+        assert_eq!(wan[0].lease_duration, Some(6 * 86400 + 23 * 3600 + 59 * 60 + 41));
+        assert!(wan[0].network_access);
+        assert_eq!(wan[0].provisioning_state, "Online");
+
+        let denied: DocsisWan =
+            serde_json::from_str(r#"{"CmIpAddress":"","CmIpLeaseDuration":"3600","NetworkAccess":"Denied"}"#).unwrap();
+        assert_eq!(denied.ipv4_addr, None);
+        assert_eq!(denied.lease_duration, Some(3600));
+        assert!(!denied.network_access);
+        assert_eq!(denied.tx_octets, None);
+
+        let counted: DocsisWan =
+            serde_json::from_str(r#"{"NetworkAccess":"Permitted","CmTxOctets":"1234","CmRxOctets":56789}"#).unwrap();
+        assert_eq!((counted.tx_octets, counted.rx_octets), (Some(1234), Some(56789)));
+    }
+
+    #[test]
+    fn downstream_ofdm_parses() {
+        let receivers: Vec<DownstreamOfdm> =
+            parse_channel_list(URL, include_str!("../tests/fixtures/dsofdminfo.json"), None).unwrap();
+        assert_eq!(receivers.len(), 2);
+        assert_eq!(receivers[0].subcarr0_freq_freq, 290_600_000.0);
+        assert_eq!(receivers[0].plc_power, 2.5);
+        assert_eq!(receivers[0].snr, 41.0);
+        assert!(receivers[0].is_locked());
+        // Unused receiver
+        assert_eq!(receivers[1].fft_type, "NA");
+        assert!(!receivers[1].is_locked());
+    }
+
+    #[test]
+    fn upstream_ofdm_parses() {
+        let channels: Vec<UpstreamOfdm> =
+            parse_channel_list(URL, include_str!("../tests/fixtures/usofdminfo.json"), None).unwrap();
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].channel_index, 0);
+        assert_eq!(channels[0].power, 44.5);
+        assert!(channels[0].is_active());
+        assert!(!channels[1].is_active());
+    }
+
+    #[test]
+    fn bare_and_wrapped_upstream_lists_match() {
+        let bare: Vec<UpstreamChannel> =
+            parse_channel_list(URL, include_str!("../tests/fixtures/usinfo.json"), None).unwrap();
+        let wrapped: Vec<UpstreamChannel> =
+            parse_channel_list(URL, include_str!("../tests/fixtures/usinfo_wrapped.json"), None).unwrap();
+        assert_eq!(bare.len(), 2);
+        assert_eq!(wrapped[0].signal_strength, 44.25);
+        assert_eq!(bare[0].snr, None);
+    }
+
+    #[test]
+    fn upstream_mer_is_read_when_reported() {
+        let text = r#"[{"portId":"1","frequency":"38600000","bandwidth":"6400000","modtype":"64QAM","signalStrength":"44.250","channelId":"3","mer":"36.5"}]"#;
+        let channels: Vec<UpstreamChannel> = parse_channel_list(URL, text, None).unwrap();
+        assert_eq!(channels[0].snr, Some(36.5));
+    }
+
+    #[test]
+    fn configured_wrapper_key_is_used() {
+        let text = r#"{"dsTable":[]}"#;
+        assert!(parse_channel_list::<DownstreamChannel>(URL, text, None).is_err());
+        let channels = parse_channel_list::<DownstreamChannel>(URL, text, Some("dsTable")).unwrap();
+        assert!(channels.is_empty());
+    }
 }
@@ -0,0 +1,175 @@
+use anyhow::{bail, Context, Result};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Command};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Read a TOML configuration file
+pub fn load(path: &Path) -> Result<toml::Table> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("Failed to parse config file {}", path.display()))
+}
+
+/// Splice the options from the `--config` file, if one is given, into the
+/// command line ahead of any subcommand. The command line is only read
+/// leniently here, so options that require each other can be split between
+/// it and the file; the merged line is validated as a whole afterwards.
+pub fn merge(command: &Command, mut argv: Vec<OsString>) -> Result<Vec<OsString>> {
+    let matches = command.clone().ignore_errors(true).get_matches_from(argv.iter());
+    let Some(path) = matches.get_one::<PathBuf>("config") else {
+        return Ok(argv);
+    };
+    let table = load(path)?;
+    argv.splice(1..1, to_args(command, &matches, &table)?);
+    Ok(argv)
+}
+
+/// Turn a configuration table into command-line arguments for every option
+/// not already set on the command line or through the environment, so
+/// those win. Keys are long option names, with dashes or underscores.
+/// Arrays repeat the option, and tables of KEY = VALUE pairs fill options
+/// taking KEY=VALUE such as `priority-map`. A flag set to false turns into
+/// its `--no-` counterpart, where there is one, such as `--no-insecure`.
+pub fn to_args(command: &Command, matches: &ArgMatches, table: &toml::Table) -> Result<Vec<OsString>> {
+    let mut args = Vec::new();
+    for (key, value) in table {
+        let long = key.replace('_', "-");
+        let Some(arg) = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(long.as_str()) && long != "config")
+        else {
+            bail!("Unknown option in config file: {}", key);
+        };
+
+        let overridden = matches!(
+            matches.value_source(arg.get_id().as_str()),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        );
+        if overridden {
+            continue;
+        }
+
+        let flag = !arg.get_action().takes_values();
+        let negated = format!("no-{}", long);
+        let has_negation = command.get_arguments().any(|arg| arg.get_long() == Some(negated.as_str()));
+        for value in flatten(key, value)? {
+            match (flag, value.as_str()) {
+                (true, "true") => args.push(format!("--{}", long).into()),
+                (true, "false") if has_negation => args.push(format!("--{}", negated).into()),
+                (true, "false") => {}
+                (true, _) => bail!("Config option {} must be true or false", key),
+                (false, value) => args.push(format!("--{}={}", long, value).into()),
+            }
+        }
+    }
+    Ok(args)
+}
+
+/// The command-line values a config value stands for
+fn flatten(key: &str, value: &toml::Value) -> Result<Vec<String>> {
+    Ok(match value {
+        toml::Value::Array(items) => items
+            .iter()
+            .map(|item| scalar(key, item))
+            .collect::<Result<_>>()?,
+        toml::Value::Table(pairs) => pairs
+            .iter()
+            .map(|(k, v)| Ok(format!("{}={}", k, scalar(key, v)?)))
+            .collect::<Result<_>>()?,
+        other => vec![scalar(key, other)?],
+    })
+}
+
+fn scalar(key: &str, value: &toml::Value) -> Result<String> {
+    Ok(match value {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Integer(i) => i.to_string(),
+        toml::Value::Float(f) => f.to_string(),
+        toml::Value::Boolean(b) => b.to_string(),
+        _ => bail!("Config option {} must be a string, number or boolean", key),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{Arg, ArgAction};
+
+    fn command() -> Command {
+        Command::new("test")
+            .arg(Arg::new("interval").long("interval").default_value("60"))
+            .arg(Arg::new("webhook").long("webhook").env("TEST_CONFIG_WEBHOOK"))
+            .arg(Arg::new("once").long("once").action(ArgAction::SetTrue))
+            .arg(Arg::new("insecure").long("insecure").action(ArgAction::SetTrue))
+            .arg(Arg::new("no_insecure").long("no-insecure").action(ArgAction::SetTrue))
+            .arg(Arg::new("urgent_patterns").long("urgent-pattern").action(ArgAction::Append))
+            .arg(Arg::new("priority_map").long("priority-map").action(ArgAction::Append))
+    }
+
+    fn args(cli: &[&str], config: &str) -> Result<Vec<String>> {
+        let command = command();
+        let matches = command.clone().get_matches_from(std::iter::once("test").chain(cli.iter().copied()));
+        let table: toml::Table = toml::from_str(config).unwrap();
+        Ok(to_args(&command, &matches, &table)?
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn config_fills_in_what_the_cli_leaves_unset() {
+        let config = r#"
+            interval = 30
+            once = true
+            urgent_pattern = ["T3 timeout", "lost sync"]
+
+            [priority-map]
+            debug = "notice"
+        "#;
+        let mut expanded = args(&["--interval=10"], config).unwrap();
+        expanded.sort();
+        assert_eq!(
+            expanded,
+            [
+                "--once",
+                "--priority-map=debug=notice",
+                "--urgent-pattern=T3 timeout",
+                "--urgent-pattern=lost sync",
+            ]
+        );
+    }
+
+    #[test]
+    fn false_turns_a_flag_off_through_its_negation() {
+        assert_eq!(args(&[], "insecure = false").unwrap(), ["--no-insecure"]);
+        assert_eq!(args(&[], "insecure = true").unwrap(), ["--insecure"]);
+        assert!(args(&[], "once = false").unwrap().is_empty());
+    }
+
+    #[test]
+    fn required_pairs_can_be_split_with_the_config_file() {
+        let command = Command::new("test")
+            .arg(Arg::new("config").long("config").value_parser(clap::value_parser!(PathBuf)))
+            .arg(Arg::new("username").long("username").requires("password"))
+            .arg(Arg::new("password").long("password").requires("username"));
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.path();
+        std::fs::write(path, "password = \"hunter2\"").unwrap();
+
+        let argv = ["test", "--username=admin", "--config", path.to_str().unwrap()]
+            .map(OsString::from)
+            .to_vec();
+        let merged = merge(&command, argv);
+
+        let matches = command.try_get_matches_from(merged.unwrap()).unwrap();
+        assert_eq!(matches.get_one::<String>("password").map(String::as_str), Some("hunter2"));
+    }
+
+    #[test]
+    fn bad_config_keys_are_rejected() {
+        assert!(args(&[], "intervall = 30").is_err());
+        assert!(args(&[], "once = \"yes\"").is_err());
+        assert!(args(&[], "config = \"other.toml\"").is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use crate::api::{DownstreamChannel, UpstreamChannel};
+use crate::monitor::Direction;
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+/// One channel's readings from a poll, as a CSV row
+#[derive(Debug, Serialize)]
+struct Row<'a> {
+    timestamp: &'a str,
+    direction: Direction,
+    channel_id: u32,
+    frequency: f64,
+    modulation: &'a str,
+    signal: f64,
+    snr: Option<f64>,
+    /// Upstream channels have no error counters, so these are left empty
+    correcteds: Option<i64>,
+    uncorrect: Option<i64>,
+}
+
+/// Append a row per channel from this poll, writing the header first if
+/// the file is new or empty
+pub async fn append(path: &Path, downstream: &[DownstreamChannel], upstream: &[UpstreamChannel]) -> Result<()> {
+    if downstream.is_empty() && upstream.is_empty() {
+        return Ok(());
+    }
+    let timestamp = chrono::Local::now().to_rfc3339();
+    let needs_header = fs::metadata(path).await.map(|m| m.len() == 0).unwrap_or(true);
+
+    let mut writer = csv::WriterBuilder::new().has_headers(needs_header).from_writer(Vec::new());
+    for c in downstream {
+        writer.serialize(Row {
+            timestamp: &timestamp,
+            direction: Direction::Downstream,
+            channel_id: c.channel_id,
+            frequency: c.frequency,
+            modulation: &c.modulation,
+            signal: c.signal_strength,
+            snr: Some(c.snr),
+            correcteds: Some(c.correcteds),
+            uncorrect: Some(c.uncorrect),
+        })?;
+    }
+    for c in upstream {
+        writer.serialize(Row {
+            timestamp: &timestamp,
+            direction: Direction::Upstream,
+            channel_id: c.channel_id,
+            frequency: c.frequency,
+            modulation: &c.modulation_type,
+            signal: c.signal_strength,
+            snr: c.snr,
+            correcteds: None,
+            uncorrect: None,
+        })?;
+    }
+    let rows = writer.into_inner()?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path).await?;
+    file.write_all(&rows).await?;
+    // Don't leave the poll's rows buffered if the process dies before the next one
+    file.sync_data().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn header_is_written_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("channels.csv");
+        let downstream = DownstreamChannel {
+            port_id: 1,
+            frequency: 591_000_000.0,
+            modulation: "256QAM".to_string(),
+            signal_strength: 3.0,
+            snr: 40.0,
+            correcteds: 12,
+            uncorrect: 1,
+            channel_id: 9,
+            primary: false,
+        };
+        let upstream = UpstreamChannel {
+            port_id: 1,
+            frequency: 38_700_000.0,
+            bandwidth: "6400000".to_string(),
+            modulation_type: "ATDMA".to_string(),
+            signal_strength: 42.0,
+            channel_id: 3,
+            snr: None,
+        };
+
+        append(&path, std::slice::from_ref(&downstream), &[upstream]).await.unwrap();
+        append(&path, &[downstream], &[]).await.unwrap();
+
+        let text = fs::read_to_string(&path).await.unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            "timestamp,direction,channel_id,frequency,modulation,signal,snr,correcteds,uncorrect"
+        );
+        assert!(lines[1].ends_with(",downstream,9,591000000.0,256QAM,3.0,40.0,12,1"));
+        assert!(lines[2].ends_with(",upstream,3,38700000.0,ATDMA,42.0,,,"));
+        assert!(lines[3].contains(",downstream,9,"));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::api::{DownstreamChannel, UpstreamChannel};
+use crate::monitor::Direction;
+use std::collections::HashMap;
+
+/// Decides which polls are written to the spectrum and CSV exports, to keep
+/// them from growing with readings that haven't changed
+#[derive(Debug)]
+pub struct Decimation {
+    /// Write every this many polls
+    every: u32,
+    /// Also write when a channel's signal or SNR moved by more than this
+    /// many dB since the last written poll
+    delta: Option<f64>,
+    polls: u32,
+    /// Signal and SNR by channel, as last written
+    written: HashMap<(Direction, u32), (f64, Option<f64>)>,
+}
+
+impl Decimation {
+    pub fn new(every: u32, delta: Option<f64>) -> Self {
+        Self {
+            every: every.max(1),
+            delta,
+            polls: 0,
+            written: HashMap::new(),
+        }
+    }
+
+    /// Whether this poll should be written. Polls with anomalies always are,
+    /// as are ones where any channel moved by more than the delta, or came
+    /// or went.
+    pub fn should_write(
+        &mut self,
+        downstream: &[DownstreamChannel],
+        upstream: &[UpstreamChannel],
+        anomalous: bool,
+    ) -> bool {
+        let readings: HashMap<(Direction, u32), (f64, Option<f64>)> = downstream
+            .iter()
+            .map(|c| ((Direction::Downstream, c.channel_id), (c.signal_strength, Some(c.snr))))
+            .chain(upstream.iter().map(|c| ((Direction::Upstream, c.channel_id), (c.signal_strength, c.snr))))
+            .collect();
+
+        let due = self.polls.is_multiple_of(self.every);
+        self.polls += 1;
+        let changed = self.delta.is_some_and(|delta| {
+            readings.len() != self.written.len()
+                || readings.iter().any(|(key, (signal, snr))| match self.written.get(key) {
+                    Some((last_signal, last_snr)) => {
+                        (signal - last_signal).abs() > delta
+                            || snr.zip(*last_snr).is_some_and(|(snr, last)| (snr - last).abs() > delta)
+                    }
+                    None => true,
+                })
+        });
+
+        let write = due || anomalous || changed;
+        if write {
+            self.written = readings;
+        }
+        write
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn downstream(snr: f64) -> DownstreamChannel {
+        DownstreamChannel {
+            port_id: 1,
+            frequency: 591_000_000.0,
+            modulation: "2".to_string(),
+            signal_strength: 3.0,
+            snr,
+            correcteds: 0,
+            uncorrect: 0,
+            channel_id: 9,
+            primary: false,
+        }
+    }
+
+    #[test]
+    fn decimation_writes_every_nth_poll_and_on_change() {
+        let mut decimation = Decimation::new(3, Some(1.0));
+        let writes: Vec<bool> = [40.0, 40.2, 40.4, 40.1, 38.0, 38.3]
+            .iter()
+            .map(|snr| decimation.should_write(&[downstream(*snr)], &[], false))
+            .collect();
+        // Polls 0 and 3 are due; poll 4 dropped 2.1 dB from the last write
+        assert_eq!(writes, [true, false, false, true, true, false]);
+        assert!(decimation.should_write(&[downstream(38.3)], &[], true));
+    }
+}
@@ -1,29 +1,53 @@
 use crate::api::EventLog;
 use chrono::{Datelike, NaiveDateTime};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 /// Identity of an event log entry for dedup purposes. The modem's `index`
 /// field is a position in the rolling log and shifts as entries age out,
-/// so the (time, type, event) triple is the stable identity.
+/// so the (time, type, event) triple is the stable identity, plus a count
+/// to tell apart identical entries logged in the same second.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct EventKey {
     pub time: String,
     #[serde(rename = "type")]
     pub event_type: String,
     pub event: String,
+    /// How many identical entries come before this one in the log, oldest
+    /// first. Absent from state files written before it existed.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub repeat: u32,
 }
 
-impl From<&EventLog> for EventKey {
-    fn from(event: &EventLog) -> Self {
+impl EventKey {
+    fn new(event: &EventLog, repeat: u32) -> Self {
         Self {
             time: event.time.clone(),
             event_type: event.event_type.clone(),
             event: event.event.clone(),
+            repeat,
         }
     }
 }
 
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+/// The key of each event in a normalized log, in the same order
+pub fn keys(events: &[EventLog]) -> Vec<EventKey> {
+    let mut counts: HashMap<EventKey, u32> = HashMap::new();
+    events
+        .iter()
+        .map(|event| {
+            let count = counts.entry(EventKey::new(event, 0)).or_default();
+            let key = EventKey::new(event, *count);
+            *count += 1;
+            key
+        })
+        .collect()
+}
+
 /// Events logged before the modem syncs time-of-day are stamped with the
 /// Unix epoch in the modem's local timezone (e.g. "12/31/69 19:01:07").
 /// chrono's %y pivot maps 69 to 2069 and 70-99 to the 1970s-90s, so any
@@ -32,17 +56,34 @@ pub fn is_pre_sync_timestamp(ts: &NaiveDateTime) -> bool {
     !(2000..2069).contains(&ts.year())
 }
 
+/// Put a fetched event log in chronological order, oldest first, and drop
+/// entries the modem listed twice under the same index. The modem usually lists events newest
+/// first but doesn't guarantee it. Events at the same second are ordered by
+/// their index. Pre-sync and unparseable timestamps say nothing about when
+/// the event happened, so those entries sort first and keep their relative
+/// order.
+pub fn normalize(events: &mut Vec<EventLog>) {
+    let mut listed = HashSet::new();
+    events.retain(|event| listed.insert((event.index, EventKey::new(event, 0))));
+    events.sort_by_cached_key(|event| {
+        let time = event.parse_timestamp().ok().filter(|ts| !is_pre_sync_timestamp(ts));
+        (time, event.index)
+    });
+}
+
 /// Events in `current` that were not present in the previous snapshot.
 pub fn new_events<'a>(current: &'a [EventLog], seen: &HashSet<EventKey>) -> Vec<&'a EventLog> {
     current
         .iter()
-        .filter(|event| !seen.contains(&EventKey::from(*event)))
+        .zip(keys(current))
+        .filter(|(_, key)| !seen.contains(key))
+        .map(|(event, _)| event)
         .collect()
 }
 
 /// Snapshot of the current event log for the next poll's dedup.
 pub fn snapshot(events: &[EventLog]) -> HashSet<EventKey> {
-    events.iter().map(EventKey::from).collect()
+    keys(events).into_iter().collect()
 }
 
 /// Parse state-file contents (a JSON array of event keys). Legacy state
@@ -54,13 +95,6 @@ pub fn parse_state(contents: &str) -> Option<HashSet<EventKey>> {
         .map(|keys| keys.into_iter().collect())
 }
 
-/// Serialize the seen set for the state file, sorted for stable output.
-pub fn serialize_state(seen: &HashSet<EventKey>) -> String {
-    let mut keys: Vec<&EventKey> = seen.iter().collect();
-    keys.sort();
-    serde_json::to_string_pretty(&keys).expect("event keys serialize to JSON")
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -73,6 +107,7 @@ mod tests {
             event_type: event_type.to_string(),
             priority: EventPriority::Notice,
             event: event.to_string(),
+            timestamp: None,
         }
     }
 
@@ -103,7 +138,7 @@ mod tests {
             event(1, "06/27/26 15:23:34", "82001100", "RNG-RSP Power Exceeds DRW"),
             event(2, "06/27/26 15:23:34", "73050400", "REG-RSP-MP Mismatch"),
         ];
-        let seen: HashSet<EventKey> = [EventKey::from(&events[0])].into();
+        let seen = snapshot(&events[..1]);
         let new = new_events(&events, &seen);
         assert_eq!(new.len(), 1);
         assert_eq!(new[0].event_type, "73050400");
@@ -130,12 +165,37 @@ mod tests {
     }
 
     #[test]
-    fn state_round_trips() {
-        let seen = snapshot(&[
-            event(1, "06/27/26 15:23:34", "82001100", "RNG-RSP"),
-            event(2, "12/31/69 19:01:07", "90000006", "POWER_ON"),
-        ]);
-        assert_eq!(parse_state(&serialize_state(&seen)), Some(seen));
+    fn shuffled_log_is_put_in_order() {
+        let mut events = vec![
+            event(5, "06/27/26 15:23:34", "73050400", "REG-RSP-MP Mismatch"),
+            event(2, "06/27/26 15:30:00", "82001100", "RNG-RSP Power Exceeds DRW"),
+            event(9, "12/31/69 19:01:07", "90000006", "CM Reboot Reason : POWER_ON"),
+            event(2, "06/27/26 15:30:00", "82001100", "RNG-RSP Power Exceeds DRW"),
+            event(4, "06/27/26 15:23:34", "84000500", "T3 time-out"),
+            event(1, "06/26/26 08:00:00", "68010300", "DHCP RENEW"),
+        ];
+        normalize(&mut events);
+        let order: Vec<&str> = events.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(order, ["90000006", "68010300", "84000500", "73050400", "82001100"]);
+
+        // Only the entries missing from the previous poll are new, and they
+        // come out oldest first
+        let seen = snapshot(&events[..2]);
+        let new: Vec<&str> = new_events(&events, &seen).iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(new, ["84000500", "73050400", "82001100"]);
+    }
+
+    #[test]
+    fn repeated_events_in_the_same_second_are_each_new() {
+        let timeout = |index| event(index, "06/27/26 15:23:34", "84000500", "T3 time-out");
+        let mut events = vec![timeout(3), timeout(1), timeout(2), timeout(2)];
+        normalize(&mut events);
+        assert_eq!(events.len(), 3);
+
+        // Only the first had been logged by the previous poll
+        let seen = snapshot(&events[..1]);
+        assert_eq!(new_events(&events, &seen).len(), 2);
+        assert!(new_events(&events, &snapshot(&events)).is_empty());
     }
 
     #[test]
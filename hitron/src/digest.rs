@@ -0,0 +1,122 @@
+use chrono::{DateTime, Datelike, Local, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Running minimum, maximum and mean of one reading
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Aggregate {
+    min: f64,
+    max: f64,
+    sum: f64,
+    count: u64,
+}
+
+impl Aggregate {
+    pub fn add(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    /// Render as "min to max unit (avg mean)", or "no readings"
+    fn describe(&self, unit: &str) -> String {
+        if self.count == 0 {
+            return "no readings".to_string();
+        }
+        format!(
+            "{:.1} to {:.1} {} (avg {:.1})",
+            self.min,
+            self.max,
+            unit,
+            self.sum / self.count as f64
+        )
+    }
+}
+
+/// Channel readings rolled up since the last daily digest
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DigestStats {
+    /// When this window started, as a Unix timestamp
+    pub since: Option<i64>,
+    pub polls: u64,
+    pub downstream_snr: Aggregate,
+    pub downstream_signal: Aggregate,
+    pub upstream_signal: Aggregate,
+    /// Uncorrectable codewords accrued across all downstream channels
+    pub uncorrectable: i64,
+    /// Anomalies newly raised, not counting polls they persisted through
+    pub anomalies: u64,
+    /// Day (days since 1 CE, local time) the last digest was sent, so it
+    /// goes out once however many polls fall in the digest hour
+    pub last_sent_day: Option<i32>,
+}
+
+impl DigestStats {
+    /// Note the start of a poll, opening the window on the first one
+    pub fn record_poll(&mut self, now: DateTime<Local>) {
+        self.since.get_or_insert(now.timestamp());
+        self.polls += 1;
+    }
+
+    /// Whether the digest should go out now, at `hour` local time
+    pub fn due(&self, now: DateTime<Local>, hour: u32) -> bool {
+        now.hour() == hour && self.last_sent_day != Some(now.num_days_from_ce())
+    }
+
+    /// Start a new window after sending the digest
+    pub fn reset(&mut self, now: DateTime<Local>) {
+        *self = DigestStats {
+            since: Some(now.timestamp()),
+            last_sent_day: Some(now.num_days_from_ce()),
+            ..DigestStats::default()
+        };
+    }
+}
+
+impl fmt::Display for DigestStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let since = self
+            .since
+            .and_then(|since| Local.timestamp_opt(since, 0).single())
+            .map_or_else(|| "startup".to_string(), |since| since.format("%Y-%m-%d %H:%M").to_string());
+        writeln!(f, "{} polls since {}", self.polls, since)?;
+        writeln!(f, "Downstream SNR: {}", self.downstream_snr.describe("dB"))?;
+        writeln!(f, "Downstream signal: {}", self.downstream_signal.describe("dBmV"))?;
+        writeln!(f, "Upstream signal: {}", self.upstream_signal.describe("dBmV"))?;
+        writeln!(f, "Uncorrectable errors: {}", self.uncorrectable)?;
+        write!(f, "Anomalies raised: {}", self.anomalies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digest_goes_out_once_a_day_and_resets() {
+        let morning = Local.with_ymd_and_hms(2026, 6, 27, 8, 5, 0).unwrap();
+        let mut stats = DigestStats::default();
+        stats.record_poll(morning);
+        stats.downstream_snr.add(38.0);
+        stats.downstream_snr.add(40.0);
+        stats.uncorrectable += 12;
+
+        assert!(!stats.due(morning, 9));
+        assert!(stats.due(morning, 8));
+        let summary = stats.to_string();
+        assert!(summary.contains("Downstream SNR: 38.0 to 40.0 dB (avg 39.0)"));
+        assert!(summary.contains("Upstream signal: no readings"));
+        assert!(summary.contains("Uncorrectable errors: 12"));
+
+        stats.reset(morning);
+        assert_eq!(stats.uncorrectable, 0);
+        assert!(!stats.due(morning + chrono::Duration::minutes(5), 8));
+        assert!(stats.due(morning + chrono::Duration::days(1), 8));
+    }
+}
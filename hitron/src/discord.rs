@@ -1,96 +1,728 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
 use serenity::http::Http;
+use serenity::model::id::ChannelId;
 use serenity::model::webhook::Webhook;
-use serenity::builder::ExecuteWebhook;
-use serenity::all::CreateEmbed;
+use serenity::builder::CreateMessage;
+use serenity::all::{CreateEmbed, CreateEmbedFooter};
 use crate::api::EventLog;
-use crate::monitor::ChannelAnomaly;
+use crate::digest::DigestStats;
+use crate::monitor::{ChannelAnomaly, Severity};
+use crate::notifier::{self, Notifier};
+
+/// Where notifications are posted
+#[derive(Debug, Clone)]
+pub enum Destination {
+    /// An incoming webhook URL, optionally posting into one of the
+    /// channel's threads
+    Webhook { url: String, thread_id: Option<u64> },
+    /// A channel posted to as a bot, for setups that need features
+    /// webhooks lack (reactions, replies, reading channel state)
+    Channel { bot_token: String, channel_id: u64 },
+}
+
+/// A webhook URL that posts into a thread of the webhook's channel
+fn in_thread(url: &str, thread_id: u64) -> Result<String> {
+    let mut url = reqwest::Url::parse(url)?;
+    url.query_pairs_mut().append_pair("thread_id", &thread_id.to_string());
+    Ok(url.into())
+}
+
+/// Who alerts ping: a role, a user or both
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mentions {
+    pub role_id: Option<u64>,
+    pub user_id: Option<u64>,
+    /// Only ping for critical events and alerts, not warnings
+    pub critical_only: bool,
+}
+
+impl Mentions {
+    /// Message content pinging everyone set, or `None` if nobody is
+    fn content(&self) -> Option<String> {
+        let mentions: Vec<String> = self
+            .role_id
+            .map(|id| format!("<@&{}>", id))
+            .into_iter()
+            .chain(self.user_id.map(|id| format!("<@{}>", id)))
+            .collect();
+        (!mentions.is_empty()).then(|| mentions.join(" "))
+    }
+}
+
+/// A resolved destination, ready to send to
+enum Target {
+    /// Posted to directly rather than through serenity, so rate limits can
+    /// be waited out a bounded number of times
+    Webhook(String),
+    Channel(ChannelId),
+}
 
 pub struct DiscordNotifier {
-    webhook: Webhook,
+    target: Target,
+    /// Where everything short of critical goes, without pinging, when set;
+    /// otherwise it all goes to `target`
+    digest_target: Option<Target>,
     http: Http,
-    role_id: Option<u64>,
+    client: reqwest::Client,
+    mentions: Mentions,
+    footer: Option<String>,
+    /// Prefixed to every title, to tell modems apart when monitoring several
+    label: Option<String>,
+    /// Prefixed to every title ahead of the label, e.g. to name the site
+    title_prefix: Option<String>,
+    /// Consecutive polls after which a channel alert is escalated; when
+    /// unset every channel alert pings
+    escalate_after: Option<u32>,
+    /// Log messages instead of posting them
+    dry_run: bool,
 }
 
 impl DiscordNotifier {
-    /// Create a new Discord notifier from a webhook URL
-    pub async fn new(webhook_url: &str, role_id: Option<u64>) -> Result<Self> {
-        let http = Http::new("");
-        let webhook = Webhook::from_url(&http, webhook_url).await?;
-        Ok(Self { webhook, http, role_id })
+    /// Create a new Discord notifier posting via a webhook or as a bot, and
+    /// optionally routing everything short of critical to a second webhook.
+    /// A dry-run notifier checks webhook URLs' shape but never contacts
+    /// Discord.
+    pub async fn new(
+        destination: Destination,
+        digest_webhook: Option<String>,
+        mentions: Mentions,
+        dry_run: bool,
+    ) -> Result<Self> {
+        let (target, http) = match destination {
+            Destination::Webhook { url, thread_id } => {
+                let http = Http::new("");
+                check_webhook(&http, &url, "--webhook", dry_run).await?;
+                let url = match thread_id {
+                    Some(thread_id) => in_thread(&url, thread_id)?,
+                    None => url,
+                };
+                (Target::Webhook(url), http)
+            }
+            Destination::Channel { bot_token, channel_id } => {
+                (Target::Channel(ChannelId::new(channel_id)), Http::new(&bot_token))
+            }
+        };
+        let digest_target = match digest_webhook {
+            Some(url) => {
+                check_webhook(&Http::new(""), &url, "--digest-webhook", dry_run).await?;
+                Some(Target::Webhook(url))
+            }
+            None => None,
+        };
+        Ok(Self {
+            target,
+            digest_target,
+            http,
+            client: reqwest::Client::new(),
+            mentions,
+            footer: None,
+            label: None,
+            title_prefix: None,
+            escalate_after: None,
+            dry_run,
+        })
+    }
+
+    /// Apply the settings shared by every embed this notifier sends
+    fn finish_embed(&self, title: &str, embed: CreateEmbed) -> CreateEmbed {
+        let embed = embed.title(notifier::decorate_title(
+            self.title_prefix.as_deref(),
+            self.label.as_deref(),
+            title,
+        ));
+        match &self.footer {
+            Some(footer) => embed.footer(CreateEmbedFooter::new(footer)),
+            None => embed,
+        }
     }
 
-    /// Send an event log entry to Discord
-    pub async fn send_event(&self, event: &EventLog) -> Result<()> {
-        // Create an embed with color based on priority
-        let color = match event.priority {
+    /// Post a message with one embed to the configured destination, or to
+    /// the digest webhook without pinging if there is one and the message
+    /// isn't critical
+    async fn execute(&self, message: Message) -> Result<()> {
+        let Message { content, title, embed, critical } = message;
+        let (target, content) = match &self.digest_target {
+            Some(digest_target) if !critical => (digest_target, None),
+            _ => (&self.target, content),
+        };
+        self.post(target, content, &title, embed).await
+    }
+
+    /// Post a message with one embed to one destination
+    async fn post(&self, target: &Target, content: Option<String>, title: &str, embed: CreateEmbed) -> Result<()> {
+        let embed = self.finish_embed(title, embed);
+        if self.dry_run {
+            let embed = serde_json::to_value(&embed)?;
+            notifier::log_dry_run(
+                embed["title"].as_str().unwrap_or_default(),
+                embed["description"].as_str().unwrap_or_default(),
+                content.as_deref(),
+            );
+            return Ok(());
+        }
+        match target {
+            Target::Webhook(url) => {
+                let mut body = serde_json::json!({ "embeds": [embed] });
+                if let Some(content) = content {
+                    body["content"] = content.into();
+                }
+                notifier::post_webhook(&self.client, url, &body).await?;
+            }
+            Target::Channel(channel_id) => {
+                let mut builder = CreateMessage::new().embed(embed);
+                if let Some(content) = content {
+                    builder = builder.content(content);
+                }
+                channel_id.send_message(&self.http, builder).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn set_footer(&mut self, footer: String) {
+        self.footer = Some(footer);
+    }
+
+    fn set_label(&mut self, label: String) {
+        self.label = Some(label);
+    }
+
+    fn set_title_prefix(&mut self, prefix: String) {
+        self.title_prefix = Some(prefix);
+    }
+
+    fn set_escalate_after(&mut self, polls: Option<u32>) {
+        self.escalate_after = polls;
+    }
+
+    fn escalate_after(&self) -> Option<u32> {
+        self.escalate_after
+    }
+
+    async fn send_event(&self, event: &EventLog, urgent: bool) -> Result<()> {
+        self.execute(event_message(event, urgent, self.mentions)).await
+    }
+
+    async fn send_channel_alert(&self, anomaly: &ChannelAnomaly, consecutive: u32, suppressed: u32) -> Result<()> {
+        self.execute(channel_alert_message(
+            anomaly,
+            consecutive,
+            suppressed,
+            self.mentions,
+            self.escalate_after,
+        ))
+        .await
+    }
+
+    async fn send_recovery(&self, anomaly: &ChannelAnomaly) -> Result<()> {
+        self.execute(recovery_message(anomaly)).await
+    }
+
+    async fn send_firmware_change(&self, old: &str, new: &str) -> Result<()> {
+        self.execute(firmware_change_message(old, new)).await
+    }
+
+    async fn send_digest(&self, stats: &DigestStats) -> Result<()> {
+        self.execute(digest_message(stats)).await
+    }
+
+    async fn send_test(&self) -> Result<()> {
+        let Message { content, title, embed, .. } = test_message(self.mentions);
+        if let Some(digest_target) = &self.digest_target {
+            self.post(digest_target, None, &title, embed.clone()).await?;
+        }
+        self.post(&self.target, content, &title, embed).await
+    }
+}
+
+/// A notification ready to send. The title is kept out of the embed so
+/// the notifier can label it.
+struct Message {
+    /// Message content, used for role and user pings
+    content: Option<String>,
+    title: String,
+    embed: CreateEmbed,
+    /// Sent to the main destination even when there is a digest webhook
+    critical: bool,
+}
+
+/// Check the webhook URL given as `option`: its shape, so a mistyped URL
+/// gets a clear message rather than an HTTP error, then, unless on a dry
+/// run, that it still exists, so a deleted webhook fails at startup rather
+/// than on the first alert. The URL itself is never shown, as it holds the
+/// webhook's token.
+async fn check_webhook(http: &Http, url: &str, option: &str, dry_run: bool) -> Result<()> {
+    let valid = reqwest::Url::parse(url)
+        .ok()
+        .is_some_and(|parsed| serenity::utils::parse_webhook(&parsed).is_some());
+    if !valid {
+        bail!(
+            "{} doesn't look like a Discord webhook URL; expected https://discord.com/api/webhooks/<id>/<token>, as shown by Edit Channel > Integrations > Webhooks > Copy Webhook URL",
+            option
+        );
+    }
+    if !dry_run {
+        Webhook::from_url(http, url)
+            .await
+            .with_context(|| format!("Failed to look up the {} webhook; it may have been deleted", option))?;
+    }
+    Ok(())
+}
+
+/// Build the message for an event log entry
+fn event_message(event: &EventLog, urgent: bool, mentions: Mentions) -> Message {
+    // Create an embed with color based on priority
+    let color = if urgent {
+        0xFF0000 // Red
+    } else {
+        match event.priority {
             crate::api::EventPriority::Critical => 0xFF0000, // Red
             crate::api::EventPriority::Warning => 0xFFA500,  // Orange
             crate::api::EventPriority::Notice => 0x0099FF,   // Blue
-            crate::api::EventPriority::Other => 0x808080,    // Gray
+            crate::api::EventPriority::Other(_) => 0x808080, // Gray
+        }
+    };
+
+    let description = format!(
+        "**Time:** {}\n**Type:** {}\n**Event:** {}",
+        event.time, event.event_type, event.event
+    );
+
+    let embed = CreateEmbed::new()
+        .color(color)
+        .description(description)
+        .timestamp(serenity::model::Timestamp::now());
+
+    // Add mentions if specified, but only for events severe enough
+    // to warrant it (critical/warning) -- notice and other are FYI-only
+    let severe = urgent
+        || match event.priority {
+            crate::api::EventPriority::Critical => true,
+            crate::api::EventPriority::Warning => !mentions.critical_only,
+            _ => false,
         };
+    let content = mentions.content().filter(|_| severe);
 
-        let description = format!(
-            "**Time:** {}\n**Type:** {}\n**Event:** {}",
-            event.time, event.event_type, event.event
-        );
+    Message {
+        content,
+        title: format!("Modem Event: {}", event.priority),
+        embed,
+        critical: urgent || event.priority == crate::api::EventPriority::Critical,
+    }
+}
+
+/// Build the message for a channel anomaly seen on `consecutive` polls in
+/// a row, `suppressed` times since it was last sent
+fn channel_alert_message(
+    anomaly: &ChannelAnomaly,
+    consecutive: u32,
+    suppressed: u32,
+    mentions: Mentions,
+    escalate_after: Option<u32>,
+) -> Message {
+    let mut severity = anomaly.severity();
+    let title = alert_title(anomaly);
+    let mut description = anomaly.to_string();
+
+    // Without an escalation threshold every alert pings; with one, only
+    // a problem that keeps coming back does
+    let escalated = escalate_after.is_some_and(|after| consecutive > after);
+    if escalated {
+        severity = Severity::Critical;
+        description = format!("{}\n\n**Seen on {} consecutive polls**", description.trim_end(), consecutive);
+    }
+    if suppressed > 0 {
+        description = format!("{}\n\n(still active, {} occurrences suppressed)", description.trim_end(), suppressed);
+    }
+
+    let embed = CreateEmbed::new()
+        .color(severity_color(severity))
+        .description(description)
+        .field("Severity", severity.to_string(), true)
+        .timestamp(serenity::model::Timestamp::now());
+
+    let pings = (escalated || escalate_after.is_none()) && (severity == Severity::Critical || !mentions.critical_only);
+    let content = mentions.content().filter(|_| pings);
+
+    Message {
+        content,
+        title,
+        embed,
+        critical: severity == Severity::Critical,
+    }
+}
+
+/// Build the notice for a cleared channel condition, showing the last
+/// reading taken while it was active. Never pings.
+fn recovery_message(anomaly: &ChannelAnomaly) -> Message {
+    let embed = CreateEmbed::new()
+        .color(0x00C853) // Green
+        .description(format!("No longer detected:\n{}", anomaly))
+        .timestamp(serenity::model::Timestamp::now());
 
-        let embed = CreateEmbed::new()
-            .title(format!("Modem Event: {}", event.priority))
-            .color(color)
-            .description(description)
-            .timestamp(serenity::model::Timestamp::now());
-
-        let mut builder = ExecuteWebhook::new().embed(embed);
-
-        // Add role mention if specified, but only for events severe enough
-        // to warrant it (critical/warning) -- notice and other are FYI-only
-        if let Some(role_id) = self.role_id {
-            if matches!(
-                event.priority,
-                crate::api::EventPriority::Critical | crate::api::EventPriority::Warning
-            ) {
-                builder = builder.content(format!("<@&{}>", role_id));
+    Message {
+        content: None,
+        title: "✅ Recovered".to_string(),
+        embed,
+        critical: false,
+    }
+}
+
+/// Build the notice for a firmware change. Never pings: a firmware push is
+/// context for other alerts rather than a problem in itself.
+fn firmware_change_message(old: &str, new: &str) -> Message {
+    let embed = CreateEmbed::new()
+        .color(0x0099FF) // Blue
+        .description(format!("**Previous:** {}\n**Current:** {}", old, new))
+        .timestamp(serenity::model::Timestamp::now());
+
+    Message {
+        content: None,
+        title: "ℹ️ Modem Firmware Changed".to_string(),
+        embed,
+        critical: false,
+    }
+}
+
+/// Build the setup check message. Pings the role and user, so the mentions
+/// can be checked too.
+fn test_message(mentions: Mentions) -> Message {
+    let embed = CreateEmbed::new()
+        .color(0x00FF00) // Green
+        .description("Notifications are set up correctly.")
+        .timestamp(serenity::model::Timestamp::now());
+
+    Message {
+        content: mentions.content(),
+        title: "Hitron monitor test message".to_string(),
+        embed,
+        critical: true,
+    }
+}
+
+/// Build the daily summary. Never pings: it is a heartbeat, not an alert.
+fn digest_message(stats: &DigestStats) -> Message {
+    let embed = CreateEmbed::new()
+        .color(0x0099FF) // Blue
+        .description(stats.to_string())
+        .timestamp(serenity::model::Timestamp::now());
+
+    Message {
+        content: None,
+        title: "📊 Daily Modem Summary".to_string(),
+        embed,
+        critical: false,
+    }
+}
+
+/// Embed color for an anomaly's severity
+fn severity_color(severity: Severity) -> u32 {
+    match severity {
+        Severity::Critical => 0xFF0000, // Red
+        Severity::Warning => 0xFFA500,  // Orange
+        Severity::Info => 0x0099FF,     // Blue
+    }
+}
+
+/// Determine the embed title based on anomaly type
+pub(crate) fn alert_title(anomaly: &ChannelAnomaly) -> String {
+    let title = match anomaly {
+        ChannelAnomaly::DownstreamLowSNR { .. } => "⚠️ Low SNR Detected",
+        ChannelAnomaly::DownstreamSignalOutOfRange { .. } => "⚠️ Downstream Signal Out of Range",
+        ChannelAnomaly::UpstreamSignalOutOfRange { .. } => "⚠️ Upstream Signal Out of Range",
+        ChannelAnomaly::UpstreamLowSNR { .. } => "⚠️ Low Upstream SNR Detected",
+        ChannelAnomaly::OfdmLowSNR { .. } => "⚠️ Low OFDM MER Detected",
+        ChannelAnomaly::OfdmPowerOutOfRange { .. } => "⚠️ OFDM Power Out of Range",
+        ChannelAnomaly::OfdmaPowerOutOfRange { .. } => "⚠️ OFDMA Upstream Power Out of Range",
+        ChannelAnomaly::HighErrorRate { triggered_channels, .. } => {
+            if triggered_channels.len() == 1 {
+                "🔴 High Error Rate Detected"
+            } else {
+                "🔴 High Error Rates Detected"
             }
         }
+        ChannelAnomaly::HighLifetimeErrorRate { .. } => "🔴 High Lifetime Error Rate",
+        ChannelAnomaly::HighCorrectedRate { .. } => "⚠️ High Corrected Error Rate",
+        ChannelAnomaly::PrimaryChannelChanged { .. } => "⚠️ Primary Channel Changed",
+        ChannelAnomaly::ChannelRenegotiated { .. } => "⚠️ Channel Renegotiated",
+        ChannelAnomaly::PartialService { .. } => "🔴 Partial Service",
+        ChannelAnomaly::SnrMarginFalling { .. } => "⚠️ SNR Margin Falling",
+        ChannelAnomaly::RapidDegradation { .. } => "📉 Rapid Degradation",
+        ChannelAnomaly::NetworkAccessDenied { .. } => "🔴 Network Access Denied",
+        ChannelAnomaly::StalledTraffic { .. } => "🔴 Traffic Stalled",
+        ChannelAnomaly::ModemUiStalled { .. } => "🔴 Modem Web Interface Stalled",
+        ChannelAnomaly::WanAddressChanged { .. } => "🌐 WAN Address Changed",
+        ChannelAnomaly::ModemRebooted { .. } => "🔴 Modem Rebooted",
+        ChannelAnomaly::RebootLogged { .. } => "🔄 Modem Rebooted",
+        ChannelAnomaly::ChannelCountDropped { .. } => "⚠️ Channels Dropped",
+        ChannelAnomaly::NoChannelsReported { .. } => "🔴 No Channels Reported",
+        ChannelAnomaly::LinkStateChanged { to, .. } if !to.up => "🔴 Link Down",
+        ChannelAnomaly::LinkStateChanged { .. } => "🔌 Link Changed",
+        ChannelAnomaly::Grouped(anomalies) => {
+            // Groups are never empty; title them after their members
+            return format!("{} ({} channels)", alert_title(&anomalies[0]), anomalies.len());
+        }
+    };
+    title.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::EventPriority;
+
+    const ROLE: Mentions = Mentions { role_id: Some(42), user_id: None, critical_only: false };
+    const NOBODY: Mentions = Mentions { role_id: None, user_id: None, critical_only: false };
+
+    fn event(priority: EventPriority) -> EventLog {
+        EventLog {
+            index: 1,
+            time: "06/27/26 15:23:34".to_string(),
+            event_type: "82001100".to_string(),
+            priority,
+            event: "RNG-RSP".to_string(),
+            timestamp: None,
+        }
+    }
 
-        self.webhook.execute(&self.http, false, builder).await?;
+    fn low_snr() -> ChannelAnomaly {
+        ChannelAnomaly::DownstreamLowSNR { channel_id: 1, snr: 30.0, threshold: 33.0 }
+    }
 
-        Ok(())
+    fn color(message: &Message) -> u64 {
+        serde_json::to_value(&message.embed).unwrap()["color"].as_u64().unwrap()
+    }
+
+    fn pings(message: &Message) -> bool {
+        message.content.as_deref() == Some("<@&42>")
+    }
+
+    #[test]
+    fn event_color_follows_priority() {
+        assert_eq!(color(&event_message(&event(EventPriority::Critical), false, ROLE)), 0xFF0000);
+        assert_eq!(color(&event_message(&event(EventPriority::Warning), false, ROLE)), 0xFFA500);
+        assert_eq!(color(&event_message(&event(EventPriority::Notice), false, ROLE)), 0x0099FF);
+        assert_eq!(color(&event_message(&event(EventPriority::Other("debug".into())), false, ROLE)), 0x808080);
+    }
+
+    #[test]
+    fn only_severe_events_ping() {
+        assert!(pings(&event_message(&event(EventPriority::Critical), false, ROLE)));
+        assert!(pings(&event_message(&event(EventPriority::Warning), false, ROLE)));
+        assert!(!pings(&event_message(&event(EventPriority::Notice), false, ROLE)));
+        assert!(!pings(&event_message(&event(EventPriority::Critical), false, NOBODY)));
+    }
+
+    #[test]
+    fn urgent_event_is_critical() {
+        let message = event_message(&event(EventPriority::Notice), true, ROLE);
+        assert_eq!(color(&message), 0xFF0000);
+        assert!(pings(&message));
+    }
+
+    #[test]
+    fn channel_alert_pings_without_escalation_threshold() {
+        let message = channel_alert_message(&low_snr(), 1, 0, ROLE, None);
+        assert_eq!(color(&message), 0xFFA500);
+        assert!(pings(&message));
+    }
+
+    #[test]
+    fn channel_alert_escalates_after_threshold() {
+        let quiet = channel_alert_message(&low_snr(), 3, 0, ROLE, Some(3));
+        assert_eq!(color(&quiet), 0xFFA500);
+        assert!(!pings(&quiet));
+
+        let escalated = channel_alert_message(&low_snr(), 4, 0, ROLE, Some(3));
+        assert_eq!(color(&escalated), 0xFF0000);
+        assert!(pings(&escalated));
     }
 
-    /// Send a channel anomaly alert to Discord
-    pub async fn send_channel_alert(&self, anomaly: &ChannelAnomaly) -> Result<()> {
-        // Determine color and title based on anomaly type
-        let (color, title) = match anomaly {
-            ChannelAnomaly::DownstreamLowSNR { .. } => (0xFFA500, "⚠️ Low SNR Detected"),
-            ChannelAnomaly::DownstreamSignalOutOfRange { .. } => (0xFFA500, "⚠️ Downstream Signal Out of Range"),
-            ChannelAnomaly::UpstreamSignalOutOfRange { .. } => (0xFFA500, "⚠️ Upstream Signal Out of Range"),
-            ChannelAnomaly::HighErrorRate { triggered_channels, .. } => {
-                let title = if triggered_channels.len() == 1 {
-                    "🔴 High Error Rate Detected"
+    #[test]
+    fn suppressed_count_is_shown() {
+        let message = channel_alert_message(&low_snr(), 5, 14, ROLE, None);
+        let description = serde_json::to_value(message.embed).unwrap()["description"].as_str().unwrap().to_string();
+        assert!(description.ends_with("(still active, 14 occurrences suppressed)"));
+    }
+
+    #[test]
+    fn grouped_alert_is_styled_after_its_members() {
+        let grouped = ChannelAnomaly::Grouped(vec![low_snr(), low_snr()]);
+        assert_eq!(color(&channel_alert_message(&grouped, 1, 0, ROLE, None)), 0xFFA500);
+        assert_eq!(alert_title(&grouped), "⚠️ Low SNR Detected (2 channels)");
+    }
+
+    #[test]
+    fn severity_is_shown_and_can_limit_pings() {
+        let message = channel_alert_message(&low_snr(), 1, 0, ROLE, None);
+        let embed = serde_json::to_value(message.embed).unwrap();
+        assert_eq!(embed["fields"][0]["name"], "Severity");
+        assert_eq!(embed["fields"][0]["value"], "Warning");
+        assert!(message.content.is_some());
+
+        let critical_only = Mentions { critical_only: true, ..ROLE };
+        assert!(channel_alert_message(&low_snr(), 1, 0, critical_only, None).content.is_none());
+        let denied = ChannelAnomaly::NetworkAccessDenied { provisioning_state: String::new() };
+        assert!(channel_alert_message(&denied, 1, 0, critical_only, None).content.is_some());
+        // Escalating makes an alert critical
+        assert!(channel_alert_message(&low_snr(), 3, 0, critical_only, Some(2)).content.is_some());
+        assert!(event_message(&event(EventPriority::Warning), false, critical_only).content.is_none());
+    }
+
+    #[test]
+    fn recovery_is_green_and_quiet() {
+        let message = recovery_message(&low_snr());
+        assert_eq!(color(&message), 0x00C853);
+        assert!(message.content.is_none());
+    }
+
+    #[test]
+    fn firmware_change_never_pings() {
+        let message = firmware_change_message("1.0", "2.0");
+        assert_eq!(color(&message), 0x0099FF);
+        assert!(message.content.is_none());
+    }
+
+    #[test]
+    fn test_message_pings_the_role() {
+        assert_eq!(test_message(ROLE).content.as_deref(), Some("<@&42>"));
+        assert!(test_message(NOBODY).content.is_none());
+    }
+
+    #[test]
+    fn role_and_user_are_both_pinged() {
+        let user = Mentions { user_id: Some(7), ..NOBODY };
+        let both = Mentions { role_id: Some(42), ..user };
+        assert_eq!(test_message(user).content.as_deref(), Some("<@7>"));
+        assert_eq!(test_message(both).content.as_deref(), Some("<@&42> <@7>"));
+
+        let critical = event_message(&event(EventPriority::Critical), false, both);
+        assert_eq!(critical.content.as_deref(), Some("<@&42> <@7>"));
+        assert!(event_message(&event(EventPriority::Notice), false, both).content.is_none());
+    }
+
+    /// Serve a webhook that rate-limits the first `limited` posts, counting
+    /// every post it receives
+    async fn mock_webhook(limited: u32) -> (DiscordNotifier, std::sync::Arc<std::sync::atomic::AtomicU32>) {
+        use axum::http::StatusCode;
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        let posts = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&posts);
+        let app = axum::Router::new().route(
+            "/webhook",
+            axum::routing::post(move || async move {
+                if counter.fetch_add(1, Ordering::SeqCst) < limited {
+                    (StatusCode::TOO_MANY_REQUESTS, r#"{"message":"You are being rate limited.","retry_after":0.01,"global":false}"#)
                 } else {
-                    "🔴 High Error Rates Detected"
-                };
-                (0xFF0000, title)
-            },
+                    (StatusCode::NO_CONTENT, "")
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/webhook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let notifier = DiscordNotifier {
+            target: Target::Webhook(url),
+            digest_target: None,
+            http: Http::new(""),
+            client: reqwest::Client::new(),
+            mentions: NOBODY,
+            footer: None,
+            label: None,
+            title_prefix: None,
+            escalate_after: None,
+            dry_run: false,
         };
+        (notifier, posts)
+    }
 
-        let embed = CreateEmbed::new()
-            .title(title)
-            .color(color)
-            .description(anomaly.to_string())
-            .timestamp(serenity::model::Timestamp::now());
+    #[tokio::test]
+    async fn rate_limited_webhook_is_resent() {
+        let (notifier, posts) = mock_webhook(1).await;
+        notifier.send_recovery(&low_snr()).await.unwrap();
+        assert_eq!(posts.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
 
-        let mut builder = ExecuteWebhook::new().embed(embed);
+    #[tokio::test]
+    async fn rate_limit_retries_are_capped() {
+        let (notifier, posts) = mock_webhook(u32::MAX).await;
+        assert!(notifier.send_recovery(&low_snr()).await.is_err());
+        assert_eq!(posts.load(std::sync::atomic::Ordering::SeqCst), notifier::RATE_LIMIT_RETRIES + 1);
+    }
 
-        // Add role mention if specified
-        if let Some(role_id) = self.role_id {
-            builder = builder.content(format!("<@&{}>", role_id));
-        }
+    #[tokio::test]
+    async fn only_critical_goes_to_the_main_webhook_with_a_digest_webhook() {
+        use std::sync::atomic::Ordering;
 
-        self.webhook.execute(&self.http, false, builder).await?;
+        let (mut notifier, main_posts) = mock_webhook(0).await;
+        let (digest, digest_posts) = mock_webhook(0).await;
+        notifier.digest_target = Some(digest.target);
 
-        Ok(())
+        notifier.send_channel_alert(&low_snr(), 1, 0).await.unwrap();
+        notifier.send_event(&event(EventPriority::Warning), false).await.unwrap();
+        notifier.send_recovery(&low_snr()).await.unwrap();
+        assert_eq!((main_posts.load(Ordering::SeqCst), digest_posts.load(Ordering::SeqCst)), (0, 3));
+
+        notifier.send_event(&event(EventPriority::Critical), false).await.unwrap();
+        notifier.send_event(&event(EventPriority::Notice), true).await.unwrap();
+        assert_eq!((main_posts.load(Ordering::SeqCst), digest_posts.load(Ordering::SeqCst)), (2, 3));
+    }
+
+    #[tokio::test]
+    async fn dry_run_never_posts() {
+        let (mut notifier, posts) = mock_webhook(0).await;
+        notifier.dry_run = true;
+        notifier.send_channel_alert(&low_snr(), 1, 0).await.unwrap();
+        assert_eq!(posts.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn title_prefix_goes_before_the_label() {
+        let (mut notifier, _) = mock_webhook(0).await;
+        let title = |notifier: &DiscordNotifier| {
+            let embed = notifier.finish_embed("✅ Recovered", CreateEmbed::new());
+            serde_json::to_value(embed).unwrap()["title"].as_str().unwrap().to_string()
+        };
+        assert_eq!(title(&notifier), "✅ Recovered");
+        notifier.set_label("failover".to_string());
+        notifier.set_title_prefix("🏠 Home".to_string());
+        assert_eq!(title(&notifier), "🏠 Home [failover] ✅ Recovered");
+    }
+
+    #[tokio::test]
+    async fn dry_run_checks_webhook_url_offline() {
+        let url = format!("https://discord.com/api/webhooks/123456789012345678/{}", "x".repeat(64));
+        let webhook = Destination::Webhook { url, thread_id: None };
+        assert!(DiscordNotifier::new(webhook.clone(), None, NOBODY, true).await.is_ok());
+        let bad = Some("https://example.com/not-a-webhook".to_string());
+        assert!(DiscordNotifier::new(webhook, bad, NOBODY, true).await.is_err());
+        let bad = "https://example.com/not-a-webhook".to_string();
+        let webhook = Destination::Webhook { url: bad, thread_id: None };
+        assert!(DiscordNotifier::new(webhook.clone(), None, NOBODY, true).await.is_err());
+
+        // Checked before contacting Discord, with the expected format
+        let Err(e) = DiscordNotifier::new(webhook, None, NOBODY, false).await else {
+            panic!("a malformed webhook URL was accepted");
+        };
+        assert!(e.to_string().starts_with("--webhook doesn't look like a Discord webhook URL"), "{}", e);
+    }
+
+    #[test]
+    fn thread_id_is_added_to_the_webhook_query() {
+        assert_eq!(
+            in_thread("https://discord.com/api/webhooks/1/abc", 99).unwrap(),
+            "https://discord.com/api/webhooks/1/abc?thread_id=99"
+        );
+        assert_eq!(
+            in_thread("https://discord.com/api/webhooks/1/abc?wait=true", 99).unwrap(),
+            "https://discord.com/api/webhooks/1/abc?wait=true&thread_id=99"
+        );
     }
 }
@@ -0,0 +1,38 @@
+use crate::api::{self, ApiClient};
+use anyhow::Result;
+
+/// Fetch one data endpoint and print its body to stdout: indented if it is
+/// JSON, keeping the modem's key order, and as is otherwise
+pub async fn run(client: &ApiClient, endpoint: &str) -> Result<()> {
+    let body = api::get_raw(client, endpoint).await?;
+    println!("{}", pretty(&body));
+    Ok(())
+}
+
+/// Indent a JSON body without reordering its keys, or return it unchanged
+/// if it isn't JSON (e.g. an HTML error page)
+fn pretty(body: &str) -> String {
+    let mut out = Vec::new();
+    let mut deserializer = serde_json::Deserializer::from_str(body);
+    let mut serializer = serde_json::Serializer::pretty(&mut out);
+    let transcoded = serde_transcode::transcode(&mut deserializer, &mut serializer).and_then(|()| deserializer.end());
+    match transcoded {
+        Ok(()) => String::from_utf8(out).expect("serde_json writes UTF-8"),
+        Err(_) => body.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_is_indented_in_the_modem_order() {
+        assert_eq!(
+            pretty(r#"[{"portId":"1","frequency":"591000000"}]"#),
+            "[\n  {\n    \"portId\": \"1\",\n    \"frequency\": \"591000000\"\n  }\n]"
+        );
+        assert_eq!(pretty("<html>Please wait</html>"), "<html>Please wait</html>");
+        assert_eq!(pretty(r#"[{"portId":"1""#), r#"[{"portId":"1""#);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::api::EventLog;
+use crate::monitor::{ChannelAnomaly, Direction};
+use anyhow::Result;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// Machine-readable log with one JSON object per line for every event sent
+/// and every anomaly detected, for post-processing with tools like `jq`.
+/// Clones share a lock, so the per-modem tasks never interleave lines.
+#[derive(Clone)]
+pub struct JsonLog {
+    path: PathBuf,
+    /// Which modem the records are about, when monitoring several
+    label: Option<String>,
+    write_lock: Arc<Mutex<()>>,
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modem: Option<&'a str>,
+    /// "event", "anomaly" or "recovery"
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    event_type: Option<&'a str>,
+    /// The channel an anomaly concerns, if it concerns exactly one
+    channel_id: Option<u32>,
+    /// Every channel an anomaly concerns
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    channels: Vec<AffectedChannel>,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct AffectedChannel {
+    direction: Direction,
+    channel_id: u32,
+}
+
+impl JsonLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            label: None,
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Tag every record with the modem it concerns
+    pub fn set_label(&mut self, label: String) {
+        self.label = Some(label);
+    }
+
+    /// Record a modem event that was sent as a notification
+    pub async fn append_event(&self, event: &EventLog) -> Result<()> {
+        self.append(Record {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            modem: self.label.as_deref(),
+            kind: "event",
+            priority: Some(event.priority.to_string()),
+            event_type: Some(&event.event_type),
+            channel_id: None,
+            channels: Vec::new(),
+            message: format!("{} {}", event.time, event.event),
+        })
+        .await
+    }
+
+    /// Record a detected channel anomaly, whether or not it was sent
+    pub async fn append_anomaly(&self, anomaly: &ChannelAnomaly) -> Result<()> {
+        self.append(self.anomaly_record("anomaly", anomaly)).await
+    }
+
+    /// Record a channel condition clearing
+    pub async fn append_recovery(&self, anomaly: &ChannelAnomaly) -> Result<()> {
+        self.append(self.anomaly_record("recovery", anomaly)).await
+    }
+
+    fn anomaly_record(&self, kind: &'static str, anomaly: &ChannelAnomaly) -> Record<'_> {
+        Record {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            modem: self.label.as_deref(),
+            kind,
+            priority: None,
+            event_type: None,
+            channel_id: anomaly.channel_id(),
+            channels: anomaly
+                .affected_channels()
+                .into_iter()
+                .map(|(direction, channel_id)| AffectedChannel { direction, channel_id })
+                .collect(),
+            message: anomaly.to_string().trim_end().to_string(),
+        }
+    }
+
+    async fn append(&self, record: Record<'_>) -> Result<()> {
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+
+        let _guard = self.write_lock.lock().await;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path).await?;
+        file.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn concurrent_records_stay_on_their_own_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = JsonLog::new(dir.path().join("alerts.jsonl"));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|channel_id| {
+                let mut log = log.clone();
+                log.set_label(format!("modem{}", channel_id));
+                tokio::spawn(async move {
+                    let anomaly = ChannelAnomaly::DownstreamLowSNR { channel_id, snr: 30.0, threshold: 33.0 };
+                    for _ in 0..10 {
+                        log.append_anomaly(&anomaly).await.unwrap();
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let text = fs::read_to_string(dir.path().join("alerts.jsonl")).await.unwrap();
+        let records: Vec<serde_json::Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(records.len(), 80);
+        let record = &records[0];
+        assert_eq!(record["type"], "anomaly");
+        assert_eq!(record["modem"], format!("modem{}", record["channel_id"]));
+        assert_eq!(record["channels"][0]["direction"], "downstream");
+        assert!(record["message"].as_str().unwrap().contains("has low SNR"));
+    }
+}
@@ -0,0 +1,6 @@
+//! The Hitron modem client and channel checks behind `hitron-monitor`, for
+//! embedding in other services
+
+pub mod api;
+pub mod digest;
+pub mod monitor;
@@ -1,32 +1,164 @@
-mod api;
+mod alert_log;
+mod config;
+mod csv_export;
+mod decimation;
+mod dump;
 mod dedup;
 mod discord;
-mod monitor;
+mod json_log;
+mod metrics;
+mod notifier;
+mod notify_queue;
+mod probe;
+mod replay;
+mod server;
+mod slack;
+mod spectrum;
+mod state;
+mod status;
+mod tail;
 
-use anyhow::Result;
-use clap::Parser;
-use std::collections::HashSet;
+use anyhow::{bail, Context, Result};
+use hitron_monitor::{api, digest, monitor};
+use clap::{CommandFactory, Parser, Subcommand};
+use notifier::Notifier;
+use rand::Rng;
+use regex::Regex;
+use std::io::IsTerminal;
+use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 use std::path::PathBuf;
-use tokio::time;
-use tokio::fs;
-use tracing::{info, error, debug, warn};
+use std::process::ExitCode;
+use tokio::sync::watch;
+use tokio::{signal, time};
+use tracing::{info, error, debug, warn, info_span, Instrument, Span};
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
 
 #[derive(Parser, Debug)]
-#[command(author, version, about = "Monitor Hitron modem event logs and send notifications to Discord", long_about = None)]
+#[command(author, version, about = "Monitor Hitron modem event logs and send notifications to Discord or Slack", long_about = None)]
 struct Args {
-    /// Discord webhook URL (can also be set via DISCORD_WEBHOOK env var)
-    #[arg(short, long, env = "DISCORD_WEBHOOK")]
-    webhook: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// TOML file of options, keyed by their long names (e.g.
+    /// `interval = 120`); options given on the command line or through the
+    /// environment win
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Disable colored output (also disabled when stdout is not a terminal
+    /// or NO_COLOR is set)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Most verbose log level shown (error, warn, info, debug or trace);
+    /// debug shows how long each endpoint took to answer
+    #[arg(long, global = true, default_value = "info")]
+    log_level: tracing::Level,
+
+    /// Log more: each -v goes one level more verbose than --log-level
+    /// (-v for debug, -vv for trace by default)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+
+    /// Log less: each -q goes one level quieter than --log-level (-q for
+    /// warn, -qq for error by default)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    quiet: u8,
+
+    /// Modem host name, IP address or URL, optionally labelled as
+    /// LABEL=HOST; repeat to monitor several modems (can also be set via
+    /// MODEM_HOST env var, comma-separated)
+    #[arg(
+        long,
+        env = "MODEM_HOST",
+        default_value = api::DEFAULT_MODEM_HOST,
+        value_delimiter = ',',
+        value_parser = parse_modem_host,
+        global = true
+    )]
+    modem_host: Vec<(Option<String>, String)>,
+
+    /// Username for modem firmware that requires a login (can also be set
+    /// via MODEM_USERNAME env var)
+    #[arg(long, env = "MODEM_USERNAME", requires = "password", global = true)]
+    username: Option<String>,
+
+    /// Password for modem firmware that requires a login (can also be set
+    /// via MODEM_PASSWORD env var)
+    #[arg(long, env = "MODEM_PASSWORD", requires = "username", global = true, hide_env_values = true)]
+    password: Option<String>,
+
+    /// Discord or Slack incoming webhook URL, told apart by host (can also
+    /// be set via DISCORD_WEBHOOK env var)
+    #[arg(short, long, env = "DISCORD_WEBHOOK", global = true)]
+    webhook: Option<String>,
+
+    /// Discord webhook URL for everything short of critical events and
+    /// alerts, posted without pinging, to keep the main destination for
+    /// what needs attention (can also be set via DISCORD_DIGEST_WEBHOOK env
+    /// var)
+    #[arg(long, env = "DISCORD_DIGEST_WEBHOOK", global = true)]
+    digest_webhook: Option<String>,
+
+    /// Discord bot token, to post as a bot instead of through a webhook
+    /// (can also be set via DISCORD_BOT_TOKEN env var)
+    #[arg(long, env = "DISCORD_BOT_TOKEN", requires = "channel_id", global = true)]
+    bot_token: Option<String>,
+
+    /// Discord channel ID the bot posts to (e.g., 123456789012345678)
+    #[arg(long, requires = "bot_token", global = true)]
+    channel_id: Option<u64>,
+
+    /// Discord thread ID for the webhook to post into, instead of the
+    /// webhook's channel; a bot can be given the thread as --channel-id
+    #[arg(long, requires = "webhook", conflicts_with = "bot_token", global = true)]
+    thread_id: Option<u64>,
 
     /// Poll interval in seconds
     #[arg(short, long, default_value = "60")]
     interval: u64,
 
+    /// Shift each poll by a random amount of up to this many seconds either
+    /// way, so requests to the modem don't line up
+    #[arg(long, default_value = "0")]
+    interval_jitter: u64,
+
+    /// Poll once, send any notifications and exit: 0 when healthy, 1 when
+    /// anomalies were found, 2 when the modem could not be read
+    #[arg(long)]
+    once: bool,
+
+    /// Log every notification, fully rendered, instead of sending it; a
+    /// Discord webhook URL is still checked but never contacted
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Poll responses recorded with `dump` in this directory instead of the
+    /// modem, one poll per timestamp in files named TIMESTAMP.ENDPOINT.json,
+    /// and exit once they run out; needs --dry-run
+    #[arg(long, value_name = "DIR", requires = "dry_run")]
+    replay: Option<PathBuf>,
+
+    /// Post a summary of the last day's readings and anomalies every day
+    /// at this local hour (0-23)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(0..24))]
+    digest_hour: Option<u32>,
+
     /// Discord role ID to ping (e.g., 123456789012345678)
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     role: Option<u64>,
 
+    /// Discord user ID to ping, alongside the role if both are set
+    #[arg(long, global = true)]
+    user: Option<u64>,
+
+    /// Only ping the role and user for critical events and alerts, not
+    /// warnings
+    #[arg(long, global = true)]
+    ping_critical_only: bool,
+
     /// Path to state file for tracking already-seen events (optional)
     #[arg(short, long)]
     state_file: Option<PathBuf>,
@@ -51,198 +183,1377 @@ struct Args {
     #[arg(long, default_value = "53.0")]
     upstream_signal_max: f64,
 
+    /// Minimum acceptable upstream SNR (MER) in dB, checked only on
+    /// firmware that reports one
+    #[arg(long, default_value = "30.0")]
+    upstream_snr_min: f64,
+
+    /// Minimum acceptable SNR (MER) in dB on DOCSIS 3.1 OFDM downstream
+    /// receivers
+    #[arg(long, default_value = "34.0")]
+    ofdm_snr_min: f64,
+
+    /// Minimum acceptable OFDM downstream PLC power in dBmV
+    #[arg(long, default_value = "-15.0")]
+    ofdm_power_min: f64,
+
+    /// Maximum acceptable OFDM downstream PLC power in dBmV
+    #[arg(long, default_value = "15.0")]
+    ofdm_power_max: f64,
+
+    /// Minimum acceptable OFDMA upstream transmit power in dBmV
+    #[arg(long, default_value = "35.0")]
+    ofdma_power_min: f64,
+
+    /// Maximum acceptable OFDMA upstream transmit power in dBmV
+    #[arg(long, default_value = "51.0")]
+    ofdma_power_max: f64,
+
+    /// TOML file of per-channel downstream limits that replace the global
+    /// ones, e.g. [downstream.1] with signal_max = 18.0
+    #[arg(long)]
+    channel_overrides: Option<PathBuf>,
+
     /// Alert if error rate (uncorrected/(corrected+uncorrected)) exceeds this threshold (0.01 = 1%)
     #[arg(long, default_value = "0.01")]
     error_rate_threshold: f64,
+
+    /// Alert if a downstream channel gains more than this many uncorrected
+    /// codewords in one poll, regardless of the error rate
+    #[arg(long, default_value = "100")]
+    uncorrectable_error_increase: i64,
+
+    /// Alert if a downstream channel's error counters as a whole, rather
+    /// than their growth since the last poll, give an error rate above this
+    /// threshold (0.01 = 1%), so a broken channel alerts on the first poll
+    #[arg(long)]
+    absolute_error_rate_threshold: Option<f64>,
+
+    /// Alert if a downstream channel gains corrected codewords faster than
+    /// this many per minute, however few are uncorrectable, as an early
+    /// warning of FEC working overtime
+    #[arg(long)]
+    corrected_rate_threshold: Option<f64>,
+
+    /// Alert when a downstream channel's SNR margin (SNR above
+    /// --downstream-snr-min) shrinks faster than this many dB per minute
+    #[arg(long)]
+    snr_margin_drop_rate: Option<f64>,
+
+    /// Alert when a downstream channel's SNR or signal has been falling
+    /// faster than this many dB per minute, fitted over the last
+    /// --degradation-window polls, while still within range
+    #[arg(long)]
+    degradation_rate: Option<f64>,
+
+    /// Polls the --degradation-rate slope is fitted over
+    #[arg(long, default_value = "5", value_parser = clap::value_parser!(u64).range(2..))]
+    degradation_window: u64,
+
+    /// Check a downstream channel's exponentially smoothed SNR against
+    /// --downstream-snr-min instead of each raw reading, giving each new
+    /// reading this weight (0 to 1), so brief dips don't alert but a
+    /// sustained decline does (0 disables)
+    #[arg(long, default_value = "0", value_parser = parse_smoothing)]
+    snr_smoothing: f64,
+
+    /// Alert when the modem's WAN octet counters show no traffic either way
+    /// for this many polls in a row while it has network access, on
+    /// firmware that reports them
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    stalled_traffic_polls: Option<u32>,
+
+    /// Alert when neither the event log nor any downstream reading has
+    /// changed for this many polls in a row, a sign the modem's web server
+    /// has hung and is serving stale pages
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    stalled_ui_polls: Option<u32>,
+
+    /// Alert when fewer downstream channels than this are locked (by
+    /// default the count of the first healthy poll)
+    #[arg(long)]
+    expected_downstream_channels: Option<usize>,
+
+    /// Alert when fewer upstream channels than this are locked (by default
+    /// the count of the first healthy poll)
+    #[arg(long)]
+    expected_upstream_channels: Option<usize>,
+
+    /// Only check these downstream and upstream channel IDs for anomalies
+    /// (comma-separated); the rest are still exported
+    #[arg(long, value_delimiter = ',')]
+    only_channels: Option<Vec<u32>>,
+
+    /// Never check these downstream or upstream channel IDs for anomalies
+    /// (comma-separated), e.g. vendor-reserved channels reporting garbage;
+    /// they are still exported
+    #[arg(long, value_delimiter = ',')]
+    ignore_channels: Vec<u32>,
+
+    /// Regex matched against event text that forces a ping and critical embed,
+    /// regardless of the modem's priority (can be repeated)
+    #[arg(long = "urgent-pattern", value_name = "REGEX")]
+    urgent_patterns: Vec<String>,
+
+    /// Regex matched against event text; matching events are never sent
+    /// (can be repeated)
+    #[arg(long = "mute-pattern", value_name = "REGEX")]
+    mute_patterns: Vec<String>,
+
+    /// Regex matched against event text; when given, only matching events
+    /// are sent (can be repeated)
+    #[arg(long = "only-pattern", value_name = "REGEX")]
+    only_patterns: Vec<String>,
+
+    /// Regex matched against new event text that marks a modem reboot,
+    /// posting a reboot notice and resetting the error counter baseline
+    /// (can be repeated; replaces the defaults)
+    #[arg(
+        long = "reboot-pattern",
+        value_name = "REGEX",
+        default_values = ["System startup", "Honoring MDD"]
+    )]
+    reboot_patterns: Vec<String>,
+
+    /// Treat a non-standard event priority as a known one, e.g.
+    /// "emergency=critical" (can be repeated)
+    #[arg(long = "priority-map", value_name = "RAW=PRIORITY", value_parser = parse_priority_mapping)]
+    priority_map: Vec<(String, api::EventPriority)>,
+
+    /// Least severe event priority that is sent as a notification
+    /// (critical, warning or notice); events matching an urgent pattern
+    /// are always sent
+    #[arg(long, default_value = "warning", value_parser = parse_priority)]
+    min_priority: api::EventPriority,
+
+    /// Immediate retries when connecting to the modem fails
+    #[arg(long, default_value = "1")]
+    connect_retries: u32,
+
+    /// Delay between connect retries in milliseconds
+    #[arg(long, default_value = "500")]
+    connect_retry_delay_ms: u64,
+
+    /// Accept any TLS certificate from the modem, as its own is
+    /// self-signed. This is the default; see --no-insecure.
+    #[arg(long, overrides_with = "no_insecure", global = true)]
+    insecure: bool,
+
+    /// Verify the modem against the system's root certificates instead of
+    /// accepting any
+    #[arg(long, global = true)]
+    no_insecure: bool,
+
+    /// Verify the modem against this certificate (PEM or DER), e.g. its own
+    /// self-signed one; overrides --insecure
+    #[arg(long, global = true)]
+    ca_cert: Option<PathBuf>,
+
+    /// Seconds to wait for the modem to answer a request
+    #[arg(long, default_value = "5", value_parser = clap::value_parser!(u64).range(1..))]
+    http_timeout: u64,
+
+    /// Seconds to wait for the event log in place of --http-timeout, for
+    /// firmware that is slow to render it
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    event_log_timeout: Option<u64>,
+
+    /// Retries for requests that time out or lose their connection, with
+    /// exponential backoff
+    #[arg(long, default_value = "0")]
+    max_retries: u32,
+
+    /// Delay before the first of those retries in milliseconds; it doubles
+    /// for each one after
+    #[arg(long, default_value = "1000")]
+    retry_delay_ms: u64,
+
+    /// Collapse anomalies of the same kind across channels into one alert
+    #[arg(long)]
+    collapse_anomalies: bool,
+
+    /// Escalate a channel alert to critical and start pinging once the same
+    /// anomaly persists for more than this many consecutive polls (by
+    /// default every channel alert pings)
+    #[arg(long)]
+    escalate_after: Option<u32>,
+
+    /// Only report an anomaly once it has held for this many consecutive
+    /// polls, to ride out one-off noisy readings
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u32).range(1..))]
+    confirm_polls: u32,
+
+    /// Minimum seconds between repeated alerts for the same anomaly on the
+    /// same channel; repeats in between are counted and reported with the
+    /// next alert (0 disables)
+    #[arg(long, default_value = "900")]
+    alert_cooldown: u64,
+
+    /// For this many seconds after startup, log new events without sending
+    /// them, so a modem replaying its log mid-reboot doesn't flood the
+    /// channel; they still count as seen (0 disables)
+    #[arg(long, default_value = "0")]
+    startup_grace: u64,
+
+    /// Show the modem's model, MAC and serial number in embed footers
+    #[arg(long)]
+    embed_identity: bool,
+
+    /// Text shown in the footer of every message, e.g. a site name or a
+    /// dashboard link; comes before the modem identity when both are shown
+    #[arg(long)]
+    embed_footer: Option<String>,
+
+    /// Text put in front of every message title, e.g. a site name
+    #[arg(long)]
+    embed_title_prefix: Option<String>,
+
+    /// Multiplier applied to reported signal strength and SNR, for firmware
+    /// that reports in other units (e.g. 0.1 for tenths of a dB)
+    #[arg(long, default_value = "1.0")]
+    signal_scale: f64,
+
+    /// Offset in dB added to reported signal strength after scaling
+    #[arg(long, default_value = "0.0", allow_hyphen_values = true)]
+    signal_offset: f64,
+
+    /// Hit every modem endpoint once at startup and log whether it parsed
+    #[arg(long)]
+    probe_on_start: bool,
+
+    /// Append a human-readable line for every alert to this file
+    #[arg(long)]
+    alert_log: Option<PathBuf>,
+
+    /// Rotate the alert log to <path>.1 once it would exceed this many bytes
+    #[arg(long, requires = "alert_log")]
+    alert_log_max_size: Option<u64>,
+
+    /// Append a JSON line for every event sent and every anomaly detected
+    /// to this file
+    #[arg(long)]
+    log_json: Option<PathBuf>,
+
+    /// Serve the monitor's current view of the modem as JSON at /status on
+    /// this address (e.g. 127.0.0.1:9090)
+    #[arg(long)]
+    status_addr: Option<SocketAddr>,
+
+    /// Also serve a self-contained HTML dashboard at / on the status address
+    #[arg(long, requires = "status_addr")]
+    dashboard: bool,
+
+    /// Serve channel stats for Prometheus at /metrics on this address
+    /// (e.g. 127.0.0.1:9091)
+    #[arg(long)]
+    metrics_addr: Option<SocketAddr>,
+
+    /// Serve a health check at /healthz on this address, answering 200
+    /// while every modem has had a successful poll within two intervals
+    /// and 503 otherwise
+    #[arg(long)]
+    health_addr: Option<SocketAddr>,
+
+    /// Health score weight for a downstream channel, e.g. "33=4" (can be repeated)
+    #[arg(long = "downstream-weight", value_name = "ID=WEIGHT", value_parser = parse_weight::<u32>)]
+    downstream_weights: Vec<(u32, f64)>,
+
+    /// Health score weight for an upstream channel, e.g. "2=0.5" (can be repeated)
+    #[arg(long = "upstream-weight", value_name = "ID=WEIGHT", value_parser = parse_weight::<u32>)]
+    upstream_weights: Vec<(u32, f64)>,
+
+    /// Health score weight for every channel with a modulation, e.g.
+    /// "OFDM=4" (can be repeated; per-channel weights take precedence)
+    #[arg(long = "modulation-weight", value_name = "MODULATION=WEIGHT", value_parser = parse_weight::<String>)]
+    modulation_weights: Vec<(String, f64)>,
+
+    /// Key the firmware wraps channel lists in, e.g. "chanInfo" for
+    /// {"chanInfo":[...]}; bare arrays and known keys always work
+    #[arg(long)]
+    channel_list_key: Option<String>,
+
+    /// strftime-style format of event log timestamps (e.g. "%Y/%m/%d
+    /// %H:%M:%S"), tried before the known firmware formats
+    #[arg(long, global = true)]
+    time_format: Option<String>,
+
+    /// Append each poll's per-channel frequency, signal and SNR to this file
+    /// as JSON lines, for plotting levels against frequency
+    #[arg(long)]
+    spectrum_export: Option<PathBuf>,
+
+    /// Only write every Nth poll to the spectrum and CSV exports; polls
+    /// with anomalies are always written
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u32).range(1..))]
+    export_every: u32,
+
+    /// Also write a poll to the spectrum and CSV exports when any channel's
+    /// signal or SNR moved by more than this many dB since the last written
+    /// poll
+    #[arg(long)]
+    export_delta: Option<f64>,
+
+    /// Append a row per channel on each poll to this CSV file (timestamp,
+    /// direction, channel, frequency, modulation, signal, SNR and error
+    /// counters), for spreadsheet analysis
+    #[arg(long)]
+    csv: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print new modem events to the terminal as they are logged, without
+    /// Discord or a state file
+    Tail {
+        /// Poll interval in seconds
+        #[arg(short, long, default_value = "5")]
+        interval: u64,
+    },
+    /// Send one test message to the configured webhook or channel, pinging
+    /// the role if one is set, and exit
+    TestWebhook,
+    /// Print one data endpoint's body as the modem sends it, without
+    /// parsing it, and exit; for bug reports when firmware changes its
+    /// responses
+    Dump {
+        /// Endpoint to fetch: model, sysinfo, link, wan, dsinfo, dsofdm,
+        /// usinfo, usofdm or status_log
+        #[arg(value_parser = parse_endpoint)]
+        endpoint: String,
+    },
+}
+
+/// Parse the command line, filling in options it leaves unset from the
+/// `--config` file if there is one
+fn parse_args() -> Result<Args> {
+    let argv = config::merge(&Args::command(), std::env::args_os().collect())?;
+    Ok(Args::parse_from(argv))
 }
 
-/// Load the set of already-seen events from the state file
-async fn load_seen_events(state_file: &Option<PathBuf>) -> Option<HashSet<dedup::EventKey>> {
-    let path = state_file.as_ref()?;
+/// Who Discord alerts ping
+fn mentions(args: &Args) -> discord::Mentions {
+    discord::Mentions {
+        role_id: args.role,
+        user_id: args.user,
+        critical_only: args.ping_critical_only,
+    }
+}
+
+/// Where notifications go, from the webhook or bot options
+fn destination(args: &Args) -> Result<notifier::Destination> {
+    Ok(match (&args.webhook, &args.bot_token, args.channel_id) {
+        (Some(_), Some(_), _) => bail!("Use either a webhook or a bot token, not both"),
+        (Some(webhook), None, _) => notifier::Destination::from_webhook_url(webhook, args.thread_id)?,
+        (None, Some(bot_token), Some(channel_id)) => notifier::Destination::Discord(discord::Destination::Channel {
+            bot_token: bot_token.clone(),
+            channel_id,
+        }),
+        _ => bail!(
+            "No notification destination set: pass a Discord or Slack incoming webhook URL with --webhook or the DISCORD_WEBHOOK environment variable, or post as a Discord bot with --bot-token (or DISCORD_BOT_TOKEN) and --channel-id"
+        ),
+    })
+}
 
-    match fs::read_to_string(path).await {
-        Ok(contents) => {
-            let seen = dedup::parse_state(&contents);
-            match &seen {
-                Some(keys) => debug!("Loaded {} seen event(s) from state file", keys.len()),
-                // Covers the legacy format, which held a bare timestamp
-                None => info!("State file is not a seen-event list, starting fresh"),
+/// Parse a modem host, optionally labelled as "label=host"
+fn parse_modem_host(s: &str) -> Result<(Option<String>, String), String> {
+    match s.split_once('=') {
+        // Don't mistake an '=' in a URL's query or path for a label
+        Some((label, host)) if !label.contains([':', '/']) => {
+            let label = label.trim();
+            if label.is_empty() {
+                return Err(format!("empty label in '{}'", s));
             }
-            seen
-        }
-        Err(e) => {
-            debug!("Could not read state file ({}), starting fresh", e);
-            None
+            Ok((Some(label.to_string()), host.trim().to_string()))
         }
+        _ => Ok((None, s.trim().to_string())),
+    }
+}
+
+/// Parse a "raw=priority" mapping, where priority is critical, warning or notice
+fn parse_priority_mapping(s: &str) -> Result<(String, api::EventPriority), String> {
+    let (raw, priority) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected RAW=PRIORITY, got '{}'", s))?;
+    Ok((raw.trim().to_ascii_lowercase(), parse_priority(priority)?))
+}
+
+/// Parse one of the standard event priorities
+fn parse_priority(s: &str) -> Result<api::EventPriority, String> {
+    match api::EventPriority::from_raw(s.trim()) {
+        api::EventPriority::Other(_) => Err(format!(
+            "unknown priority '{}' (expected critical, warning or notice)",
+            s
+        )),
+        known => Ok(known),
+    }
+}
+
+/// Parse an exponential smoothing factor between 0 and 1
+fn parse_smoothing(s: &str) -> Result<f64, String> {
+    let alpha: f64 = s.trim().parse().map_err(|_| format!("invalid smoothing factor '{}'", s))?;
+    if !(0.0..=1.0).contains(&alpha) {
+        return Err(format!("smoothing factor must be between 0 and 1, got {}", alpha));
     }
+    Ok(alpha)
+}
+
+/// Parse a data endpoint's short name or file name into its file name
+fn parse_endpoint(s: &str) -> Result<String, String> {
+    let name = s.trim().trim_end_matches(".asp");
+    api::ENDPOINTS
+        .iter()
+        .find(|(short, file)| *short == name || file.trim_end_matches(".asp") == name)
+        .map(|(_, file)| file.to_string())
+        .ok_or_else(|| {
+            let names: Vec<&str> = api::ENDPOINTS.iter().map(|(short, _)| *short).collect();
+            format!("unknown endpoint '{}' (expected one of {})", s, names.join(", "))
+        })
+}
+
+/// Parse a "key=weight" health score weight
+fn parse_weight<K: std::str::FromStr>(s: &str) -> Result<(K, f64), String> {
+    let (key, weight) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=WEIGHT, got '{}'", s))?;
+    let key = key.trim().parse().map_err(|_| format!("invalid key '{}'", key))?;
+    let weight: f64 = weight.trim().parse().map_err(|_| format!("invalid weight '{}'", weight))?;
+    if weight < 0.0 {
+        return Err(format!("weight must not be negative, got {}", weight));
+    }
+    Ok((key, weight))
+}
+
+/// Compile the event patterns given to `flag` once, failing on the first
+/// invalid one
+fn compile_patterns(flag: &str, patterns: &[String]) -> Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|p| Regex::new(p).with_context(|| format!("Invalid --{} '{}'", flag, p)))
+        .collect()
+}
+
+/// Event text patterns, compiled once at startup
+#[derive(Clone)]
+struct EventPatterns {
+    /// Force a ping and critical embed
+    urgent: Vec<Regex>,
+    /// Never sent
+    mute: Vec<Regex>,
+    /// When non-empty, the only events sent
+    only: Vec<Regex>,
+    /// Mark a modem reboot
+    reboot: Vec<Regex>,
 }
 
-/// Save the set of already-seen events to the state file
-async fn save_seen_events(state_file: &Option<PathBuf>, seen: &HashSet<dedup::EventKey>) -> Result<()> {
-    if let Some(path) = state_file {
-        // Create parent directory if it doesn't exist
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).await?;
+impl EventPatterns {
+    fn compile(args: &Args) -> Result<Self> {
+        Ok(Self {
+            urgent: compile_patterns("urgent-pattern", &args.urgent_patterns)?,
+            mute: compile_patterns("mute-pattern", &args.mute_patterns)?,
+            only: compile_patterns("only-pattern", &args.only_patterns)?,
+            reboot: compile_patterns("reboot-pattern", &args.reboot_patterns)?,
+        })
+    }
+
+    fn is_urgent(&self, event: &api::EventLog) -> bool {
+        self.urgent.iter().any(|re| re.is_match(&event.event))
+    }
+
+    fn is_reboot(&self, event: &api::EventLog) -> bool {
+        self.reboot.iter().any(|re| re.is_match(&event.event))
+    }
+
+    /// Whether the mute and only patterns let this event through
+    fn allows(&self, event: &api::EventLog) -> bool {
+        !self.mute.iter().any(|re| re.is_match(&event.event))
+            && (self.only.is_empty() || self.only.iter().any(|re| re.is_match(&event.event)))
+    }
+}
+
+/// Logs failed fetches, at warn for transient failures that should clear
+/// on their own and at error otherwise. While the modem serves a web page
+/// instead of data, e.g. as it reboots, only the first failure is warned of.
+#[derive(Default)]
+struct FetchLog {
+    serving_page: bool,
+}
+
+impl FetchLog {
+    fn failed(&mut self, what: &str, e: &anyhow::Error) {
+        match e.downcast_ref::<api::ApiError>() {
+            Some(api::ApiError::NotJson { .. }) if self.serving_page => debug!("Failed to fetch {}: {}", what, e),
+            Some(api::ApiError::NotJson { .. }) => {
+                warn!("Failed to fetch {}: {}; waiting for it to serve data again", what, e);
+                self.serving_page = true;
+            }
+            Some(api_error) if api_error.is_transient() => warn!("Failed to fetch {} (transient): {}", what, e),
+            _ => error!("Failed to fetch {}: {}", what, e),
         }
+    }
 
-        fs::write(path, dedup::serialize_state(seen)).await?;
-        debug!("Saved {} seen event(s)", seen.len());
+    /// Note a successful fetch, ending any run of web pages
+    fn succeeded(&mut self) {
+        if std::mem::take(&mut self.serving_page) {
+            info!("Modem is serving data again");
+        }
     }
-    Ok(())
 }
 
-/// Log a new event and forward it to Discord if it warrants a notification
-async fn report_event(event: &api::EventLog, notifier: &discord::DiscordNotifier) {
-    match event.parse_timestamp() {
-        // The modem stamps events logged before it syncs time-of-day with
-        // the Unix epoch in local time (e.g. "12/31/69 19:01:07"); they are
-        // reboot-window noise, so keep them out of Discord
-        Ok(ts) if dedup::is_pre_sync_timestamp(&ts) => {
-            error!(
-                "Ignoring pre-clock-sync event ('{}'): [{}] {} - {}",
-                event.time, event.priority, event.event_type, event.event
-            );
+/// Time until the next poll: the interval, shifted by a random amount of
+/// up to `jitter` either way
+fn jittered(interval: Duration, jitter: Duration) -> Duration {
+    if jitter.is_zero() {
+        return interval;
+    }
+    let jitter = jitter.as_secs_f64();
+    let offset = rand::thread_rng().gen_range(-jitter..=jitter);
+    Duration::from_secs_f64((interval.as_secs_f64() + offset).max(0.0))
+}
+
+/// How a poll went, worst last, for `--once`'s exit status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum PollOutcome {
+    Healthy,
+    Anomalies,
+    /// The event log or channel info could not be fetched
+    FetchFailed,
+}
+
+impl PollOutcome {
+    fn exit_code(self) -> ExitCode {
+        match self {
+            PollOutcome::Healthy => ExitCode::SUCCESS,
+            PollOutcome::Anomalies => ExitCode::from(1),
+            PollOutcome::FetchFailed => ExitCode::from(2),
+        }
+    }
+}
+
+/// Routes new events and channel anomalies to the notifier and the alert log
+struct Reporter {
+    notifier: notify_queue::NotifyQueue,
+    patterns: EventPatterns,
+    /// Least severe event priority worth a notification
+    min_priority: api::EventPriority,
+    alert_log: Option<alert_log::AlertLog>,
+    json_log: Option<json_log::JsonLog>,
+    cooldown: monitor::AlertCooldown,
+    /// Events are logged but not sent until then
+    grace_until: Option<std::time::Instant>,
+}
+
+/// Settings shared by every modem's polling task
+struct Shared {
+    args: Args,
+    patterns: EventPatterns,
+    /// Shared by every modem, so their lines don't interleave and their
+    /// rotations don't race
+    alert_log: Option<alert_log::AlertLog>,
+    json_log: Option<json_log::JsonLog>,
+    thresholds: monitor::ChannelThresholds,
+    weights: monitor::ChannelWeights,
+}
+
+/// One modem being monitored, with everything its polling task owns
+struct Modem {
+    /// Prefixed to alert titles and used to keep per-modem files apart when
+    /// monitoring several modems
+    label: Option<String>,
+    client: api::ApiClient,
+    notifier: Box<dyn Notifier>,
+    state_file: Option<PathBuf>,
+    spectrum_export: Option<PathBuf>,
+    csv: Option<PathBuf>,
+    /// Latest poll, for the status page (first modem only) and metrics
+    status: status::SharedStatus,
+}
+
+impl Reporter {
+    /// Log a new event and forward it to the notifier if it warrants a notification
+    async fn report_event(&self, event: &api::EventLog) {
+        match event.parse_timestamp() {
+            // The modem stamps events logged before it syncs time-of-day with
+            // the Unix epoch in local time (e.g. "12/31/69 19:01:07"); they are
+            // reboot-window noise, so keep them out of Discord
+            Ok(ts) if dedup::is_pre_sync_timestamp(&ts) => {
+                error!(
+                    "Ignoring pre-clock-sync event ('{}'): [{}] {} - {}",
+                    event.time, event.priority, event.event_type, event.event
+                );
+                return;
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to parse timestamp for event: {}", e),
+        }
+
+        info!("Event: [{}] {} - {}", event.priority, event.event_type, event.event);
+
+        // Muting wins over everything, urgency included
+        if !self.patterns.allows(event) {
+            debug!("Event filtered out by --mute-pattern/--only-pattern: {}", event.event);
+            return;
+        }
+
+        // Urgent patterns override the modem's own priority classification
+        let urgent = self.patterns.is_urgent(event);
+        if urgent {
+            warn!("Event matched an urgent pattern: {}", event.event);
+        }
+
+        if self.grace_until.is_some_and(|until| std::time::Instant::now() < until) {
+            info!("Not sending event during the startup grace period");
             return;
         }
-        Ok(_) => {}
-        Err(e) => warn!("Failed to parse timestamp for event: {}", e),
+
+        if urgent || event.priority.severity() >= self.min_priority.severity() {
+            self.notifier.send_event(event, urgent);
+            if let Some(alert_log) = &self.alert_log {
+                if let Err(e) = alert_log.append_event(event).await {
+                    error!("Failed to write alert log: {}", e);
+                }
+            }
+            if let Some(json_log) = &self.json_log {
+                if let Err(e) = json_log.append_event(event).await {
+                    error!("Failed to write JSON log: {}", e);
+                }
+            }
+        }
     }
 
-    info!("Event: [{}] {} - {}", event.priority, event.event_type, event.event);
+    /// Send an alert for each channel anomaly
+    async fn report_anomalies(&mut self, anomalies: &[monitor::ChannelAnomaly], channel_state: &monitor::ChannelState) {
+        if anomalies.is_empty() {
+            return;
+        }
+
+        info!("Detected {} channel anomal{}", anomalies.len(), if anomalies.len() == 1 { "y" } else { "ies" });
+        let now = std::time::Instant::now();
+        for anomaly in anomalies {
+            // Logged whether or not the cooldown lets the alert through
+            if let Some(json_log) = &self.json_log {
+                if let Err(e) = json_log.append_anomaly(anomaly).await {
+                    error!("Failed to write JSON log: {}", e);
+                }
+            }
 
-    // Only send non-Notice events to Discord webhook
-    if event.priority != api::EventPriority::Notice {
-        if let Err(e) = notifier.send_event(event).await {
-            error!("Failed to send event: {}", e);
+            let consecutive = channel_state.consecutive_count(anomaly);
+            // Escalating is news, so it skips the cooldown
+            let escalating = self.notifier.escalates_at(consecutive);
+            let Some(suppressed) = self.cooldown.check(anomaly, now, escalating) else {
+                debug!("Suppressing repeated alert: {}", anomaly);
+                continue;
+            };
+
+            self.notifier.send_channel_alert(anomaly, consecutive, suppressed);
+            if let Some(alert_log) = &self.alert_log {
+                if let Err(e) = alert_log.append_anomaly(anomaly).await {
+                    error!("Failed to write alert log: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Send a notice for each channel condition that has cleared
+    async fn report_recoveries(&self, recovered: &[monitor::ChannelAnomaly]) {
+        for anomaly in recovered {
+            info!("Channel condition cleared: {}", anomaly);
+            self.notifier.send_recovery(anomaly);
+            if let Some(alert_log) = &self.alert_log {
+                if let Err(e) = alert_log.append_recovery(anomaly).await {
+                    error!("Failed to write alert log: {}", e);
+                }
+            }
+            if let Some(json_log) = &self.json_log {
+                if let Err(e) = json_log.append_recovery(anomaly).await {
+                    error!("Failed to write JSON log: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Split channel check results into anomalies still present and ones that
+/// have cleared
+fn sort_channel_events(
+    events: Vec<monitor::ChannelEvent>,
+    anomalies: &mut Vec<monitor::ChannelAnomaly>,
+    recovered: &mut Vec<monitor::ChannelAnomaly>,
+) {
+    for event in events {
+        match event {
+            monitor::ChannelEvent::Raised(anomaly) => anomalies.push(anomaly),
+            monitor::ChannelEvent::Cleared(anomaly) => recovered.push(anomaly),
         }
     }
 }
 
-/// Report events not seen on the previous poll, then persist the new snapshot
+/// Report events not seen on the previous poll, then persist the new
+/// snapshot. Returns a new event marking a reboot, if any; the first run's
+/// log may be days old, so it is never searched.
 async fn process_event_log(
     events: &[api::EventLog],
-    seen: &mut Option<HashSet<dedup::EventKey>>,
-    notifier: &discord::DiscordNotifier,
+    state: &mut state::State,
+    reporter: &Reporter,
     state_file: &Option<PathBuf>,
-) {
-    match seen {
+) -> Option<api::EventLog> {
+    let mut reboot = None;
+    match &state.seen {
         Some(keys) => {
             let new_events = dedup::new_events(events, keys);
             if !new_events.is_empty() {
                 info!("Found {} new event(s)", new_events.len());
-                for event in new_events {
-                    report_event(event, notifier).await;
+                for event in &new_events {
+                    reporter.report_event(event).await;
                 }
             }
+            reboot = new_events.into_iter().rev().find(|e| reporter.patterns.is_reboot(e)).cloned();
         }
         // First run - report only the most recent event rather than
         // replaying the modem's whole rolling log
-        None => match events.first() {
+        None => match events.last() {
             Some(most_recent) => {
                 info!("First run - reporting most recent event only");
-                report_event(most_recent, notifier).await;
+                reporter.report_event(most_recent).await;
             }
             None => info!("No events found on first run"),
         },
     }
 
-    *seen = Some(dedup::snapshot(events));
-    if let Some(keys) = seen {
-        if let Err(e) = save_seen_events(state_file, keys).await {
-            error!("Failed to save state: {}", e);
-        }
+    // Mark as seen before persisting, so a failed write can't re-send
+    state.set_seen(dedup::snapshot(events));
+    if let Err(e) = state.save(state_file).await {
+        error!("Failed to save state, will retry next poll: {}", e);
     }
+    reboot
+}
+
+/// `base` moved `verbose` levels towards trace and `quiet` levels towards
+/// error, stopping at either end
+fn log_level(base: tracing::Level, verbose: u8, quiet: u8) -> tracing::Level {
+    const LEVELS: [tracing::Level; 5] = [
+        tracing::Level::ERROR,
+        tracing::Level::WARN,
+        tracing::Level::INFO,
+        tracing::Level::DEBUG,
+        tracing::Level::TRACE,
+    ];
+    let base = LEVELS.iter().position(|level| *level == base).unwrap_or(2);
+    let index = (base + verbose as usize).saturating_sub(quiet as usize).min(LEVELS.len() - 1);
+    LEVELS[index]
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+async fn main() -> Result<ExitCode> {
+    let args = parse_args()?;
 
-    let args = Args::parse();
+    // Color only when writing to a terminal, unless disabled outright
+    let color = !args.no_color && std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal();
 
-    // Create API client and Discord notifier
-    let client = api::create_client()?;
-    let notifier = discord::DiscordNotifier::new(&args.webhook, args.role).await?;
+    // Initialize tracing; RUST_LOG, when set, takes precedence over the flags
+    let level = log_level(args.log_level, args.verbose, args.quiet);
+    let filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::from_level(level).into())
+        .from_env_lossy();
+    tracing_subscriber::fmt().with_ansi(color).with_env_filter(filter).init();
+    let patterns = EventPatterns::compile(&args)?;
+    let downstream_overrides = match &args.channel_overrides {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read channel overrides {}", path.display()))?;
+            monitor::parse_channel_overrides(&text)
+                .with_context(|| format!("Failed to parse channel overrides {}", path.display()))?
+        }
+        None => Default::default(),
+    };
+
+    if let Some(Command::TestWebhook) = args.command {
+        let notifier = notifier::create(destination(&args)?, args.digest_webhook.clone(), mentions(&args), args.dry_run).await?;
+        notifier.send_test().await.context("Failed to send test message")?;
+        info!("Test message sent");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Create an API client per modem
+    let connect_retry = api::ConnectRetry {
+        attempts: args.connect_retries,
+        delay: Duration::from_millis(args.connect_retry_delay_ms),
+    };
+    let retry = api::RetryPolicy {
+        max_retries: args.max_retries,
+        base_delay: Duration::from_millis(args.retry_delay_ms),
+    };
+    let signal_scale = api::SignalScale {
+        factor: args.signal_scale,
+        offset: args.signal_offset,
+    };
+    let tls = match &args.ca_cert {
+        Some(path) => api::TlsVerification::Pinned(
+            api::load_certificate(path).with_context(|| format!("Failed to load CA certificate {}", path.display()))?,
+        ),
+        None if args.insecure || !args.no_insecure => {
+            if args.replay.is_none() {
+                warn!("Accepting any TLS certificate from the modem; pass --ca-cert to verify it");
+            }
+            api::TlsVerification::AcceptInvalid
+        }
+        None => api::TlsVerification::System,
+    };
+    let client_for = |source: Box<dyn api::Source>| {
+        let mut client = api::ApiClient::new(source, signal_scale);
+        client.set_retry(retry);
+        client.set_priority_map(args.priority_map.iter().cloned().collect());
+        client.set_channel_list_key(args.channel_list_key.clone());
+        client.set_time_format(args.time_format.clone());
+        client
+    };
+    let mut clients = Vec::new();
+    if let Some(dir) = &args.replay {
+        let replay = replay::Replay::load(dir)?;
+        info!("Replaying {} recorded polls from {}", replay.polls(), dir.display());
+        clients.push((None, client_for(Box::new(replay))));
+    }
+    for (label, host) in args.modem_host.iter().filter(|_| args.replay.is_none()) {
+        let mut source = api::create_http_source(host, Duration::from_secs(args.http_timeout), connect_retry, &tls)?;
+        source.set_event_log_timeout(args.event_log_timeout.map(Duration::from_secs));
+        if let (Some(username), Some(password)) = (&args.username, &args.password) {
+            source.set_credentials(api::Credentials {
+                username: username.clone(),
+                password: password.clone(),
+            });
+            // Not fatal: the modem may just be down, and requests log in again
+            // when they find the session missing
+            if let Err(e) = api::login(&source, username, password).await {
+                error!("Failed to log in to the modem at {}: {}", host, e);
+            }
+        }
+        // With several modems every one needs a label to tell them apart
+        let label = label.clone().or_else(|| (args.modem_host.len() > 1).then(|| host.clone()));
+        clients.push((label, client_for(Box::new(source))));
+    }
+
+    if let Some(Command::Tail { interval }) = args.command {
+        return tail::run(&clients[0].1, Duration::from_secs(interval), color)
+            .await
+            .map(|()| ExitCode::SUCCESS);
+    }
+    if let Some(Command::Dump { endpoint }) = &args.command {
+        return dump::run(&clients[0].1, endpoint).await.map(|()| ExitCode::SUCCESS);
+    }
+
+    let destination = destination(&args)?;
 
     info!("Hitron Modem Monitor started");
-    info!("Polling interval: {} seconds", args.interval);
+    if args.once {
+        info!("Polling once");
+    } else if args.interval_jitter > 0 {
+        info!("Polling interval: {} ± {} seconds", args.interval, args.interval_jitter);
+    } else {
+        info!("Polling interval: {} seconds", args.interval);
+    }
     if args.state_file.is_some() {
         info!("State persistence enabled");
     }
+    if args.dry_run {
+        info!("Dry run: notifications are logged, not sent");
+    }
 
-    // Load already-seen events from state file
-    let mut seen_events = load_seen_events(&args.state_file).await;
-
-    // Initialize channel monitoring
-    let thresholds = monitor::ChannelThresholds {
-        downstream_snr_min: args.downstream_snr_min,
-        downstream_signal_min: args.downstream_signal_min,
-        downstream_signal_max: args.downstream_signal_max,
-        upstream_signal_min: args.upstream_signal_min,
-        upstream_signal_max: args.upstream_signal_max,
-        error_rate_threshold: args.error_rate_threshold,
-    };
-    let mut channel_state = monitor::ChannelState::new();
+    let mut modems = Vec::new();
+    let several = clients.len() > 1;
+    for (label, client) in clients {
+        let mut notifier = notifier::create(destination.clone(), args.digest_webhook.clone(), mentions(&args), args.dry_run).await?;
+        notifier.set_escalate_after(args.escalate_after);
+        if let Some(footer) = &args.embed_footer {
+            notifier.set_footer(footer.clone());
+        }
+        if let Some(prefix) = &args.embed_title_prefix {
+            notifier.set_title_prefix(prefix.clone());
+        }
 
-    // On startup, send new events since last run
-    match api::get_event_log(&client).await {
-        Ok(events) => {
-            process_event_log(&events, &mut seen_events, &notifier, &args.state_file).await;
+        // Keep per-modem files apart, but leave a single modem's where they were
+        let namespaced = |path: &Option<PathBuf>| match (&label, several) {
+            (Some(label), true) => path.as_deref().map(|path| state::namespaced_path(path, label)),
+            _ => path.clone(),
+        };
+        let state_file = namespaced(&args.state_file);
+        let spectrum_export = namespaced(&args.spectrum_export);
+        let csv = namespaced(&args.csv);
+        if let Some(label) = &label {
+            notifier.set_label(label.clone());
         }
-        Err(e) => {
-            error!("Failed to fetch initial event log: {}", e);
+
+        modems.push(Modem {
+            label,
+            client,
+            notifier,
+            state_file,
+            spectrum_export,
+            csv,
+            status: status::SharedStatus::default(),
+        });
+    }
+
+    // The status page follows the first modem
+    if let Some(addr) = args.status_addr {
+        let status = Arc::clone(&modems[0].status);
+        let with_dashboard = args.dashboard;
+        tokio::spawn(async move {
+            if let Err(e) = server::serve(addr, status, with_dashboard).await {
+                error!("Status server failed: {}", e);
+            }
+        });
+    }
+    if let Some(addr) = args.metrics_addr {
+        let statuses = modems
+            .iter()
+            .map(|modem| (modem.label.clone(), Arc::clone(&modem.status)))
+            .collect();
+        tokio::spawn(async move {
+            if let Err(e) = server::serve_metrics(addr, statuses).await {
+                error!("Metrics server failed: {}", e);
+            }
+        });
+    }
+    if let Some(addr) = args.health_addr {
+        let statuses = modems
+            .iter()
+            .map(|modem| (modem.label.clone(), Arc::clone(&modem.status)))
+            .collect();
+        // Leave room for jitter pushing a poll late
+        let max_age = 2 * Duration::from_secs(args.interval + args.interval_jitter);
+        tokio::spawn(async move {
+            if let Err(e) = server::serve_health(addr, statuses, max_age).await {
+                error!("Health server failed: {}", e);
+            }
+        });
+    }
+
+    let shared = Arc::new(Shared {
+        thresholds: monitor::ChannelThresholds {
+            downstream_snr_min: args.downstream_snr_min,
+            downstream_signal_min: args.downstream_signal_min,
+            downstream_signal_max: args.downstream_signal_max,
+            upstream_signal_min: args.upstream_signal_min,
+            upstream_signal_max: args.upstream_signal_max,
+            upstream_snr_min: args.upstream_snr_min,
+            error_rate_threshold: args.error_rate_threshold,
+            uncorrectable_error_increase: args.uncorrectable_error_increase,
+            absolute_error_rate_threshold: args.absolute_error_rate_threshold,
+            corrected_rate_threshold: args.corrected_rate_threshold,
+            snr_margin_drop_rate: args.snr_margin_drop_rate,
+            snr_smoothing: (args.snr_smoothing > 0.0).then_some(args.snr_smoothing),
+            degradation_rate: args.degradation_rate,
+            degradation_window: args.degradation_window as usize,
+            stalled_traffic_polls: args.stalled_traffic_polls,
+            stalled_ui_polls: args.stalled_ui_polls,
+            expected_downstream_channels: args.expected_downstream_channels,
+            expected_upstream_channels: args.expected_upstream_channels,
+            downstream_overrides,
+            channels: monitor::ChannelFilter {
+                only: args.only_channels.as_ref().map(|ids| ids.iter().copied().collect()),
+                ignore: args.ignore_channels.iter().copied().collect(),
+            },
+            ofdm_snr_min: args.ofdm_snr_min,
+            ofdm_power_min: args.ofdm_power_min,
+            ofdm_power_max: args.ofdm_power_max,
+            ofdma_power_min: args.ofdma_power_min,
+            ofdma_power_max: args.ofdma_power_max,
+        },
+        weights: monitor::ChannelWeights {
+            downstream: args.downstream_weights.iter().copied().collect(),
+            upstream: args.upstream_weights.iter().copied().collect(),
+            modulation: args
+                .modulation_weights
+                .iter()
+                .map(|(modulation, weight)| (modulation.to_ascii_lowercase(), *weight))
+                .collect(),
+        },
+        patterns,
+        alert_log: args
+            .alert_log
+            .clone()
+            .map(|path| alert_log::AlertLog::new(path, args.alert_log_max_size)),
+        json_log: args.log_json.clone().map(json_log::JsonLog::new),
+        args,
+    });
+
+    // One polling task per modem, each stopping when told to shut down; a
+    // replay stops by itself once its recordings run out
+    let once = shared.args.once || shared.args.replay.is_some();
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let tasks: Vec<_> = modems
+        .into_iter()
+        .map(|modem| {
+            let span = match &modem.label {
+                Some(label) => info_span!("modem", label = %label),
+                None => Span::none(),
+            };
+            tokio::spawn(run_modem(modem, Arc::clone(&shared), shutdown_rx.clone()).instrument(span))
+        })
+        .collect();
+
+    // A single poll ends on its own; the daemon runs until signalled
+    if !once {
+        shutdown_signal().await?;
+        info!("Shutting down");
+        shutdown_tx.send(true)?;
+    }
+    let mut outcome = PollOutcome::Healthy;
+    for task in tasks {
+        outcome = outcome.max(task.await?);
+    }
+    Ok(if once { outcome.exit_code() } else { ExitCode::SUCCESS })
+}
+
+/// Wait for SIGINT (Ctrl-C) or SIGTERM (e.g. from systemd)
+async fn shutdown_signal() -> Result<()> {
+    let mut terminate = signal::unix::signal(signal::unix::SignalKind::terminate())?;
+    tokio::select! {
+        result = signal::ctrl_c() => result?,
+        _ = terminate.recv() => {}
+    }
+    Ok(())
+}
+
+/// Monitor one modem until told to shut down (or for one poll with
+/// `--once`), then save its state and return how the last poll went
+async fn run_modem(modem: Modem, shared: Arc<Shared>, mut shutdown: watch::Receiver<bool>) -> PollOutcome {
+    let Modem {
+        label,
+        client,
+        mut notifier,
+        state_file,
+        spectrum_export,
+        csv,
+        status,
+    } = modem;
+    let args = &shared.args;
+
+    // Load already-seen events and the last known firmware from state file
+    let mut state = state::State::load(&state_file).await;
+
+    // Identify the modem so alerts from identical units can be told apart
+    match api::get_modem_identity(&client).await {
+        Ok(identity) => {
+            info!("Monitoring {}", identity);
+            status.write().expect("status lock poisoned").modem = Some(identity.to_string());
+            if args.embed_identity {
+                let footer = match &args.embed_footer {
+                    Some(footer) => format!("{} · {}", footer, identity),
+                    None => identity.to_string(),
+                };
+                notifier.set_footer(footer);
+            }
+
+            // A firmware push can change behavior and endpoint shapes
+            // overnight, so flag it to help explain whatever breaks next
+            if let Some(firmware) = identity.firmware {
+                if let Some(previous) = state.set_firmware(firmware.clone()) {
+                    info!("Modem firmware changed: {} -> {}", previous, firmware);
+                    if let Err(e) = notifier.send_firmware_change(&previous, &firmware).await {
+                        error!("Failed to send firmware change notification: {}", e);
+                    }
+                }
+            }
         }
+        Err(e) => warn!("Failed to fetch modem identity: {}", e),
     }
 
-    // Start polling loop
-    let mut interval_timer = time::interval(Duration::from_secs(args.interval));
+    if args.probe_on_start {
+        probe::run(&client).await;
+    }
 
-    loop {
-        interval_timer.tick().await;
+    // Sent from their own task, so a slow webhook doesn't delay the next poll
+    let mut reporter = Reporter {
+        notifier: notify_queue::NotifyQueue::spawn(notifier),
+        patterns: shared.patterns.clone(),
+        min_priority: args.min_priority.clone(),
+        alert_log: shared.alert_log.clone().map(|mut alert_log| {
+            if let Some(label) = &label {
+                alert_log.set_label(label.clone());
+            }
+            alert_log
+        }),
+        json_log: shared.json_log.clone().map(|mut json_log| {
+            if let Some(label) = &label {
+                json_log.set_label(label.clone());
+            }
+            json_log
+        }),
+        cooldown: monitor::AlertCooldown::new(Duration::from_secs(args.alert_cooldown)),
+        grace_until: (args.startup_grace > 0)
+            .then(|| std::time::Instant::now() + Duration::from_secs(args.startup_grace)),
+    };
+
+    // Initialize channel monitoring
+    let thresholds = &shared.thresholds;
+    let mut channel_state = state::load_channel_state(&state_file).await;
+
+    let mut decimation = decimation::Decimation::new(args.export_every, args.export_delta);
 
+    // A reboot the event log shows, reported with the next poll's anomalies
+    let mut logged_reboot = None;
+
+    // On startup, send new events since last run; a single poll fetches
+    // them anyway
+    if !args.once {
         match api::get_event_log(&client).await {
-            Ok(current_events) => {
-                process_event_log(&current_events, &mut seen_events, &notifier, &args.state_file).await;
+            Ok(mut events) => {
+                dedup::normalize(&mut events);
+                logged_reboot = process_event_log(&events, &mut state, &reporter, &state_file).await;
             }
             Err(e) => {
-                error!("Failed to fetch event log: {}", e);
+                error!("Failed to fetch initial event log: {}", e);
             }
         }
+    }
+
+    // Start polling loop; the first poll runs straight away
+    let interval = Duration::from_secs(args.interval);
+    let jitter = Duration::from_secs(args.interval_jitter);
+    let mut next_poll = time::Instant::now();
+    let mut outcome = PollOutcome::Healthy;
+    let mut poll: u64 = 0;
+    let mut fetch_log = FetchLog::default();
+
+    loop {
+        if !client.next_poll() {
+            info!("Replay finished");
+            break;
+        }
+        tokio::select! {
+            _ = time::sleep_until(next_poll) => {}
+            _ = shutdown.changed() => break,
+        }
+        // A poll that overran its slot is caught up once, not in a burst
+        next_poll = (next_poll + jittered(interval, jitter)).max(time::Instant::now());
+        poll += 1;
 
-        // Check channel status for anomalies
-        let mut anomalies = Vec::new();
+        // Everything logged during the poll carries its sequence number
+        outcome = async {
+            channel_state.digest.record_poll(chrono::Local::now());
 
-        // Check downstream channels
-        match api::get_downstream_info(&client).await {
-            Ok(channels) => {
-                let downstream_anomalies = monitor::check_downstream_channels(&channels, &mut channel_state, &thresholds);
-                anomalies.extend(downstream_anomalies);
+            let mut fetch_failed = false;
+            let mut event_log_hash = None;
+            match api::get_event_log(&client).await {
+                Ok(mut current_events) => {
+                    fetch_log.succeeded();
+                    event_log_hash = Some(monitor::event_log_hash(&current_events));
+                    dedup::normalize(&mut current_events);
+                    if let Some(event) = process_event_log(&current_events, &mut state, &reporter, &state_file).await {
+                        logged_reboot = Some(event);
+                    }
+                    status.write().expect("status lock poisoned").set_events(&current_events);
+                }
+                Err(e) => {
+                    fetch_log.failed("event log", &e);
+                    fetch_failed = true;
+                }
             }
-            Err(e) => {
-                error!("Failed to fetch downstream channel info: {}", e);
+
+            // Check channel status for anomalies
+            let mut anomalies = Vec::new();
+            let mut recovered = Vec::new();
+
+            // Check for a reboot, silent or logged, ahead of the channels so a
+            // new one resets the error baseline before it is compared against
+            let uptime_drop = match api::get_system_info(&client).await {
+                Ok(info) => info.first().and_then(|info| monitor::check_system_info(info, &mut channel_state)),
+                Err(e) => {
+                    fetch_log.failed("system info", &e);
+                    None
+                }
+            };
+            let logged = logged_reboot.take().map(|event| monitor::ChannelAnomaly::RebootLogged {
+                time: event.time,
+                event: event.event,
+            });
+            if let Some(reboot) = channel_state.settle_reboot(uptime_drop, logged, chrono::Utc::now().timestamp()) {
+                info!("Modem rebooted; resetting error counter baseline");
+                anomalies.push(reboot);
             }
-        }
 
-        // Check upstream channels
-        match api::get_upstream_info(&client).await {
-            Ok(channels) => {
-                let upstream_anomalies = monitor::check_upstream_channels(&channels, &mut channel_state, &thresholds);
-                anomalies.extend(upstream_anomalies);
+            // Check downstream channels
+            let downstream = match api::get_downstream_info(&client).await {
+                Ok(channels) => {
+                    fetch_log.succeeded();
+                    let downstream_events = monitor::check_downstream_channels(&channels, &mut channel_state, thresholds);
+                    sort_channel_events(downstream_events, &mut anomalies, &mut recovered);
+                    Some(channels)
+                }
+                Err(e) => {
+                    fetch_log.failed("downstream channel info", &e);
+                    fetch_failed = true;
+                    None
+                }
+            };
+
+            // A hung web server keeps serving the same pages, which would
+            // otherwise look like a quiet, healthy line
+            if let (Some(hash), Some(_)) = (event_log_hash, &downstream) {
+                let stalled_events = monitor::check_ui_stalled(hash, &mut channel_state, thresholds);
+                sort_channel_events(stalled_events, &mut anomalies, &mut recovered);
             }
-            Err(e) => {
-                error!("Failed to fetch upstream channel info: {}", e);
+
+            // Check upstream channels
+            let upstream = match api::get_upstream_info(&client).await {
+                Ok(channels) => {
+                    let upstream_events = monitor::check_upstream_channels(&channels, &mut channel_state, thresholds);
+                    sort_channel_events(upstream_events, &mut anomalies, &mut recovered);
+                    Some(channels)
+                }
+                Err(e) => {
+                    fetch_log.failed("upstream channel info", &e);
+                    fetch_failed = true;
+                    None
+                }
+            };
+
+            // Check the DOCSIS 3.1 channels; modems provisioned without them
+            // report idle receivers, so a failed fetch doesn't fail the poll
+            match api::get_downstream_ofdm(&client).await {
+                Ok(receivers) => {
+                    let ofdm_events = monitor::check_downstream_ofdm(&receivers, &mut channel_state, thresholds);
+                    sort_channel_events(ofdm_events, &mut anomalies, &mut recovered);
+                }
+                Err(e) => fetch_log.failed("downstream OFDM info", &e),
+            }
+            match api::get_upstream_ofdm(&client).await {
+                Ok(channels) => {
+                    let ofdm_events = monitor::check_upstream_ofdm(&channels, &mut channel_state, thresholds);
+                    sort_channel_events(ofdm_events, &mut anomalies, &mut recovered);
+                }
+                Err(e) => fetch_log.failed("upstream OFDM info", &e),
+            }
+
+            // Check the Ethernet ports, for cabling problems the DOCSIS side
+            // can't show
+            match api::get_link_status(&client).await {
+                Ok(links) => anomalies.extend(monitor::check_link_status(&links, &mut channel_state)),
+                Err(e) => fetch_log.failed("link status", &e),
+            }
+
+            // Check the WAN configuration
+            match api::get_docsis_wan(&client).await {
+                Ok(wan) => {
+                    if let Some(wan) = wan.first() {
+                        let wan_events = monitor::check_docsis_wan(wan, &mut channel_state, thresholds);
+                        sort_channel_events(wan_events, &mut anomalies, &mut recovered);
+                    }
+                }
+                Err(e) => fetch_log.failed("DOCSIS WAN info", &e),
             }
-        }
 
-        // Send Discord notifications for anomalies
-        if !anomalies.is_empty() {
-            info!("Detected {} channel anomal{}", anomalies.len(), if anomalies.len() == 1 { "y" } else { "ies" });
-            for anomaly in &anomalies {
-                if let Err(e) = notifier.send_channel_alert(anomaly).await {
-                    error!("Failed to send channel alert: {}", e);
+            if let (Some(downstream), Some(upstream)) = (&downstream, &upstream) {
+                status
+                    .write()
+                    .expect("status lock poisoned")
+                    .set_poll(
+                        downstream,
+                        upstream,
+                        &anomalies,
+                        thresholds,
+                        &shared.weights,
+                        &channel_state.smoothed_snr,
+                    );
+            }
+
+            // Both exports write the same polls
+            if spectrum_export.is_some() || csv.is_some() {
+                let downstream = downstream.as_deref().unwrap_or_default();
+                let upstream = upstream.as_deref().unwrap_or_default();
+                if decimation.should_write(downstream, upstream, !anomalies.is_empty()) {
+                    if let Some(path) = &spectrum_export {
+                        if let Err(e) = spectrum::append(path, downstream, upstream).await {
+                            error!("Failed to write spectrum export: {}", e);
+                        }
+                    }
+                    if let Some(path) = &csv {
+                        if let Err(e) = csv_export::append(path, downstream, upstream).await {
+                            error!("Failed to write CSV export: {}", e);
+                        }
+                    }
+                }
+            }
+
+            // Track how long each anomaly has persisted, per channel
+            let checked: Vec<monitor::Direction> = [
+                downstream.as_ref().map(|_| monitor::Direction::Downstream),
+                upstream.as_ref().map(|_| monitor::Direction::Upstream),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            // A condition that cleared before it was confirmed was never
+            // reported, so there is nothing to announce a recovery from; its
+            // streak is checked before this poll resets it
+            recovered.retain(|anomaly| channel_state.is_confirmed(anomaly, args.confirm_polls));
+            channel_state.record_anomalies(&anomalies, &checked);
+            if let Err(e) = state::save_channel_state(&state_file, &channel_state).await {
+                error!("Failed to save channel state: {}", e);
+            }
+
+            // Anomalies still waiting for --confirm-polls are neither reported
+            // nor count towards the poll's outcome
+            anomalies.retain(|anomaly| {
+                let confirmed = channel_state.is_confirmed(anomaly, args.confirm_polls);
+                if !confirmed {
+                    debug!(
+                        "Waiting for confirmation ({}/{} polls): {}",
+                        channel_state.consecutive_count(anomaly),
+                        args.confirm_polls,
+                        anomaly
+                    );
+                }
+                confirmed
+            });
+
+            // Send Discord notifications for anomalies
+            if args.collapse_anomalies {
+                anomalies = monitor::collapse_anomalies(anomalies);
+            }
+            reporter.report_anomalies(&anomalies, &channel_state).await;
+            reporter.report_recoveries(&recovered).await;
+
+            // Once a day, a heartbeat summarizing the readings since the last
+            if let Some(hour) = args.digest_hour {
+                let now = chrono::Local::now();
+                if channel_state.digest.due(now, hour) {
+                    info!("Sending daily digest");
+                    reporter.notifier.send_digest(&channel_state.digest);
+                    channel_state.digest.reset(now);
+                    if let Err(e) = state::save_channel_state(&state_file, &channel_state).await {
+                        error!("Failed to save channel state: {}", e);
+                    }
                 }
             }
+
+            // OFDM, system info, link status and WAN are extras some firmware lacks,
+            // so only the core fetches count as a failed poll
+            if fetch_failed {
+                PollOutcome::FetchFailed
+            } else if !anomalies.is_empty() {
+                PollOutcome::Anomalies
+            } else {
+                PollOutcome::Healthy
+            }
+        }
+        .instrument(info_span!("poll", seq = poll))
+        .await;
+        if outcome != PollOutcome::FetchFailed {
+            status.write().expect("status lock poisoned").last_successful_poll = Some(std::time::Instant::now());
         }
+        if args.once {
+            break;
+        }
+    }
+
+    // Flush anything a failed save left behind
+    if let Err(e) = state.save(&state_file).await {
+        error!("Failed to save state on shutdown: {}", e);
+    }
+    if let Err(e) = state::save_channel_state(&state_file, &channel_state).await {
+        error!("Failed to save channel state on shutdown: {}", e);
     }
+    reporter.notifier.close().await;
+    outcome
 }
@@ -0,0 +1,165 @@
+use crate::status::Status;
+use std::fmt::Write;
+
+/// Label names and values, plus the sample value
+type Sample = (Vec<(&'static str, String)>, f64);
+
+/// One metric family: its name, type and help text, and a way to pull its
+/// samples (label set and value) out of a status snapshot
+struct Family {
+    name: &'static str,
+    kind: &'static str,
+    help: &'static str,
+    samples: fn(&Status) -> Vec<Sample>,
+}
+
+const FAMILIES: &[Family] = &[
+    Family {
+        name: "hitron_health_score",
+        kind: "gauge",
+        help: "Weighted percentage of channels not affected by a current anomaly",
+        samples: |s| s.health_score.map(|score| (Vec::new(), score)).into_iter().collect(),
+    },
+    Family {
+        name: "hitron_downstream_snr",
+        kind: "gauge",
+        help: "Downstream channel SNR in dB",
+        samples: |s| s.downstream.iter().map(|c| (channel(c.channel_id), c.snr)).collect(),
+    },
+    Family {
+        name: "hitron_downstream_snr_smoothed",
+        kind: "gauge",
+        help: "Exponentially smoothed downstream channel SNR in dB, with --snr-smoothing",
+        samples: |s| {
+            s.downstream
+                .iter()
+                .filter_map(|c| c.smoothed_snr.map(|snr| (channel(c.channel_id), snr)))
+                .collect()
+        },
+    },
+    Family {
+        name: "hitron_downstream_snr_margin",
+        kind: "gauge",
+        help: "Downstream channel SNR in dB above the minimum it is checked against",
+        samples: |s| s.downstream.iter().map(|c| (channel(c.channel_id), c.snr_margin)).collect(),
+    },
+    Family {
+        name: "hitron_downstream_signal",
+        kind: "gauge",
+        help: "Downstream channel signal strength in dBmV",
+        samples: |s| s.downstream.iter().map(|c| (channel(c.channel_id), c.signal_strength)).collect(),
+    },
+    Family {
+        name: "hitron_upstream_signal",
+        kind: "gauge",
+        help: "Upstream channel signal strength in dBmV",
+        samples: |s| s.upstream.iter().map(|c| (channel(c.channel_id), c.signal_strength)).collect(),
+    },
+    Family {
+        name: "hitron_corrected_total",
+        kind: "counter",
+        help: "Corrected codewords on a downstream channel since the modem last reset its counters",
+        samples: |s| s.downstream.iter().map(|c| (channel(c.channel_id), c.correcteds as f64)).collect(),
+    },
+    Family {
+        name: "hitron_uncorrectable_total",
+        kind: "counter",
+        help: "Uncorrectable codewords on a downstream channel since the modem last reset its counters",
+        samples: |s| s.downstream.iter().map(|c| (channel(c.channel_id), c.uncorrect as f64)).collect(),
+    },
+];
+
+fn channel(channel_id: u32) -> Vec<(&'static str, String)> {
+    vec![("channel", channel_id.to_string())]
+}
+
+/// Escape a label value for the text exposition format
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Render the latest poll of each modem in the Prometheus text format.
+/// Labelled modems get a `modem` label on every sample.
+pub fn render(modems: &[(Option<String>, Status)]) -> String {
+    let mut out = String::new();
+    for family in FAMILIES {
+        let _ = writeln!(out, "# HELP {} {}", family.name, family.help);
+        let _ = writeln!(out, "# TYPE {} {}", family.name, family.kind);
+
+        for (modem, status) in modems {
+            for (mut labels, value) in (family.samples)(status) {
+                if let Some(modem) = modem {
+                    labels.insert(0, ("modem", modem.clone()));
+                }
+                let labels: Vec<String> = labels
+                    .iter()
+                    .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+                    .collect();
+                if labels.is_empty() {
+                    let _ = writeln!(out, "{} {}", family.name, value);
+                } else {
+                    let _ = writeln!(out, "{}{{{}}} {}", family.name, labels.join(","), value);
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::{DownstreamStatus, UpstreamStatus};
+
+    fn status() -> Status {
+        Status {
+            health_score: Some(87.5),
+            downstream: vec![DownstreamStatus {
+                channel_id: 9,
+                frequency: 591_000_000.0,
+                modulation: "256QAM".to_string(),
+                signal_strength: 3.1,
+                snr: 40.4,
+                smoothed_snr: None,
+                snr_margin: 7.4,
+                correcteds: 12,
+                uncorrect: 2,
+            }],
+            upstream: vec![UpstreamStatus {
+                channel_id: 1,
+                frequency: 38_596_000.0,
+                modulation_type: "64QAM".to_string(),
+                signal_strength: 44.5,
+            }],
+            ..Status::default()
+        }
+    }
+
+    #[test]
+    fn renders_channel_samples() {
+        let text = render(&[(None, status())]);
+        assert!(text.contains("# TYPE hitron_downstream_snr gauge\n"));
+        assert!(text.contains("hitron_health_score 87.5\n"));
+        assert!(text.contains("hitron_downstream_snr{channel=\"9\"} 40.4\n"));
+        assert!(text.contains("hitron_downstream_snr_margin{channel=\"9\"} 7.4\n"));
+        assert!(text.contains("hitron_upstream_signal{channel=\"1\"} 44.5\n"));
+        assert!(text.contains("# TYPE hitron_uncorrectable_total counter\n"));
+        assert!(text.contains("hitron_uncorrectable_total{channel=\"9\"} 2\n"));
+    }
+
+    #[test]
+    fn labelled_modems_are_told_apart() {
+        let text = render(&[
+            (Some("primary".to_string()), status()),
+            (Some("failover".to_string()), status()),
+        ]);
+        assert!(text.contains("hitron_downstream_snr{modem=\"primary\",channel=\"9\"} 40.4\n"));
+        assert!(text.contains("hitron_downstream_snr{modem=\"failover\",channel=\"9\"} 40.4\n"));
+        assert!(text.contains("hitron_health_score{modem=\"failover\"} 87.5\n"));
+        // Metadata appears once per family, not once per modem
+        assert_eq!(text.matches("# TYPE hitron_downstream_snr ").count(), 1);
+    }
+}
@@ -1,5 +1,14 @@
-use crate::api::{DownstreamChannel, UpstreamChannel};
-use std::collections::HashMap;
+use crate::digest::DigestStats;
+use crate::api::{
+    DocsisWan, DownstreamChannel, DownstreamOfdm, Duplex, EventLog, LinkStatus, SystemInfo, UpstreamChannel,
+    UpstreamOfdm,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::mem::Discriminant;
+use std::time::{Duration, Instant};
+use tracing::debug;
 
 #[derive(Debug, Clone)]
 pub struct ChannelThresholds {
@@ -8,7 +17,132 @@ pub struct ChannelThresholds {
     pub downstream_signal_max: f64,
     pub upstream_signal_min: f64,
     pub upstream_signal_max: f64,
+    /// Minimum acceptable upstream SNR (MER), checked only on firmware
+    /// that reports it
+    pub upstream_snr_min: f64,
     pub error_rate_threshold: f64,
+    /// Alert when a channel gains more than this many uncorrectable
+    /// codewords in one poll, whatever the ratio to corrected ones
+    pub uncorrectable_error_increase: i64,
+    /// Alert when a channel's lifetime uncorrectable/(corrected +
+    /// uncorrectable) ratio exceeds this, which needs no previous poll;
+    /// `None` disables the check
+    pub absolute_error_rate_threshold: Option<f64>,
+    /// Alert when a channel gains corrected codewords faster than this many
+    /// per minute, whatever its uncorrectables do; `None` disables the check
+    pub corrected_rate_threshold: Option<f64>,
+    /// Alert when a channel's SNR margin shrinks faster than this many dB
+    /// per minute; `None` disables the trend check
+    pub snr_margin_drop_rate: Option<f64>,
+    /// Alert when a downstream channel's SNR or signal has been falling
+    /// faster than this many dB per minute over the last
+    /// `degradation_window` polls; `None` disables the check
+    pub degradation_rate: Option<f64>,
+    /// Polls the degradation slope is fitted over
+    pub degradation_window: usize,
+    /// Weight of each new reading in a channel's exponentially smoothed
+    /// SNR, which the low-SNR check then uses in place of the raw reading;
+    /// `None` checks the raw reading
+    pub snr_smoothing: Option<f64>,
+    /// Downstream channels the modem should have locked; `None` learns the
+    /// count from the first healthy poll
+    pub expected_downstream_channels: Option<usize>,
+    /// Upstream channels the modem should have locked, likewise
+    pub expected_upstream_channels: Option<usize>,
+    /// Alert when the WAN octet counters haven't moved in either direction
+    /// for this many polls in a row while the modem has network access;
+    /// `None` disables the check
+    pub stalled_traffic_polls: Option<u32>,
+    /// Alert when neither the event log nor any downstream reading has
+    /// changed for this many polls in a row, as when the modem's web server
+    /// hangs; `None` disables the check
+    pub stalled_ui_polls: Option<u32>,
+    /// Per-channel downstream limits that replace the ones above
+    pub downstream_overrides: HashMap<u32, ChannelOverride>,
+    /// Which SC-QAM channels, downstream and upstream, are checked at all
+    pub channels: ChannelFilter,
+    /// Minimum acceptable SNR (MER) on a DOCSIS 3.1 OFDM downstream
+    /// receiver; OFDM runs denser modulations than SC-QAM, so it has its
+    /// own limits
+    pub ofdm_snr_min: f64,
+    /// Acceptable range of an OFDM receiver's PLC power
+    pub ofdm_power_min: f64,
+    pub ofdm_power_max: f64,
+    /// Acceptable range of an active OFDMA upstream channel's transmit
+    /// power
+    pub ofdma_power_min: f64,
+    pub ofdma_power_max: f64,
+}
+
+/// Downstream limits for one channel that differ from the global ones, e.g.
+/// for a channel that runs hot by design. Unset limits fall back.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChannelOverride {
+    pub snr_min: Option<f64>,
+    pub signal_min: Option<f64>,
+    pub signal_max: Option<f64>,
+}
+
+/// Channels left out of anomaly detection, e.g. vendor-reserved ones that
+/// report garbage. They are still exported and shown on the status page.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelFilter {
+    /// When set, only these channel IDs are checked
+    pub only: Option<HashSet<u32>>,
+    /// Channel IDs never checked
+    pub ignore: HashSet<u32>,
+}
+
+impl ChannelFilter {
+    pub fn allows(&self, channel_id: u32) -> bool {
+        self.only.as_ref().is_none_or(|only| only.contains(&channel_id)) && !self.ignore.contains(&channel_id)
+    }
+}
+
+/// The layout of a channel overrides file:
+///
+/// ```toml
+/// [downstream.1]
+/// signal_max = 18.0
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OverridesFile {
+    #[serde(default)]
+    downstream: HashMap<String, ChannelOverride>,
+}
+
+/// Parse a channel overrides file into overrides by channel ID
+pub fn parse_channel_overrides(text: &str) -> anyhow::Result<HashMap<u32, ChannelOverride>> {
+    let file: OverridesFile = toml::from_str(text)?;
+    file.downstream
+        .into_iter()
+        .map(|(channel_id, limits)| match channel_id.parse() {
+            Ok(channel_id) => Ok((channel_id, limits)),
+            Err(_) => anyhow::bail!("'{}' is not a channel ID", channel_id),
+        })
+        .collect()
+}
+
+impl ChannelThresholds {
+    /// Minimum SNR for a downstream channel, its own if overridden
+    pub fn downstream_snr_min_for(&self, channel_id: u32) -> f64 {
+        self.downstream_overrides
+            .get(&channel_id)
+            .and_then(|o| o.snr_min)
+            .unwrap_or(self.downstream_snr_min)
+    }
+
+    /// Acceptable signal range for a downstream channel, its own if
+    /// overridden
+    pub fn downstream_signal_range_for(&self, channel_id: u32) -> (f64, f64) {
+        let limits = self.downstream_overrides.get(&channel_id);
+        (
+            limits.and_then(|o| o.signal_min).unwrap_or(self.downstream_signal_min),
+            limits.and_then(|o| o.signal_max).unwrap_or(self.downstream_signal_max),
+        )
+    }
 }
 
 impl Default for ChannelThresholds {
@@ -20,24 +154,324 @@ impl Default for ChannelThresholds {
             downstream_signal_max: 15.0,        // Adjusted based on your modem
             upstream_signal_min: 37.0,          // Adjusted based on your modem
             upstream_signal_max: 53.0,          // Adjusted based on your modem
+            upstream_snr_min: 30.0,             // Minimum 30 dB upstream MER
             error_rate_threshold: 0.01,         // Alert if uncorrectable/(corrected+uncorrectable) > 1%
+            uncorrectable_error_increase: 100,  // Alert if > 100 new uncorrectables in one poll
+            absolute_error_rate_threshold: None,
+            corrected_rate_threshold: None,
+            snr_margin_drop_rate: None,
+            snr_smoothing: None,
+            degradation_rate: None,
+            degradation_window: 5,
+            expected_downstream_channels: None,
+            expected_upstream_channels: None,
+            stalled_traffic_polls: None,
+            stalled_ui_polls: None,
+            downstream_overrides: HashMap::new(),
+            channels: ChannelFilter::default(),
+            ofdm_snr_min: 34.0,                 // Enough MER for 1024-QAM subcarriers
+            ofdm_power_min: -15.0,
+            ofdm_power_max: 15.0,
+            ofdma_power_min: 35.0,
+            ofdma_power_max: 51.0,              // Headroom below SC-QAM's 53, as OFDMA runs denser modulations
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// What the channel checks remember between polls. The last readings,
+/// primary channel, WAN configuration, link states and uptime are persisted
+/// across restarts so error deltas, address changes, link changes and
+/// reboots can be spotted on the first poll; the rest only lives in memory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChannelState {
     pub previous_downstream: HashMap<u32, DownstreamChannel>,
     pub previous_upstream: HashMap<u32, UpstreamChannel>,
+    /// Last channel the modem flagged as its primary downstream
+    pub primary_channel: Option<u32>,
+    /// Last DOCSIS WAN configuration read from the modem
+    #[serde(default)]
+    pub previous_wan: Option<DocsisWan>,
+    /// Modem uptime at the last poll
+    #[serde(default)]
+    pub previous_uptime: Option<Duration>,
+    /// Each Ethernet port's link at the last poll, by port name
+    #[serde(default)]
+    pub previous_links: HashMap<String, LinkState>,
+    /// Downstream channel count of the first healthy poll, used when no
+    /// count is configured
+    #[serde(default)]
+    pub learned_downstream_channels: Option<usize>,
+    /// Upstream channel count of the first healthy poll
+    #[serde(default)]
+    pub learned_upstream_channels: Option<usize>,
+    /// Size of the last non-empty downstream channel list
+    #[serde(default)]
+    pub last_downstream_count: Option<usize>,
+    /// Size of the last non-empty upstream channel list
+    #[serde(default)]
+    pub last_upstream_count: Option<usize>,
+    /// When the last reported reboot happened, as an estimated Unix
+    /// timestamp, so a reboot both logged and seen in the uptime is only
+    /// reported once
+    #[serde(default)]
+    pub last_reboot: Option<i64>,
+    /// Readings rolled up for the daily digest
+    #[serde(default)]
+    pub digest: DigestStats,
+    /// Exponentially smoothed downstream SNR by channel, when smoothing is
+    /// on
+    #[serde(default)]
+    pub smoothed_snr: HashMap<u32, f64>,
+    /// When `previous_downstream` was recorded
+    #[serde(skip)]
+    pub downstream_checked_at: Option<Instant>,
+    /// Each downstream channel's latest readings, oldest first, for the
+    /// degradation slope
+    #[serde(skip)]
+    recent_readings: HashMap<u32, VecDeque<Reading>>,
+    /// When `previous_wan` was recorded
+    #[serde(skip)]
+    wan_checked_at: Option<Instant>,
+    /// Polls in a row on which no WAN traffic flowed either way
+    #[serde(skip)]
+    idle_wan_polls: u32,
+    /// Whether every downstream reading on the last check matched the one
+    /// before it exactly
+    #[serde(skip)]
+    downstream_unchanged: bool,
+    /// Hash of the event log at the last poll
+    #[serde(skip)]
+    event_log_hash: Option<u64>,
+    /// Polls in a row on which neither the event log nor the downstream
+    /// readings changed
+    #[serde(skip)]
+    frozen_polls: u32,
+    /// How many polls in a row each kind of anomaly has been seen on each
+    /// channel
+    #[serde(skip)]
+    consecutive: HashMap<StreakKey, u32>,
+    /// Conditions raised on the last downstream check, for spotting when
+    /// they clear
+    #[serde(skip)]
+    active_downstream: HashMap<ActiveKey, ChannelAnomaly>,
+    /// Conditions raised on the last upstream check
+    #[serde(skip)]
+    active_upstream: HashMap<ActiveKey, ChannelAnomaly>,
+    /// Conditions raised on the last WAN check
+    #[serde(skip)]
+    active_wan: HashMap<ActiveKey, ChannelAnomaly>,
+    /// Conditions raised on the last stalled web interface check
+    #[serde(skip)]
+    active_ui: HashMap<ActiveKey, ChannelAnomaly>,
+    /// Conditions raised on the last downstream OFDM check
+    #[serde(skip)]
+    active_downstream_ofdm: HashMap<ActiveKey, ChannelAnomaly>,
+    /// Conditions raised on the last upstream OFDM check
+    #[serde(skip)]
+    active_upstream_ofdm: HashMap<ActiveKey, ChannelAnomaly>,
+}
+
+impl Default for ChannelState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
+/// One kind of anomaly on one channel, or on no channel in particular
+type ActiveKey = (Discriminant<ChannelAnomaly>, Option<u32>);
+
+/// One kind of anomaly on one channel (or, for anomalies not tied to a
+/// channel, in one direction)
+type StreakKey = (Discriminant<ChannelAnomaly>, Direction, Option<u32>);
+
 impl ChannelState {
     pub fn new() -> Self {
         Self {
             previous_downstream: HashMap::new(),
             previous_upstream: HashMap::new(),
+            primary_channel: None,
+            previous_wan: None,
+            previous_uptime: None,
+            previous_links: HashMap::new(),
+            learned_downstream_channels: None,
+            learned_upstream_channels: None,
+            last_downstream_count: None,
+            last_upstream_count: None,
+            last_reboot: None,
+            digest: DigestStats::default(),
+            smoothed_snr: HashMap::new(),
+            downstream_checked_at: None,
+            recent_readings: HashMap::new(),
+            wan_checked_at: None,
+            idle_wan_polls: 0,
+            downstream_unchanged: false,
+            event_log_hash: None,
+            frozen_polls: 0,
+            consecutive: HashMap::new(),
+            active_downstream: HashMap::new(),
+            active_upstream: HashMap::new(),
+            active_wan: HashMap::new(),
+            active_ui: HashMap::new(),
+            active_downstream_ofdm: HashMap::new(),
+            active_upstream_ofdm: HashMap::new(),
         }
     }
+
+    /// Count this poll's anomalies towards their consecutive-occurrence
+    /// streaks. Streaks not seen this poll are reset, except in directions
+    /// that weren't checked (e.g. because the fetch failed), which are left
+    /// as they were.
+    pub fn record_anomalies(&mut self, anomalies: &[ChannelAnomaly], checked: &[Direction]) {
+        let mut consecutive: HashMap<StreakKey, u32> = self
+            .consecutive
+            .iter()
+            .filter(|((_, direction, _), _)| !checked.contains(direction))
+            .map(|(key, count)| (*key, *count))
+            .collect();
+
+        let keys: HashSet<StreakKey> = anomalies.iter().flat_map(streak_keys).collect();
+        for key in keys {
+            let count = self.consecutive.get(&key).copied().unwrap_or(0) + 1;
+            consecutive.insert(key, count);
+        }
+
+        // For the digest, count anomalies just raised rather than every poll
+        // they persist through; one-off changes are always new
+        let raised = anomalies
+            .iter()
+            .filter(|anomaly| {
+                let keys = streak_keys(anomaly);
+                keys.is_empty() || keys.iter().any(|key| consecutive.get(key) == Some(&1))
+            })
+            .count();
+        self.digest.anomalies += raised as u64;
+
+        self.consecutive = consecutive;
+    }
+
+    /// How many polls in a row this anomaly has been seen, taking the
+    /// longest streak among the channels it covers
+    pub fn consecutive_count(&self, anomaly: &ChannelAnomaly) -> u32 {
+        streak_keys(anomaly)
+            .iter()
+            .filter_map(|key| self.consecutive.get(key))
+            .max()
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Forget the last downstream readings, so error rates are measured
+    /// afresh from the next poll, e.g. once the modem has rebooted
+    pub fn reset_error_baseline(&mut self) {
+        self.previous_downstream.clear();
+        self.downstream_checked_at = None;
+    }
+
+    /// Settle a poll's reboot signals, the uptime going backwards and a
+    /// reboot in the event log, into at most one reboot, reported once
+    /// however many signals and polls it shows up in. A new reboot also
+    /// resets the error baseline. `now` is a Unix timestamp.
+    pub fn settle_reboot(
+        &mut self,
+        uptime_drop: Option<ChannelAnomaly>,
+        logged: Option<ChannelAnomaly>,
+        now: i64,
+    ) -> Option<ChannelAnomaly> {
+        let reboot = uptime_drop.or(logged)?;
+        // Signals for one reboot arrive polls apart at most, but uptime may
+        // only count whole hours, so estimates of it can differ by one
+        let booted = now - self.previous_uptime.map_or(0, |uptime| uptime.as_secs() as i64);
+        if self.last_reboot.is_some_and(|last| (booted - last).abs() <= SAME_REBOOT_WINDOW_SECS) {
+            return None;
+        }
+        self.last_reboot = Some(booted);
+        self.reset_error_baseline();
+        Some(reboot)
+    }
+
+    /// Whether this anomaly has held for at least `polls` polls in a row
+    /// and so should be reported. One-off changes have no streak to wait
+    /// for and are always confirmed, and a group only once every member is.
+    pub fn is_confirmed(&self, anomaly: &ChannelAnomaly, polls: u32) -> bool {
+        if let ChannelAnomaly::Grouped(anomalies) = anomaly {
+            return anomalies.iter().all(|member| self.is_confirmed(member, polls));
+        }
+        let consecutive = self.consecutive_count(anomaly);
+        consecutive == 0 || consecutive >= polls
+    }
+}
+
+/// Reboot signals whose estimated reboot times are this close are taken for
+/// the same reboot
+const SAME_REBOOT_WINDOW_SECS: i64 = 2 * 60 * 60;
+
+fn streak_keys(anomaly: &ChannelAnomaly) -> Vec<StreakKey> {
+    let kind = std::mem::discriminant(anomaly);
+    match anomaly {
+        ChannelAnomaly::Grouped(anomalies) => anomalies.iter().flat_map(streak_keys).collect(),
+        // Not tied to a channel, but still downstream conditions; the modem
+        // is provisioned over the downstream
+        ChannelAnomaly::PartialService { .. } | ChannelAnomaly::NetworkAccessDenied { .. } => {
+            vec![(kind, Direction::Downstream, None)]
+        }
+        // Counted per direction, whichever channels went missing
+        ChannelAnomaly::ChannelCountDropped { direction, .. }
+        | ChannelAnomaly::NoChannelsReported { direction, .. } => vec![(kind, *direction, None)],
+        _ => anomaly
+            .affected_channels()
+            .into_iter()
+            .map(|(direction, channel_id)| (kind, direction, Some(channel_id)))
+            .collect(),
+    }
+}
+
+/// A change in a channel condition found by one check
+#[derive(Debug, Clone)]
+pub enum ChannelEvent {
+    /// The anomaly is present on this poll
+    Raised(ChannelAnomaly),
+    /// The anomaly was present on the previous poll but no longer is; this
+    /// holds the last reading taken while it was active
+    Cleared(ChannelAnomaly),
+}
+
+/// Raise every current anomaly, and clear any condition that was active on
+/// the previous check but isn't now. One-off changes such as a new primary
+/// channel or WAN address are raised but never tracked, as there is nothing
+/// to recover from.
+fn track_conditions(
+    active: &mut HashMap<ActiveKey, ChannelAnomaly>,
+    anomalies: Vec<ChannelAnomaly>,
+) -> Vec<ChannelEvent> {
+    let current: HashMap<ActiveKey, ChannelAnomaly> = anomalies
+        .iter()
+        .filter(|a| {
+            !matches!(
+                a,
+                ChannelAnomaly::PrimaryChannelChanged { .. }
+                    | ChannelAnomaly::ChannelRenegotiated { .. }
+                    | ChannelAnomaly::WanAddressChanged { .. }
+                    | ChannelAnomaly::ModemRebooted { .. }
+                    | ChannelAnomaly::RebootLogged { .. }
+                    | ChannelAnomaly::LinkStateChanged { .. }
+            )
+        })
+        .map(|a| ((std::mem::discriminant(a), a.channel_id()), a.clone()))
+        .collect();
+
+    let mut cleared: Vec<(ActiveKey, ChannelAnomaly)> = active
+        .drain()
+        .filter(|(key, _)| !current.contains_key(key))
+        .collect();
+    // Stable order for notifications
+    cleared.sort_by_key(|((_, channel_id), _)| *channel_id);
+    *active = current;
+
+    anomalies
+        .into_iter()
+        .map(ChannelEvent::Raised)
+        .chain(cleared.into_iter().map(|(_, anomaly)| ChannelEvent::Cleared(anomaly)))
+        .collect()
 }
 
 #[derive(Debug, Clone)]
@@ -48,6 +482,73 @@ pub struct ChannelErrorStats {
     pub error_rate: f64,
 }
 
+/// One poll's levels on a downstream channel
+#[derive(Debug, Clone, Copy)]
+struct Reading {
+    at: Instant,
+    snr: f64,
+    signal: f64,
+}
+
+/// A level measured on a channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Measurement {
+    Snr,
+    Signal,
+}
+
+impl std::fmt::Display for Measurement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Measurement::Snr => write!(f, "SNR"),
+            Measurement::Signal => write!(f, "signal"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Downstream,
+    Upstream,
+}
+
+/// An Ethernet port's link as seen on one poll
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkState {
+    pub up: bool,
+    pub speed_mbps: Option<u32>,
+    pub duplex: Option<Duplex>,
+}
+
+impl From<&LinkStatus> for LinkState {
+    fn from(link: &LinkStatus) -> Self {
+        Self {
+            up: link.up,
+            speed_mbps: link.speed_mbps.filter(|_| link.up),
+            duplex: link.duplex.filter(|_| link.up),
+        }
+    }
+}
+
+impl std::fmt::Display for LinkState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.up {
+            return write!(f, "down");
+        }
+        let speed = self.speed_mbps.map(|mbps| match mbps {
+            mbps if mbps >= 1000 => format!("{}Gbps", mbps as f64 / 1000.0),
+            mbps => format!("{}Mbps", mbps),
+        });
+        let parts: Vec<String> = [speed, self.duplex.map(|d| d.to_string())].into_iter().flatten().collect();
+        if parts.is_empty() {
+            write!(f, "up")
+        } else {
+            write!(f, "{}", parts.join(" "))
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ChannelAnomaly {
     DownstreamLowSNR {
@@ -67,10 +568,269 @@ pub enum ChannelAnomaly {
         min: f64,
         max: f64,
     },
+    UpstreamLowSNR {
+        channel_id: u32,
+        snr: f64,
+        threshold: f64,
+    },
     HighErrorRate {
         threshold: f64,
+        increase_threshold: i64,
         triggered_channels: Vec<ChannelErrorStats>,
     },
+    /// A channel whose error counters, taken over the modem's uptime, show
+    /// too many uncorrectables
+    HighLifetimeErrorRate {
+        channel_id: u32,
+        /// In Hz
+        frequency: f64,
+        error_rate: f64,
+        uncorrected: i64,
+        corrected: i64,
+        threshold: f64,
+    },
+    /// A channel whose FEC is correcting codewords at a high rate, which
+    /// often comes before uncorrectables
+    HighCorrectedRate {
+        channel_id: u32,
+        corrected_delta: i64,
+        /// Corrected codewords per minute
+        rate: f64,
+        threshold: f64,
+    },
+    PrimaryChannelChanged {
+        from: u32,
+        to: u32,
+    },
+    /// A channel moved to another frequency or modulation since the last
+    /// poll, e.g. dropping from 256-QAM to 64-QAM as the plant degrades
+    ChannelRenegotiated {
+        direction: Direction,
+        channel_id: u32,
+        /// Frequency and modulation on the previous poll, frequency in Hz
+        from: (f64, String),
+        to: (f64, String),
+    },
+    PartialService {
+        last_primary: u32,
+    },
+    SnrMarginFalling {
+        channel_id: u32,
+        margin: f64,
+        rate: f64,
+    },
+    /// A channel's SNR or signal falling steadily over several polls, an
+    /// early warning while it is still within range
+    RapidDegradation {
+        channel_id: u32,
+        measurement: Measurement,
+        /// Least-squares slope over the window, in dB per minute
+        slope: f64,
+        polls: usize,
+    },
+    /// The CMTS has stopped letting the modem pass traffic
+    NetworkAccessDenied {
+        provisioning_state: String,
+    },
+    /// The modem has network access, but its WAN octet counters haven't
+    /// moved in either direction for several polls, a soft outage signal
+    /// levels can't show
+    StalledTraffic {
+        polls: u32,
+    },
+    /// Neither the event log nor any downstream reading has changed for
+    /// several polls, as when the modem's web server hangs and keeps
+    /// serving stale pages while the line may well be down
+    ModemUiStalled {
+        polls: u32,
+    },
+    WanAddressChanged {
+        from: String,
+        to: String,
+    },
+    /// The modem's uptime went backwards, so it restarted since the last poll
+    ModemRebooted {
+        uptime: Duration,
+        previous: Duration,
+    },
+    /// A new event log entry matching a reboot pattern
+    RebootLogged {
+        time: String,
+        event: String,
+    },
+    /// Fewer channels locked than expected, e.g. the modem dropping bonded
+    /// channels as the line degrades
+    ChannelCountDropped {
+        direction: Direction,
+        locked: usize,
+        expected: usize,
+        /// Previously seen channels missing from this poll
+        missing: Vec<u32>,
+    },
+    /// The modem reported no channels at all in one direction after
+    /// reporting some, as it does while re-ranging
+    NoChannelsReported {
+        direction: Direction,
+        /// Size of the last non-empty channel list
+        last_count: usize,
+    },
+    /// An Ethernet port went down, came up, or renegotiated its speed or
+    /// duplex, e.g. a failing cable dropping to 100Mbps
+    LinkStateChanged {
+        port: String,
+        from: LinkState,
+        to: LinkState,
+    },
+    /// A locked DOCSIS 3.1 OFDM downstream receiver with SNR (MER) below
+    /// the OFDM minimum
+    OfdmLowSNR {
+        receiver: u32,
+        snr: f64,
+        threshold: f64,
+    },
+    /// A locked OFDM downstream receiver's PLC power outside the OFDM range
+    OfdmPowerOutOfRange {
+        receiver: u32,
+        power: f64,
+        min: f64,
+        max: f64,
+    },
+    /// An active OFDMA upstream channel transmitting outside the upstream
+    /// signal range
+    OfdmaPowerOutOfRange {
+        channel_index: u32,
+        power: f64,
+        min: f64,
+        max: f64,
+    },
+    /// Several channels reporting the same kind of anomaly in one poll
+    Grouped(Vec<ChannelAnomaly>),
+}
+
+/// How serious an anomaly is, for styling alerts and deciding who is
+/// pinged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// A change worth knowing about but not a fault, e.g. a new WAN address
+    Info,
+    Warning,
+    Critical,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Info => write!(f, "Info"),
+            Severity::Warning => write!(f, "Warning"),
+            Severity::Critical => write!(f, "Critical"),
+        }
+    }
+}
+
+impl ChannelAnomaly {
+    /// How serious this anomaly is; a group is as serious as its worst
+    /// member
+    pub fn severity(&self) -> Severity {
+        match self {
+            ChannelAnomaly::HighErrorRate { .. }
+            | ChannelAnomaly::HighLifetimeErrorRate { .. }
+            | ChannelAnomaly::PartialService { .. }
+            | ChannelAnomaly::NetworkAccessDenied { .. }
+            | ChannelAnomaly::StalledTraffic { .. }
+            | ChannelAnomaly::ModemUiStalled { .. }
+            | ChannelAnomaly::NoChannelsReported { .. }
+            | ChannelAnomaly::ModemRebooted { .. }
+            | ChannelAnomaly::RebootLogged { .. } => Severity::Critical,
+            ChannelAnomaly::LinkStateChanged { to, .. } if !to.up => Severity::Critical,
+            ChannelAnomaly::DownstreamLowSNR { .. }
+            | ChannelAnomaly::DownstreamSignalOutOfRange { .. }
+            | ChannelAnomaly::UpstreamSignalOutOfRange { .. }
+            | ChannelAnomaly::UpstreamLowSNR { .. }
+            | ChannelAnomaly::HighCorrectedRate { .. }
+            | ChannelAnomaly::PrimaryChannelChanged { .. }
+            | ChannelAnomaly::ChannelRenegotiated { .. }
+            | ChannelAnomaly::SnrMarginFalling { .. }
+            | ChannelAnomaly::RapidDegradation { .. }
+            | ChannelAnomaly::ChannelCountDropped { .. }
+            | ChannelAnomaly::OfdmLowSNR { .. }
+            | ChannelAnomaly::OfdmPowerOutOfRange { .. }
+            | ChannelAnomaly::OfdmaPowerOutOfRange { .. } => Severity::Warning,
+            ChannelAnomaly::WanAddressChanged { .. } | ChannelAnomaly::LinkStateChanged { .. } => Severity::Info,
+            ChannelAnomaly::Grouped(anomalies) => {
+                anomalies.iter().map(ChannelAnomaly::severity).max().unwrap_or(Severity::Warning)
+            }
+        }
+    }
+
+    /// The single channel this anomaly concerns, if it is per-channel
+    pub fn channel_id(&self) -> Option<u32> {
+        match self {
+            ChannelAnomaly::DownstreamLowSNR { channel_id, .. }
+            | ChannelAnomaly::DownstreamSignalOutOfRange { channel_id, .. }
+            | ChannelAnomaly::UpstreamSignalOutOfRange { channel_id, .. }
+            | ChannelAnomaly::UpstreamLowSNR { channel_id, .. }
+            | ChannelAnomaly::HighLifetimeErrorRate { channel_id, .. }
+            | ChannelAnomaly::HighCorrectedRate { channel_id, .. }
+            | ChannelAnomaly::ChannelRenegotiated { channel_id, .. }
+            | ChannelAnomaly::SnrMarginFalling { channel_id, .. }
+            | ChannelAnomaly::RapidDegradation { channel_id, .. } => Some(*channel_id),
+            ChannelAnomaly::OfdmLowSNR { receiver, .. }
+            | ChannelAnomaly::OfdmPowerOutOfRange { receiver, .. } => Some(*receiver),
+            ChannelAnomaly::OfdmaPowerOutOfRange { channel_index, .. } => Some(*channel_index),
+            _ => None,
+        }
+    }
+
+    /// Whether this anomaly concerns DOCSIS 3.1 OFDM channels, which are
+    /// numbered apart from the SC-QAM ones
+    fn is_ofdm(&self) -> bool {
+        match self {
+            ChannelAnomaly::OfdmLowSNR { .. }
+            | ChannelAnomaly::OfdmPowerOutOfRange { .. }
+            | ChannelAnomaly::OfdmaPowerOutOfRange { .. } => true,
+            ChannelAnomaly::Grouped(anomalies) => anomalies.iter().all(ChannelAnomaly::is_ofdm),
+            _ => false,
+        }
+    }
+
+    /// Every channel this anomaly concerns, with its direction
+    pub fn affected_channels(&self) -> Vec<(Direction, u32)> {
+        match self {
+            ChannelAnomaly::DownstreamLowSNR { channel_id, .. }
+            | ChannelAnomaly::DownstreamSignalOutOfRange { channel_id, .. }
+            | ChannelAnomaly::HighLifetimeErrorRate { channel_id, .. }
+            | ChannelAnomaly::HighCorrectedRate { channel_id, .. }
+            | ChannelAnomaly::SnrMarginFalling { channel_id, .. }
+            | ChannelAnomaly::RapidDegradation { channel_id, .. } => vec![(Direction::Downstream, *channel_id)],
+            ChannelAnomaly::UpstreamSignalOutOfRange { channel_id, .. }
+            | ChannelAnomaly::UpstreamLowSNR { channel_id, .. } => vec![(Direction::Upstream, *channel_id)],
+            ChannelAnomaly::OfdmLowSNR { receiver, .. }
+            | ChannelAnomaly::OfdmPowerOutOfRange { receiver, .. } => vec![(Direction::Downstream, *receiver)],
+            ChannelAnomaly::OfdmaPowerOutOfRange { channel_index, .. } => vec![(Direction::Upstream, *channel_index)],
+            ChannelAnomaly::HighErrorRate { triggered_channels, .. } => triggered_channels
+                .iter()
+                .map(|stats| (Direction::Downstream, stats.channel_id))
+                .collect(),
+            ChannelAnomaly::PrimaryChannelChanged { to, .. } => vec![(Direction::Downstream, *to)],
+            ChannelAnomaly::ChannelRenegotiated { direction, channel_id, .. } => vec![(*direction, *channel_id)],
+            ChannelAnomaly::PartialService { .. }
+            | ChannelAnomaly::NetworkAccessDenied { .. }
+            | ChannelAnomaly::StalledTraffic { .. }
+            | ChannelAnomaly::ModemUiStalled { .. }
+            | ChannelAnomaly::WanAddressChanged { .. }
+            | ChannelAnomaly::ModemRebooted { .. }
+            | ChannelAnomaly::RebootLogged { .. }
+            | ChannelAnomaly::NoChannelsReported { .. }
+            | ChannelAnomaly::LinkStateChanged { .. } => Vec::new(),
+            ChannelAnomaly::ChannelCountDropped { direction, missing, .. } => {
+                missing.iter().map(|channel_id| (*direction, *channel_id)).collect()
+            }
+            ChannelAnomaly::Grouped(anomalies) => anomalies
+                .iter()
+                .flat_map(ChannelAnomaly::affected_channels)
+                .collect(),
+        }
+    }
 }
 
 impl std::fmt::Display for ChannelAnomaly {
@@ -85,64 +845,430 @@ impl std::fmt::Display for ChannelAnomaly {
             ChannelAnomaly::UpstreamSignalOutOfRange { channel_id, signal, min, max } => {
                 write!(f, "Upstream channel {} signal out of range: {:.1} dBmV (expected: {:.1} to {:.1} dBmV)", channel_id, signal, min, max)
             }
-            ChannelAnomaly::HighErrorRate { threshold, triggered_channels } => {
-                write!(f, "High error rate detected on {} channel(s) (threshold: {:.2}%)\n\n",
-                    triggered_channels.len(), threshold * 100.0)?;
+            ChannelAnomaly::UpstreamLowSNR { channel_id, snr, threshold } => {
+                write!(f, "Upstream channel {} has low SNR: {:.1} dB (threshold: {:.1} dB)", channel_id, snr, threshold)
+            }
+            ChannelAnomaly::HighErrorRate { threshold, increase_threshold, triggered_channels } => {
+                write!(f, "High error rate detected on {} channel(s) (threshold: {:.2}% or +{} uncorrected per poll)\n\n",
+                    triggered_channels.len(), threshold * 100.0, increase_threshold)?;
 
                 for stats in triggered_channels {
-                    write!(f, "• Channel {}: {:.2}% error rate (uncorrected: +{}, corrected: +{})\n",
+                    writeln!(f, "• Channel {}: {:.2}% error rate (uncorrected: +{}, corrected: +{})",
                         stats.channel_id, stats.error_rate * 100.0, stats.uncorrected_delta, stats.corrected_delta)?;
                 }
 
                 Ok(())
             }
+            ChannelAnomaly::HighLifetimeErrorRate { channel_id, frequency, error_rate, uncorrected, corrected, threshold } => {
+                write!(f, "Channel {} ({:.1} MHz) has a {:.2}% lifetime error rate (uncorrected: {}, corrected: {}; threshold: {:.2}%)",
+                    channel_id, frequency / 1_000_000.0, error_rate * 100.0, uncorrected, corrected, threshold * 100.0)
+            }
+            ChannelAnomaly::HighCorrectedRate { channel_id, corrected_delta, rate, threshold } => {
+                write!(f, "Channel {} corrected {} codewords since the last poll ({:.0}/min; threshold: {:.0}/min)",
+                    channel_id, corrected_delta, rate, threshold)
+            }
+            ChannelAnomaly::PrimaryChannelChanged { from, to } => {
+                write!(f, "Primary downstream channel changed from {} to {}", from, to)
+            }
+            ChannelAnomaly::ChannelRenegotiated { direction, channel_id, from, to } => {
+                let direction = match direction {
+                    Direction::Downstream => "Downstream",
+                    Direction::Upstream => "Upstream",
+                };
+                write!(f, "{} channel {} renegotiated from {:.1} MHz {} to {:.1} MHz {}",
+                    direction, channel_id, from.0 / 1_000_000.0, from.1, to.0 / 1_000_000.0, to.1)
+            }
+            ChannelAnomaly::PartialService { last_primary } => {
+                write!(f, "No primary downstream channel reported (was channel {}); modem is in partial service", last_primary)
+            }
+            ChannelAnomaly::SnrMarginFalling { channel_id, margin, rate } => {
+                write!(f, "Channel {} SNR margin falling at {:.2} dB/min ({:.1} dB above minimum)", channel_id, rate, margin)
+            }
+            ChannelAnomaly::RapidDegradation { channel_id, measurement, slope, polls } => {
+                write!(f, "Channel {} {} falling steadily: {:+.2} dB/min over the last {} polls", channel_id, measurement, slope, polls)
+            }
+            ChannelAnomaly::NetworkAccessDenied { provisioning_state } => {
+                write!(f, "Network access denied by the ISP")?;
+                if !provisioning_state.is_empty() {
+                    write!(f, " (provisioning state: {})", provisioning_state)?;
+                }
+                Ok(())
+            }
+            ChannelAnomaly::StalledTraffic { polls } => {
+                write!(f, "No traffic sent or received for {} polls, though the modem has network access", polls)
+            }
+            ChannelAnomaly::ModemUiStalled { polls } => {
+                write!(f, "Event log and downstream readings unchanged for {} polls; the modem's web interface may have hung, and power-cycling the modem may be needed", polls)
+            }
+            ChannelAnomaly::WanAddressChanged { from, to } => {
+                write!(f, "WAN address changed from {} to {}", from, to)
+            }
+            ChannelAnomaly::ModemRebooted { uptime, previous } => {
+                write!(f, "Modem rebooted: up for {} (was up for {} at the last poll)",
+                    format_uptime(*uptime), format_uptime(*previous))
+            }
+            ChannelAnomaly::RebootLogged { time, event } => {
+                write!(f, "Modem rebooted, per its event log at {}: {}", time, event)
+            }
+            ChannelAnomaly::ChannelCountDropped { direction, locked, expected, missing } => {
+                let direction = match direction {
+                    Direction::Downstream => "Downstream",
+                    Direction::Upstream => "Upstream",
+                };
+                write!(f, "{} channel count dropped: {} of {} channels locked", direction, locked, expected)?;
+                if !missing.is_empty() {
+                    let missing: Vec<String> = missing.iter().map(u32::to_string).collect();
+                    write!(f, " (missing: {})", missing.join(", "))?;
+                }
+                Ok(())
+            }
+            ChannelAnomaly::NoChannelsReported { direction, last_count } => {
+                let direction = match direction {
+                    Direction::Downstream => "downstream",
+                    Direction::Upstream => "upstream",
+                };
+                write!(f, "Modem reported no {} channels (was {}); it may be re-ranging", direction, last_count)
+            }
+            ChannelAnomaly::LinkStateChanged { port, from, to } => match (from.up, to.up) {
+                (_, false) => write!(f, "{} link down (was {})", port, from),
+                (false, true) => write!(f, "{} link up at {}", port, to),
+                (true, true) => write!(f, "{} link now {} (was {})", port, to, from),
+            },
+            ChannelAnomaly::OfdmLowSNR { receiver, snr, threshold } => {
+                write!(f, "OFDM receiver {} has low MER: {:.1} dB (threshold: {:.1} dB)", receiver, snr, threshold)
+            }
+            ChannelAnomaly::OfdmPowerOutOfRange { receiver, power, min, max } => {
+                write!(f, "OFDM receiver {} PLC power out of range: {:.1} dBmV (expected: {:.1} to {:.1} dBmV)", receiver, power, min, max)
+            }
+            ChannelAnomaly::OfdmaPowerOutOfRange { channel_index, power, min, max } => {
+                write!(f, "OFDMA upstream channel {} power out of range: {:.1} dBmV (expected: {:.1} to {:.1} dBmV)", channel_index, power, min, max)
+            }
+            ChannelAnomaly::Grouped(anomalies) => {
+                write!(f, "{} channels affected\n\n", anomalies.len())?;
+
+                for anomaly in anomalies {
+                    writeln!(f, "• {}", anomaly)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Render an uptime as days, hours and minutes
+fn format_uptime(uptime: Duration) -> String {
+    let minutes = uptime.as_secs() / 60;
+    format!("{}d {}h {}m", minutes / 1440, minutes / 60 % 24, minutes % 60)
+}
+
+/// A renegotiation anomaly if a channel's frequency or modulation differs
+/// from the previous poll's
+fn check_renegotiated(
+    direction: Direction,
+    channel_id: u32,
+    previous: (f64, &str),
+    current: (f64, &str),
+) -> Option<ChannelAnomaly> {
+    (previous != current).then(|| ChannelAnomaly::ChannelRenegotiated {
+        direction,
+        channel_id,
+        from: (previous.0, previous.1.to_string()),
+        to: (current.0, current.1.to_string()),
+    })
+}
+
+/// Hash of an event log, for telling whether it changed between polls
+pub fn event_log_hash(events: &[EventLog]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for event in events {
+        (event.index, &event.time, &event.event_type, event.priority.to_string(), &event.event).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Check for the modem's web server serving the same stale pages: an
+/// event log hashing to `event_log_hash` unchanged from the last poll,
+/// along with downstream readings unchanged on the last downstream check
+pub fn check_ui_stalled(event_log_hash: u64, state: &mut ChannelState, thresholds: &ChannelThresholds) -> Vec<ChannelEvent> {
+    let unchanged = state.event_log_hash == Some(event_log_hash) && state.downstream_unchanged;
+    state.event_log_hash = Some(event_log_hash);
+    if unchanged {
+        state.frozen_polls += 1;
+    } else {
+        state.frozen_polls = 0;
+    }
+
+    let mut anomalies = Vec::new();
+    if let Some(polls) = thresholds.stalled_ui_polls {
+        if state.frozen_polls >= polls {
+            anomalies.push(ChannelAnomaly::ModemUiStalled {
+                polls: state.frozen_polls,
+            });
         }
     }
+    track_conditions(&mut state.active_ui, anomalies)
+}
+
+/// Growth of a WAN octet counter since the previous poll, counting from zero
+/// when it went backwards, as it does when the modem reboots
+fn octet_delta(current: u64, previous: u64) -> u64 {
+    current.checked_sub(previous).unwrap_or(current)
+}
+
+/// Growth of an error counter since the previous poll. The modem zeroes its
+/// counters when it reboots, so a counter that went backwards has counted
+/// up from zero since.
+fn counter_delta(current: i64, previous: i64) -> i64 {
+    if current < previous {
+        current
+    } else {
+        current - previous
+    }
+}
+
+/// Compare the number of locked channels against the configured count, or
+/// else the one learned from the first poll that raised no other anomaly.
+/// `seen` is every channel locked on an earlier poll, to name the missing ones.
+fn check_channel_count(
+    direction: Direction,
+    locked: &[u32],
+    seen: &[u32],
+    configured: Option<usize>,
+    learned: &mut Option<usize>,
+    healthy: bool,
+) -> Option<ChannelAnomaly> {
+    let expected = configured.or(*learned);
+    if expected.is_none() && healthy && !locked.is_empty() {
+        *learned = Some(locked.len());
+    }
+
+    let expected = expected?;
+    if locked.len() >= expected {
+        return None;
+    }
+    let mut missing: Vec<u32> = seen.iter().copied().filter(|channel_id| !locked.contains(channel_id)).collect();
+    missing.sort_unstable();
+    Some(ChannelAnomaly::ChannelCountDropped {
+        direction,
+        locked: locked.len(),
+        expected,
+        missing,
+    })
+}
+
+/// Spot a channel list that came back empty after the modem reported
+/// channels, which would otherwise pass for a poll with nothing wrong.
+/// `last_count` is the size of the last non-empty list.
+fn check_channels_reported(
+    direction: Direction,
+    reported: usize,
+    last_count: &mut Option<usize>,
+) -> Option<ChannelAnomaly> {
+    if reported > 0 {
+        *last_count = Some(reported);
+        return None;
+    }
+    last_count.map(|last_count| ChannelAnomaly::NoChannelsReported { direction, last_count })
+}
+
+/// Least-squares slope of one level against time across `readings`, in dB
+/// per minute, or `None` if they were all taken at the same moment
+fn slope_per_minute(readings: &VecDeque<Reading>, level: impl Fn(&Reading) -> f64) -> Option<f64> {
+    let first = readings.front()?.at;
+    let points: Vec<(f64, f64)> = readings
+        .iter()
+        .map(|r| (r.at.duration_since(first).as_secs_f64() / 60.0, level(r)))
+        .collect();
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+/// How far a channel's SNR sits above its configured minimum, in dB
+pub fn snr_margin(channel: &DownstreamChannel, thresholds: &ChannelThresholds) -> f64 {
+    channel.snr - thresholds.downstream_snr_min_for(channel.channel_id)
 }
 
 pub fn check_downstream_channels(
-    channels: &[DownstreamChannel],
+    all_channels: &[DownstreamChannel],
     state: &mut ChannelState,
     thresholds: &ChannelThresholds,
-) -> Vec<ChannelAnomaly> {
+) -> Vec<ChannelEvent> {
+    let channels: Vec<DownstreamChannel> = all_channels
+        .iter()
+        .filter(|c| thresholds.channels.allows(c.channel_id))
+        .cloned()
+        .collect();
+    // A live line's readings always move a little; none of them moving at
+    // all is a sign of a stale page
+    state.downstream_unchanged = !channels.is_empty()
+        && channels.iter().all(|c| {
+            state.previous_downstream.get(&c.channel_id).is_some_and(|prev| {
+                (prev.frequency, prev.signal_strength, prev.snr, prev.correcteds, prev.uncorrect)
+                    == (c.frequency, c.signal_strength, c.snr, c.correcteds, c.uncorrect)
+            })
+        });
     let mut anomalies = Vec::new();
+    let seen: Vec<u32> = state.previous_downstream.keys().copied().collect();
 
     // Collect error stats for channels that exceed the threshold
     let mut triggered_channels = Vec::new();
 
-    for channel in channels {
-        // Check SNR
-        if channel.snr < thresholds.downstream_snr_min {
+    let now = Instant::now();
+    let elapsed_minutes = state
+        .downstream_checked_at
+        .map(|at| now.duration_since(at).as_secs_f64() / 60.0)
+        .filter(|minutes| *minutes > 0.0);
+
+    for channel in &channels {
+        state.digest.downstream_snr.add(channel.snr);
+        state.digest.downstream_signal.add(channel.signal_strength);
+
+        // Check SNR, smoothed if asked so a brief dip doesn't cross the
+        // threshold but a sustained decline does
+        let snr = match thresholds.snr_smoothing {
+            Some(alpha) => {
+                let smoothed = state
+                    .smoothed_snr
+                    .get(&channel.channel_id)
+                    .map_or(channel.snr, |previous| alpha * channel.snr + (1.0 - alpha) * previous);
+                state.smoothed_snr.insert(channel.channel_id, smoothed);
+                smoothed
+            }
+            None => {
+                // Don't report an average left over from a run with smoothing on
+                state.smoothed_snr.remove(&channel.channel_id);
+                channel.snr
+            }
+        };
+        let snr_min = thresholds.downstream_snr_min_for(channel.channel_id);
+        if snr < snr_min {
             anomalies.push(ChannelAnomaly::DownstreamLowSNR {
                 channel_id: channel.channel_id,
-                snr: channel.snr,
-                threshold: thresholds.downstream_snr_min,
+                snr,
+                threshold: snr_min,
             });
         }
 
         // Check signal strength
-        if channel.signal_strength < thresholds.downstream_signal_min
-            || channel.signal_strength > thresholds.downstream_signal_max
-        {
+        let (signal_min, signal_max) = thresholds.downstream_signal_range_for(channel.channel_id);
+        if channel.signal_strength < signal_min || channel.signal_strength > signal_max {
             anomalies.push(ChannelAnomaly::DownstreamSignalOutOfRange {
                 channel_id: channel.channel_id,
                 signal: channel.signal_strength,
-                min: thresholds.downstream_signal_min,
-                max: thresholds.downstream_signal_max,
+                min: signal_min,
+                max: signal_max,
             });
         }
 
+        // Check how fast the SNR margin is shrinking
+        if let (Some(max_rate), Some(minutes), Some(prev)) = (
+            thresholds.snr_margin_drop_rate,
+            elapsed_minutes,
+            state.previous_downstream.get(&channel.channel_id),
+        ) {
+            let margin = snr_margin(channel, thresholds);
+            let rate = (snr_margin(prev, thresholds) - margin) / minutes;
+            // Below zero the low-SNR check already fires
+            if margin > 0.0 && rate > max_rate {
+                anomalies.push(ChannelAnomaly::SnrMarginFalling {
+                    channel_id: channel.channel_id,
+                    margin,
+                    rate,
+                });
+            }
+        }
+
+        // Check for a steady slide over the last few polls, which a single
+        // poll-to-poll change can't tell apart from noise
+        if let Some(max_rate) = thresholds.degradation_rate {
+            let readings = state.recent_readings.entry(channel.channel_id).or_default();
+            readings.push_back(Reading {
+                at: now,
+                snr: channel.snr,
+                signal: channel.signal_strength,
+            });
+            while readings.len() > thresholds.degradation_window {
+                readings.pop_front();
+            }
+            if readings.len() == thresholds.degradation_window {
+                let slopes = [
+                    (Measurement::Snr, slope_per_minute(readings, |r| r.snr)),
+                    (Measurement::Signal, slope_per_minute(readings, |r| r.signal)),
+                ];
+                // One alert per channel, SNR first
+                let falling = slopes
+                    .into_iter()
+                    .find_map(|(measurement, slope)| Some((measurement, slope.filter(|slope| *slope < -max_rate)?)));
+                if let Some((measurement, slope)) = falling {
+                    anomalies.push(ChannelAnomaly::RapidDegradation {
+                        channel_id: channel.channel_id,
+                        measurement,
+                        slope,
+                        polls: readings.len(),
+                    });
+                }
+            }
+        }
+
+        // Check the error counters as a whole, which needs no previous
+        // reading, so a broken channel is caught on the first poll
+        if let Some(threshold) = thresholds.absolute_error_rate_threshold {
+            let total_errors = channel.uncorrect + channel.correcteds;
+            if channel.uncorrect > 0 {
+                let error_rate = channel.uncorrect as f64 / total_errors as f64;
+                if error_rate > threshold {
+                    anomalies.push(ChannelAnomaly::HighLifetimeErrorRate {
+                        channel_id: channel.channel_id,
+                        frequency: channel.frequency,
+                        error_rate,
+                        uncorrected: channel.uncorrect,
+                        corrected: channel.correcteds,
+                        threshold,
+                    });
+                }
+            }
+        }
+
         // Check for high error rates
         if let Some(prev) = state.previous_downstream.get(&channel.channel_id) {
-            let uncorrected_delta = channel.uncorrect - prev.uncorrect;
-            let corrected_delta = channel.correcteds - prev.correcteds;
+            anomalies.extend(check_renegotiated(
+                Direction::Downstream,
+                channel.channel_id,
+                (prev.frequency, &prev.modulation),
+                (channel.frequency, &channel.modulation),
+            ));
+
+            let uncorrected_delta = counter_delta(channel.uncorrect, prev.uncorrect);
+            let corrected_delta = counter_delta(channel.correcteds, prev.correcteds);
+            state.digest.uncorrectable += uncorrected_delta;
+
+            // Check corrected errors on their own, as FEC working hard
+            // keeps the uncorrectable ratio low
+            if let (Some(threshold), Some(minutes)) = (thresholds.corrected_rate_threshold, elapsed_minutes) {
+                let rate = corrected_delta as f64 / minutes;
+                if rate > threshold {
+                    anomalies.push(ChannelAnomaly::HighCorrectedRate {
+                        channel_id: channel.channel_id,
+                        corrected_delta,
+                        rate,
+                        threshold,
+                    });
+                }
+            }
 
             // Only check if there were new errors in this interval
             if uncorrected_delta > 0 || corrected_delta > 0 {
                 let total_errors = uncorrected_delta + corrected_delta;
                 let error_rate = uncorrected_delta as f64 / total_errors as f64;
 
-                if error_rate > thresholds.error_rate_threshold {
+                if error_rate > thresholds.error_rate_threshold
+                    || uncorrected_delta > thresholds.uncorrectable_error_increase
+                {
                     triggered_channels.push(ChannelErrorStats {
                         channel_id: channel.channel_id,
                         uncorrected_delta,
@@ -157,25 +1283,73 @@ pub fn check_downstream_channels(
         state.previous_downstream.insert(channel.channel_id, channel.clone());
     }
 
+    state.downstream_checked_at = Some(now);
+
     // If any channel triggered the error threshold, create a single anomaly
     if !triggered_channels.is_empty() {
         anomalies.push(ChannelAnomaly::HighErrorRate {
             threshold: thresholds.error_rate_threshold,
+            increase_threshold: thresholds.uncorrectable_error_increase,
             triggered_channels,
         });
     }
 
-    anomalies
+    // Check the primary channel; it carries the modem's timing and MAC
+    // management, so losing or moving it is a DOCSIS-level health signal.
+    // It is looked for among every channel, as a primary on an ignored
+    // channel is no less primary.
+    let primary = all_channels.iter().find(|c| c.primary).map(|c| c.channel_id);
+    match (state.primary_channel, primary) {
+        (Some(from), Some(to)) if from != to => {
+            anomalies.push(ChannelAnomaly::PrimaryChannelChanged { from, to });
+        }
+        (Some(last_primary), None) if !channels.is_empty() => {
+            anomalies.push(ChannelAnomaly::PartialService { last_primary });
+        }
+        _ => {}
+    }
+    if primary.is_some() {
+        state.primary_channel = primary;
+    }
+
+    // Check for dropped channels, often the earliest sign of trouble. Losing
+    // every channel gets its own alert rather than a count drop as well.
+    let locked: Vec<u32> = channels.iter().map(|c| c.channel_id).collect();
+    let healthy = anomalies.is_empty();
+    if let Some(anomaly) =
+        check_channels_reported(Direction::Downstream, all_channels.len(), &mut state.last_downstream_count)
+    {
+        anomalies.push(anomaly);
+    } else {
+        anomalies.extend(check_channel_count(
+            Direction::Downstream,
+            &locked,
+            &seen,
+            thresholds.expected_downstream_channels,
+            &mut state.learned_downstream_channels,
+            healthy,
+        ));
+    }
+
+    track_conditions(&mut state.active_downstream, anomalies)
 }
 
 pub fn check_upstream_channels(
-    channels: &[UpstreamChannel],
+    all_channels: &[UpstreamChannel],
     state: &mut ChannelState,
     thresholds: &ChannelThresholds,
-) -> Vec<ChannelAnomaly> {
+) -> Vec<ChannelEvent> {
+    let channels: Vec<UpstreamChannel> = all_channels
+        .iter()
+        .filter(|c| thresholds.channels.allows(c.channel_id))
+        .cloned()
+        .collect();
     let mut anomalies = Vec::new();
+    let seen: Vec<u32> = state.previous_upstream.keys().copied().collect();
+
+    for channel in &channels {
+        state.digest.upstream_signal.add(channel.signal_strength);
 
-    for channel in channels {
         // Check signal strength
         if channel.signal_strength < thresholds.upstream_signal_min
             || channel.signal_strength > thresholds.upstream_signal_max
@@ -188,9 +1362,1030 @@ pub fn check_upstream_channels(
             });
         }
 
+        // Check SNR, which not every firmware reports
+        if let Some(snr) = channel.snr.filter(|snr| *snr < thresholds.upstream_snr_min) {
+            anomalies.push(ChannelAnomaly::UpstreamLowSNR {
+                channel_id: channel.channel_id,
+                snr,
+                threshold: thresholds.upstream_snr_min,
+            });
+        }
+
+        if let Some(prev) = state.previous_upstream.get(&channel.channel_id) {
+            anomalies.extend(check_renegotiated(
+                Direction::Upstream,
+                channel.channel_id,
+                (prev.frequency, &prev.modulation_type),
+                (channel.frequency, &channel.modulation_type),
+            ));
+        }
+
         // Update state
         state.previous_upstream.insert(channel.channel_id, channel.clone());
     }
 
+    // Check for dropped channels
+    let locked: Vec<u32> = channels.iter().map(|c| c.channel_id).collect();
+    let healthy = anomalies.is_empty();
+    if let Some(anomaly) =
+        check_channels_reported(Direction::Upstream, all_channels.len(), &mut state.last_upstream_count)
+    {
+        anomalies.push(anomaly);
+    } else {
+        anomalies.extend(check_channel_count(
+            Direction::Upstream,
+            &locked,
+            &seen,
+            thresholds.expected_upstream_channels,
+            &mut state.learned_upstream_channels,
+            healthy,
+        ));
+    }
+
+    track_conditions(&mut state.active_upstream, anomalies)
+}
+
+/// Check the locked DOCSIS 3.1 OFDM downstream receivers' MER and PLC power
+/// against the OFDM limits. Idle receivers report zeroes and are skipped.
+pub fn check_downstream_ofdm(
+    receivers: &[DownstreamOfdm],
+    state: &mut ChannelState,
+    thresholds: &ChannelThresholds,
+) -> Vec<ChannelEvent> {
+    let mut anomalies = Vec::new();
+
+    for receiver in receivers.iter().filter(|r| r.is_locked()) {
+        if receiver.snr < thresholds.ofdm_snr_min {
+            anomalies.push(ChannelAnomaly::OfdmLowSNR {
+                receiver: receiver.receiver,
+                snr: receiver.snr,
+                threshold: thresholds.ofdm_snr_min,
+            });
+        }
+
+        if receiver.plc_power < thresholds.ofdm_power_min || receiver.plc_power > thresholds.ofdm_power_max {
+            anomalies.push(ChannelAnomaly::OfdmPowerOutOfRange {
+                receiver: receiver.receiver,
+                power: receiver.plc_power,
+                min: thresholds.ofdm_power_min,
+                max: thresholds.ofdm_power_max,
+            });
+        }
+    }
+
+    track_conditions(&mut state.active_downstream_ofdm, anomalies)
+}
+
+/// Check the active OFDMA upstream channels' transmit power against their
+/// own range
+pub fn check_upstream_ofdm(
+    channels: &[UpstreamOfdm],
+    state: &mut ChannelState,
+    thresholds: &ChannelThresholds,
+) -> Vec<ChannelEvent> {
+    let mut anomalies = Vec::new();
+
+    for channel in channels.iter().filter(|c| c.is_active()) {
+        if channel.power < thresholds.ofdma_power_min || channel.power > thresholds.ofdma_power_max {
+            anomalies.push(ChannelAnomaly::OfdmaPowerOutOfRange {
+                channel_index: channel.channel_index,
+                power: channel.power,
+                min: thresholds.ofdma_power_min,
+                max: thresholds.ofdma_power_max,
+            });
+        }
+    }
+
+    track_conditions(&mut state.active_upstream_ofdm, anomalies)
+}
+
+/// Check the modem's WAN configuration: alert while the ISP denies it
+/// network access, whenever it is handed a new IPv4 or IPv6 address, and
+/// while traffic has stalled despite access
+pub fn check_docsis_wan(
+    wan: &DocsisWan,
+    state: &mut ChannelState,
+    thresholds: &ChannelThresholds,
+) -> Vec<ChannelEvent> {
+    let mut anomalies = Vec::new();
+
+    if !wan.network_access {
+        anomalies.push(ChannelAnomaly::NetworkAccessDenied {
+            provisioning_state: wan.provisioning_state.clone(),
+        });
+    }
+
+    // An address that disappears isn't a change; network access covers it
+    if let Some(prev) = &state.previous_wan {
+        let (ipv4, ipv6) = wan.addresses();
+        let (prev_ipv4, prev_ipv6) = prev.addresses();
+        for (from, to) in [(prev_ipv4, ipv4), (prev_ipv6, ipv6)] {
+            if let (Some(from), Some(to)) = (from, to) {
+                if from != to {
+                    anomalies.push(ChannelAnomaly::WanAddressChanged {
+                        from: from.to_string(),
+                        to: to.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    // Check that traffic is flowing, on firmware that counts it
+    let now = Instant::now();
+    let deltas = state.previous_wan.as_ref().and_then(|prev| {
+        let sent = octet_delta(wan.tx_octets?, prev.tx_octets?);
+        let received = octet_delta(wan.rx_octets?, prev.rx_octets?);
+        Some((sent, received))
+    });
+    match deltas {
+        Some((sent, received)) => {
+            if let Some(seconds) = state.wan_checked_at.map(|at| now.duration_since(at).as_secs_f64()) {
+                debug!("WAN traffic since the last poll: {:.0} B/s received, {:.0} B/s sent",
+                    received as f64 / seconds, sent as f64 / seconds);
+            }
+            if sent == 0 && received == 0 && wan.network_access {
+                state.idle_wan_polls += 1;
+            } else {
+                state.idle_wan_polls = 0;
+            }
+        }
+        None => state.idle_wan_polls = 0,
+    }
+    if let Some(polls) = thresholds.stalled_traffic_polls {
+        if state.idle_wan_polls >= polls {
+            anomalies.push(ChannelAnomaly::StalledTraffic {
+                polls: state.idle_wan_polls,
+            });
+        }
+    }
+
+    state.previous_wan = Some(wan.clone());
+    state.wan_checked_at = Some(now);
+
+    track_conditions(&mut state.active_wan, anomalies)
+}
+
+/// Spot a reboot the event log may not mention: the modem's uptime went
+/// backwards since the last poll. Firmware that doesn't report uptime is
+/// never flagged.
+pub fn check_system_info(info: &SystemInfo, state: &mut ChannelState) -> Option<ChannelAnomaly> {
+    let uptime = info.system_uptime?;
+    let previous = state.previous_uptime.replace(uptime)?;
+    (uptime < previous).then_some(ChannelAnomaly::ModemRebooted { uptime, previous })
+}
+
+/// Compare each Ethernet port's link against the last poll, raising a change
+/// when it goes down, comes up, or renegotiates. Ports seen for the first
+/// time are only remembered.
+pub fn check_link_status(links: &[LinkStatus], state: &mut ChannelState) -> Vec<ChannelAnomaly> {
+    let mut anomalies = Vec::new();
+    for (index, link) in links.iter().enumerate() {
+        let port = link.port.clone().unwrap_or_else(|| format!("Port {}", index + 1));
+        let current = LinkState::from(link);
+        if let Some(previous) = state.previous_links.insert(port.clone(), current.clone()) {
+            if previous != current {
+                anomalies.push(ChannelAnomaly::LinkStateChanged {
+                    port,
+                    from: previous,
+                    to: current,
+                });
+            }
+        }
+    }
     anomalies
 }
+
+/// Merge per-channel anomalies of the same kind into a single `Grouped`
+/// anomaly, so a plant-wide problem produces one alert instead of one per
+/// channel. Order follows the first occurrence of each kind.
+pub fn collapse_anomalies(anomalies: Vec<ChannelAnomaly>) -> Vec<ChannelAnomaly> {
+    let mut groups: Vec<Vec<ChannelAnomaly>> = Vec::new();
+
+    for anomaly in anomalies {
+        let existing = if anomaly.channel_id().is_some() {
+            groups.iter_mut().find(|group| {
+                std::mem::discriminant(&group[0]) == std::mem::discriminant(&anomaly)
+            })
+        } else {
+            None
+        };
+
+        match existing {
+            Some(group) => group.push(anomaly),
+            None => groups.push(vec![anomaly]),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|mut group| {
+            if group.len() == 1 {
+                group.remove(0)
+            } else {
+                ChannelAnomaly::Grouped(group)
+            }
+        })
+        .collect()
+}
+
+/// Rate limit for repeated alerts about the same anomaly, so a channel
+/// flapping at the edge of its range doesn't alert on every poll
+#[derive(Debug, Clone)]
+pub struct AlertCooldown {
+    period: Duration,
+    sent: HashMap<CooldownKey, CooldownEntry>,
+}
+
+/// An anomaly's kind and the channels it covers
+type CooldownKey = (Discriminant<ChannelAnomaly>, Vec<(Direction, u32)>);
+
+#[derive(Debug, Clone)]
+struct CooldownEntry {
+    sent_at: Instant,
+    last_seen: Instant,
+    suppressed: u32,
+}
+
+impl AlertCooldown {
+    /// Allow one alert per anomaly every `period`; zero disables the limit
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            sent: HashMap::new(),
+        }
+    }
+
+    /// Decide whether to send an alert for `anomaly` now. Returns how many
+    /// identical alerts were suppressed since the last one was sent, or
+    /// `None` if this one should be suppressed too. `force` sends regardless,
+    /// e.g. when the alert has just escalated.
+    pub fn check(&mut self, anomaly: &ChannelAnomaly, now: Instant, force: bool) -> Option<u32> {
+        let mut channels = anomaly.affected_channels();
+        channels.sort_by_key(|(direction, channel_id)| (*direction == Direction::Upstream, *channel_id));
+        let key = (std::mem::discriminant(anomaly), channels);
+
+        // Every new address is news (e.g. for a dynamic DNS hook), however
+        // soon it follows the last, and so is every link going down or up
+        if matches!(
+            anomaly,
+            ChannelAnomaly::WanAddressChanged { .. } | ChannelAnomaly::LinkStateChanged { .. }
+        ) {
+            return Some(0);
+        }
+
+        // Forget anomalies that haven't recurred for a whole period, so a
+        // later occurrence isn't reported as still active
+        let period = self.period;
+        self.sent.retain(|_, entry| now.duration_since(entry.last_seen) <= period);
+
+        match self.sent.get_mut(&key) {
+            Some(entry) if !force && now.duration_since(entry.sent_at) < period => {
+                entry.suppressed += 1;
+                entry.last_seen = now;
+                None
+            }
+            previous => {
+                let suppressed = previous.map_or(0, |entry| entry.suppressed);
+                self.sent.insert(
+                    key,
+                    CooldownEntry {
+                        sent_at: now,
+                        last_seen: now,
+                        suppressed: 0,
+                    },
+                );
+                Some(suppressed)
+            }
+        }
+    }
+}
+
+/// Relative importance of channels in the health score. A channel's own
+/// weight wins over its modulation's; anything unlisted weighs 1.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelWeights {
+    pub downstream: HashMap<u32, f64>,
+    pub upstream: HashMap<u32, f64>,
+    /// Keyed by lowercased modulation name, e.g. "256qam" or "ofdm"
+    pub modulation: HashMap<String, f64>,
+}
+
+impl ChannelWeights {
+    fn weight(&self, direction: Direction, channel_id: u32, modulation: &str) -> f64 {
+        let by_channel = match direction {
+            Direction::Downstream => &self.downstream,
+            Direction::Upstream => &self.upstream,
+        };
+        by_channel
+            .get(&channel_id)
+            .or_else(|| self.modulation.get(&modulation.to_ascii_lowercase()))
+            .copied()
+            .unwrap_or(1.0)
+    }
+}
+
+/// Overall modem health from 0 to 100: the weighted share of channels not
+/// affected by any current anomaly. `None` when there is nothing to weigh.
+pub fn health_score(
+    downstream: &[DownstreamChannel],
+    upstream: &[UpstreamChannel],
+    anomalies: &[ChannelAnomaly],
+    weights: &ChannelWeights,
+) -> Option<f64> {
+    // Only SC-QAM channels are scored, and OFDM receiver numbers would
+    // collide with their IDs
+    let affected: HashSet<(Direction, u32)> = anomalies
+        .iter()
+        .filter(|anomaly| !anomaly.is_ofdm())
+        .flat_map(ChannelAnomaly::affected_channels)
+        .collect();
+
+    let channels: Vec<(Direction, u32, f64)> = downstream
+        .iter()
+        .map(|c| (Direction::Downstream, c.channel_id, &c.modulation))
+        .chain(upstream.iter().map(|c| (Direction::Upstream, c.channel_id, &c.modulation_type)))
+        .map(|(direction, id, modulation)| (direction, id, weights.weight(direction, id, modulation)))
+        .collect();
+
+    let total: f64 = channels.iter().map(|(_, _, weight)| weight).sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let healthy: f64 = channels
+        .iter()
+        .filter(|(direction, id, _)| !affected.contains(&(*direction, *id)))
+        .map(|(_, _, weight)| weight)
+        .sum();
+    Some(100.0 * healthy / total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn low_snr(channel_id: u32) -> ChannelAnomaly {
+        ChannelAnomaly::DownstreamLowSNR { channel_id, snr: 30.0, threshold: 33.0 }
+    }
+
+    fn upstream_out_of_range(channel_id: u32) -> ChannelAnomaly {
+        ChannelAnomaly::UpstreamSignalOutOfRange { channel_id, signal: 55.0, min: 37.0, max: 53.0 }
+    }
+
+    fn downstream(correcteds: i64, uncorrect: i64) -> DownstreamChannel {
+        DownstreamChannel {
+            port_id: 1,
+            frequency: 591_000_000.0,
+            modulation: "2".to_string(),
+            signal_strength: 3.0,
+            snr: 40.0,
+            correcteds,
+            uncorrect,
+            channel_id: 9,
+            primary: false,
+        }
+    }
+
+    fn error_rate_anomalies(
+        thresholds: &ChannelThresholds,
+        previous: DownstreamChannel,
+        current: DownstreamChannel,
+    ) -> usize {
+        let mut state = ChannelState::new();
+        check_downstream_channels(&[previous], &mut state, thresholds);
+        check_downstream_channels(&[current], &mut state, thresholds)
+            .iter()
+            .filter(|e| matches!(e, ChannelEvent::Raised(ChannelAnomaly::HighErrorRate { .. })))
+            .count()
+    }
+
+    #[test]
+    fn ofdm_has_its_own_limits() {
+        let thresholds = ChannelThresholds::default();
+        let mut state = ChannelState::new();
+        let mut receivers: Vec<DownstreamOfdm> =
+            serde_json::from_str(include_str!("../tests/fixtures/dsofdminfo.json")).unwrap();
+        assert!(check_downstream_ofdm(&receivers, &mut state, &thresholds).is_empty());
+
+        // 32 dB would pass as SC-QAM but not as OFDM; the idle receiver's
+        // zeroes are never flagged
+        receivers[0].snr = 32.0;
+        let events = check_downstream_ofdm(&receivers, &mut state, &thresholds);
+        assert!(matches!(
+            events.as_slice(),
+            [ChannelEvent::Raised(ChannelAnomaly::OfdmLowSNR { receiver: 0, .. })]
+        ));
+
+        let mut channels: Vec<UpstreamOfdm> =
+            serde_json::from_str(include_str!("../tests/fixtures/usofdminfo.json")).unwrap();
+        assert!(check_upstream_ofdm(&channels, &mut state, &thresholds).is_empty());
+        // Within the SC-QAM upstream range, but not OFDMA's
+        channels[0].power = 52.0;
+        let events = check_upstream_ofdm(&channels, &mut state, &thresholds);
+        assert!(matches!(
+            events.as_slice(),
+            [ChannelEvent::Raised(ChannelAnomaly::OfdmaPowerOutOfRange { channel_index: 0, .. })]
+        ));
+    }
+
+    #[test]
+    fn channel_override_replaces_global_limits() {
+        let thresholds = ChannelThresholds {
+            downstream_overrides: parse_channel_overrides("[downstream.1]\nsignal_max = 18.0\n").unwrap(),
+            ..ChannelThresholds::default()
+        };
+        let hot = |channel_id| DownstreamChannel { channel_id, signal_strength: 16.5, ..downstream(0, 0) };
+
+        // Channel 1 is allowed to run hot; channel 2 still uses the default 15 dBmV
+        let events = check_downstream_channels(&[hot(1), hot(2)], &mut ChannelState::new(), &thresholds);
+        assert!(matches!(
+            events[..],
+            [ChannelEvent::Raised(ChannelAnomaly::DownstreamSignalOutOfRange { channel_id: 2, max, .. })] if max == 15.0
+        ));
+
+        assert!(parse_channel_overrides("[downstream.first]\nsnr_min = 30.0\n").is_err());
+        assert!(parse_channel_overrides("[downstream.1]\nsnr = 30.0\n").is_err());
+    }
+
+    #[test]
+    fn uncorrectable_increase_fires_above_absolute_delta() {
+        // Ratio check effectively off, so only the absolute delta matters
+        let thresholds = ChannelThresholds {
+            error_rate_threshold: 1.0,
+            uncorrectable_error_increase: 50,
+            ..ChannelThresholds::default()
+        };
+        assert_eq!(error_rate_anomalies(&thresholds, downstream(0, 100), downstream(10_000, 150)), 0);
+        assert_eq!(error_rate_anomalies(&thresholds, downstream(0, 100), downstream(10_000, 151)), 1);
+    }
+
+    #[test]
+    fn upstream_snr_is_checked_only_when_reported() {
+        let upstream = |snr| UpstreamChannel {
+            port_id: 1,
+            frequency: 38_600_000.0,
+            bandwidth: "6400000".to_string(),
+            modulation_type: "64QAM".to_string(),
+            signal_strength: 44.0,
+            channel_id: 3,
+            snr,
+        };
+        let thresholds = ChannelThresholds::default();
+
+        let events = check_upstream_channels(&[upstream(Some(28.0))], &mut ChannelState::new(), &thresholds);
+        assert!(matches!(
+            events.as_slice(),
+            [ChannelEvent::Raised(ChannelAnomaly::UpstreamLowSNR { channel_id: 3, .. })]
+        ));
+        assert!(check_upstream_channels(&[upstream(Some(35.0))], &mut ChannelState::new(), &thresholds).is_empty());
+        assert!(check_upstream_channels(&[upstream(None)], &mut ChannelState::new(), &thresholds).is_empty());
+    }
+
+    #[test]
+    fn lifetime_error_rate_fires_on_the_first_poll() {
+        let thresholds = ChannelThresholds {
+            absolute_error_rate_threshold: Some(0.05),
+            ..ChannelThresholds::default()
+        };
+        let events = check_downstream_channels(&[downstream(900, 100)], &mut ChannelState::new(), &thresholds);
+        let [ChannelEvent::Raised(anomaly)] = events.as_slice() else {
+            panic!("expected one anomaly, got {:?}", events);
+        };
+        assert!(matches!(anomaly, ChannelAnomaly::HighLifetimeErrorRate { channel_id: 9, .. }));
+        assert!(anomaly.to_string().starts_with("Channel 9 (591.0 MHz) has a 10.00% lifetime error rate"));
+
+        let healthy = check_downstream_channels(&[downstream(990, 10)], &mut ChannelState::new(), &thresholds);
+        assert!(healthy.is_empty());
+        let disabled = check_downstream_channels(&[downstream(900, 100)], &mut ChannelState::new(), &ChannelThresholds::default());
+        assert!(disabled.is_empty());
+    }
+
+    #[test]
+    fn corrected_rate_fires_without_uncorrectables() {
+        let thresholds = ChannelThresholds {
+            corrected_rate_threshold: Some(1000.0),
+            ..ChannelThresholds::default()
+        };
+        let mut state = ChannelState::new();
+        check_downstream_channels(&[downstream(0, 0)], &mut state, &thresholds);
+        state.downstream_checked_at = Some(Instant::now() - Duration::from_secs(120));
+
+        let events = check_downstream_channels(&[downstream(5000, 0)], &mut state, &thresholds);
+        let [ChannelEvent::Raised(ChannelAnomaly::HighCorrectedRate { channel_id: 9, corrected_delta: 5000, rate, .. })] =
+            events.as_slice()
+        else {
+            panic!("expected only a corrected rate anomaly, got {:?}", events);
+        };
+        assert!((rate - 2500.0).abs() < 10.0, "{}", rate);
+
+        // 1500 over two minutes is under the threshold
+        state.downstream_checked_at = Some(Instant::now() - Duration::from_secs(120));
+        let events = check_downstream_channels(&[downstream(6500, 0)], &mut state, &thresholds);
+        assert!(matches!(events[..], [ChannelEvent::Cleared(ChannelAnomaly::HighCorrectedRate { .. })]));
+    }
+
+    #[test]
+    fn error_rate_fires_independently_of_delta() {
+        let thresholds = ChannelThresholds::default();
+        // 2 uncorrected against 8 corrected is a 20% rate but a tiny delta
+        assert_eq!(error_rate_anomalies(&thresholds, downstream(0, 0), downstream(8, 2)), 1);
+        // 1 uncorrected against 999 corrected is exactly 0.1%
+        assert_eq!(error_rate_anomalies(&thresholds, downstream(0, 0), downstream(999, 1)), 0);
+    }
+
+    #[test]
+    fn counter_reset_is_counted_from_zero() {
+        assert_eq!(counter_delta(150, 100), 50);
+        assert_eq!(counter_delta(3, 5000), 3);
+
+        // A reboot resets both counters; without reset handling the deltas
+        // would be hugely negative
+        let thresholds = ChannelThresholds::default();
+        assert_eq!(error_rate_anomalies(&thresholds, downstream(90_000, 5_000), downstream(1_000, 0)), 0);
+        // Errors since the reset still count
+        assert_eq!(error_rate_anomalies(&thresholds, downstream(90_000, 5_000), downstream(1_000, 200)), 1);
+    }
+
+    #[test]
+    fn low_snr_is_raised_then_cleared() {
+        let thresholds = ChannelThresholds::default();
+        let mut state = ChannelState::new();
+        let mut channel = downstream(0, 0);
+
+        channel.snr = 30.0;
+        let events = check_downstream_channels(&[channel.clone()], &mut state, &thresholds);
+        assert!(matches!(events[..], [ChannelEvent::Raised(ChannelAnomaly::DownstreamLowSNR { channel_id: 9, .. })]));
+
+        // Still low: raised again, nothing cleared
+        let events = check_downstream_channels(&[channel.clone()], &mut state, &thresholds);
+        assert!(matches!(events[..], [ChannelEvent::Raised(ChannelAnomaly::DownstreamLowSNR { .. })]));
+
+        channel.snr = 40.0;
+        let events = check_downstream_channels(&[channel.clone()], &mut state, &thresholds);
+        assert!(matches!(
+            events[..],
+            [ChannelEvent::Cleared(ChannelAnomaly::DownstreamLowSNR { channel_id: 9, snr, .. })] if snr == 30.0
+        ));
+
+        // Cleared only once
+        assert!(check_downstream_channels(&[channel], &mut state, &thresholds).is_empty());
+    }
+
+    #[test]
+    fn smoothed_snr_rides_out_a_brief_dip() {
+        let thresholds = ChannelThresholds {
+            snr_smoothing: Some(0.5),
+            ..ChannelThresholds::default()
+        };
+        let mut state = ChannelState::new();
+        let mut channel = downstream(0, 0);
+        check_downstream_channels(&[channel.clone()], &mut state, &thresholds);
+
+        // One poll at 30 dB only pulls the average down to 35
+        channel.snr = 30.0;
+        assert!(check_downstream_channels(&[channel.clone()], &mut state, &thresholds).is_empty());
+        assert_eq!(state.smoothed_snr[&9], 35.0);
+        channel.snr = 40.0;
+        assert!(check_downstream_channels(&[channel.clone()], &mut state, &thresholds).is_empty());
+
+        // Staying there does get it under the threshold
+        channel.snr = 30.0;
+        assert!(check_downstream_channels(&[channel.clone()], &mut state, &thresholds).is_empty());
+        let events = check_downstream_channels(&[channel], &mut state, &thresholds);
+        assert!(matches!(
+            events[..],
+            [ChannelEvent::Raised(ChannelAnomaly::DownstreamLowSNR { channel_id: 9, snr, .. })] if snr < 33.0
+        ));
+    }
+
+    fn wan(ipv4: &str, network_access: bool) -> DocsisWan {
+        DocsisWan {
+            ipv4_addr: Some(ipv4.to_string()),
+            network_access,
+            ..DocsisWan::default()
+        }
+    }
+
+    #[test]
+    fn stalled_traffic_is_raised_after_enough_idle_polls() {
+        let thresholds = ChannelThresholds {
+            stalled_traffic_polls: Some(2),
+            ..ChannelThresholds::default()
+        };
+        let mut state = ChannelState::new();
+        let counted = |tx, rx| DocsisWan {
+            tx_octets: Some(tx),
+            rx_octets: Some(rx),
+            ..wan("10.0.0.2", true)
+        };
+
+        let polls: Vec<Vec<ChannelEvent>> = [counted(100, 500), counted(100, 500), counted(100, 500), counted(100, 900)]
+            .iter()
+            .map(|wan| check_docsis_wan(wan, &mut state, &thresholds))
+            .collect();
+        assert!(polls[0].is_empty());
+        // One idle poll isn't a stall yet
+        assert!(polls[1].is_empty());
+        assert!(matches!(polls[2][..], [ChannelEvent::Raised(ChannelAnomaly::StalledTraffic { polls: 2 })]));
+        assert!(matches!(polls[3][..], [ChannelEvent::Cleared(ChannelAnomaly::StalledTraffic { .. })]));
+
+        // Idle while access is denied is down to the denial, not a stall
+        for _ in 0..3 {
+            let events = check_docsis_wan(&DocsisWan { network_access: false, ..counted(100, 900) }, &mut state, &thresholds);
+            assert!(!events.iter().any(|e| matches!(e, ChannelEvent::Raised(ChannelAnomaly::StalledTraffic { .. }))));
+        }
+    }
+
+    #[test]
+    fn steady_slide_is_rapid_degradation() {
+        let thresholds = ChannelThresholds {
+            degradation_rate: Some(0.5),
+            degradation_window: 3,
+            ..ChannelThresholds::default()
+        };
+        let mut state = ChannelState::new();
+        let mut poll = |snr: f64| {
+            // Space the polls a minute apart
+            for reading in state.recent_readings.values_mut().flatten() {
+                reading.at -= Duration::from_secs(60);
+            }
+            check_downstream_channels(&[DownstreamChannel { snr, ..downstream(0, 0) }], &mut state, &thresholds)
+        };
+
+        // Falling a dB a minute, but the window isn't full until the third poll
+        assert!(poll(40.0).is_empty());
+        assert!(poll(39.0).is_empty());
+        let events = poll(38.0);
+        let [ChannelEvent::Raised(ChannelAnomaly::RapidDegradation { channel_id: 9, measurement: Measurement::Snr, slope, polls: 3 })] =
+            events.as_slice()
+        else {
+            panic!("expected only rapid degradation, got {:?}", events);
+        };
+        assert!((slope + 1.0).abs() < 0.01, "{}", slope);
+
+        // Levelling off brings the slope back under the threshold
+        let events = poll(38.0);
+        assert!(matches!(events[..], [ChannelEvent::Cleared(ChannelAnomaly::RapidDegradation { .. })]));
+    }
+
+    #[test]
+    fn frozen_event_log_and_readings_are_a_stalled_ui() {
+        let thresholds = ChannelThresholds {
+            stalled_ui_polls: Some(2),
+            ..ChannelThresholds::default()
+        };
+        let mut state = ChannelState::new();
+        let mut poll = |channel: DownstreamChannel, hash: u64| {
+            check_downstream_channels(&[channel], &mut state, &thresholds);
+            check_ui_stalled(hash, &mut state, &thresholds)
+        };
+
+        assert!(poll(downstream(10, 0), 7).is_empty());
+        assert!(poll(downstream(10, 0), 7).is_empty());
+        // Moving counters mean a live page, whatever the log does
+        assert!(poll(downstream(20, 0), 7).is_empty());
+        assert!(poll(downstream(20, 0), 7).is_empty());
+        let events = poll(downstream(20, 0), 7);
+        assert!(matches!(events[..], [ChannelEvent::Raised(ChannelAnomaly::ModemUiStalled { polls: 2 })]));
+
+        let events = poll(downstream(20, 0), 8);
+        assert!(matches!(events[..], [ChannelEvent::Cleared(ChannelAnomaly::ModemUiStalled { .. })]));
+    }
+
+    #[test]
+    fn wan_address_change_and_lost_access_are_raised() {
+        let mut state = ChannelState::new();
+
+        // Nothing to compare against on the first poll
+        assert!(check_docsis_wan(&wan("10.0.0.2", true), &mut state, &ChannelThresholds::default()).is_empty());
+
+        let events = check_docsis_wan(&wan("10.0.0.7", false), &mut state, &ChannelThresholds::default());
+        assert!(matches!(events[..], [
+            ChannelEvent::Raised(ChannelAnomaly::NetworkAccessDenied { .. }),
+            ChannelEvent::Raised(ChannelAnomaly::WanAddressChanged { ref from, ref to }),
+        ] if from == "10.0.0.2" && to == "10.0.0.7"));
+
+        // Access restored clears the denial; the address change is one-off
+        let events = check_docsis_wan(&wan("10.0.0.7", true), &mut state, &ChannelThresholds::default());
+        assert!(matches!(events[..], [ChannelEvent::Cleared(ChannelAnomaly::NetworkAccessDenied { .. })]));
+
+        // Every address change gets through the cooldown
+        let mut cooldown = AlertCooldown::new(Duration::from_secs(900));
+        let changed = ChannelAnomaly::WanAddressChanged { from: "10.0.0.2".into(), to: "10.0.0.7".into() };
+        let now = Instant::now();
+        assert_eq!(cooldown.check(&changed, now, false), Some(0));
+        assert_eq!(cooldown.check(&changed, now, false), Some(0));
+    }
+
+    fn link(port: &str, up: bool, speed_mbps: Option<u32>) -> LinkStatus {
+        LinkStatus {
+            port: Some(port.to_string()),
+            up,
+            speed_mbps,
+            duplex: up.then_some(Duplex::Full),
+        }
+    }
+
+    #[test]
+    fn link_transitions_are_raised() {
+        let mut state = ChannelState::new();
+        assert!(check_link_status(&[link("WAN", true, Some(1000))], &mut state).is_empty());
+        assert!(check_link_status(&[link("WAN", true, Some(1000))], &mut state).is_empty());
+
+        let down = check_link_status(&[link("WAN", false, None)], &mut state);
+        assert_eq!(down.len(), 1);
+        assert_eq!(down[0].to_string(), "WAN link down (was 1Gbps full)");
+
+        let up = check_link_status(&[link("WAN", true, Some(100))], &mut state);
+        assert_eq!(up[0].to_string(), "WAN link up at 100Mbps full");
+
+        let faster = check_link_status(&[link("WAN", true, Some(2500))], &mut state);
+        assert_eq!(faster[0].to_string(), "WAN link now 2.5Gbps full (was 100Mbps full)");
+    }
+
+    #[test]
+    fn dropped_channels_are_named() {
+        let thresholds = ChannelThresholds::default();
+        let mut state = ChannelState::new();
+        let bonded = |ids: &[u32]| -> Vec<DownstreamChannel> {
+            ids.iter()
+                .map(|id| DownstreamChannel { channel_id: *id, ..downstream(0, 0) })
+                .collect()
+        };
+
+        // The first healthy poll sets the expected count
+        assert!(check_downstream_channels(&bonded(&[1, 2, 3, 4]), &mut state, &thresholds).is_empty());
+        assert_eq!(state.learned_downstream_channels, Some(4));
+
+        let events = check_downstream_channels(&bonded(&[1, 4]), &mut state, &thresholds);
+        assert!(matches!(&events[..], [ChannelEvent::Raised(anomaly @ ChannelAnomaly::ChannelCountDropped { .. })]
+            if anomaly.to_string() == "Downstream channel count dropped: 2 of 4 channels locked (missing: 2, 3)"));
+
+        let events = check_downstream_channels(&bonded(&[1, 2, 3, 4]), &mut state, &thresholds);
+        assert!(matches!(events[..], [ChannelEvent::Cleared(ChannelAnomaly::ChannelCountDropped { .. })]));
+
+        // A configured count wins over the learned one
+        let thresholds = ChannelThresholds {
+            expected_downstream_channels: Some(8),
+            ..ChannelThresholds::default()
+        };
+        let events = check_downstream_channels(&bonded(&[1, 2, 3, 4]), &mut state, &thresholds);
+        assert!(matches!(events[..], [ChannelEvent::Raised(ChannelAnomaly::ChannelCountDropped { expected: 8, .. })]));
+    }
+
+    #[test]
+    fn empty_channel_list_is_an_outage() {
+        let thresholds = ChannelThresholds::default();
+        let mut state = ChannelState::new();
+
+        // Nothing to lose before any channels were reported
+        assert!(check_upstream_channels(&[], &mut state, &thresholds).is_empty());
+
+        check_downstream_channels(&[downstream(0, 0)], &mut state, &thresholds);
+        let events = check_downstream_channels(&[], &mut state, &thresholds);
+        assert!(matches!(&events[..], [ChannelEvent::Raised(anomaly @ ChannelAnomaly::NoChannelsReported { last_count: 1, .. })]
+            if anomaly.to_string() == "Modem reported no downstream channels (was 1); it may be re-ranging"));
+
+        let events = check_downstream_channels(&[downstream(0, 0)], &mut state, &thresholds);
+        assert!(matches!(events[..], [ChannelEvent::Cleared(ChannelAnomaly::NoChannelsReported { .. })]));
+    }
+
+    #[test]
+    fn reset_baseline_skips_the_error_check() {
+        let thresholds = ChannelThresholds::default();
+        let mut state = ChannelState::new();
+        check_downstream_channels(&[downstream(1000, 0)], &mut state, &thresholds);
+        state.reset_error_baseline();
+        // Counted up from zero after the reboot, with no baseline to compare
+        let events = check_downstream_channels(&[downstream(10, 500)], &mut state, &thresholds);
+        assert!(events.is_empty());
+        assert_eq!(error_rate_anomalies(&thresholds, downstream(10, 500), downstream(10, 700)), 1);
+    }
+
+    #[test]
+    fn reboot_seen_twice_is_reported_once() {
+        let mut state = ChannelState::new();
+        let now = 1_800_000_000;
+        let info = |secs| SystemInfo {
+            system_uptime: Some(Duration::from_secs(secs)),
+            ..SystemInfo::default()
+        };
+        let logged = || ChannelAnomaly::RebootLogged { time: "06/27/26 15:23:34".into(), event: "System startup".into() };
+        check_system_info(&info(86_400), &mut state);
+        check_downstream_channels(&[downstream(1000, 0)], &mut state, &ChannelThresholds::default());
+
+        // Both signals on one poll: one reboot, reported as the uptime drop
+        let drop = check_system_info(&info(120), &mut state);
+        let reboot = state.settle_reboot(drop, Some(logged()), now);
+        assert!(matches!(reboot, Some(ChannelAnomaly::ModemRebooted { .. })));
+        assert!(state.previous_downstream.is_empty());
+
+        // The same reboot logged again on a later poll
+        check_downstream_channels(&[downstream(10, 0)], &mut state, &ChannelThresholds::default());
+        check_system_info(&info(180), &mut state);
+        assert!(state.settle_reboot(None, Some(logged()), now + 60).is_none());
+        assert!(!state.previous_downstream.is_empty());
+
+        // A later one is news
+        let drop = check_system_info(&info(60), &mut state);
+        assert!(state.settle_reboot(drop, None, now + 3 * 60 * 60).is_some());
+    }
+
+    #[test]
+    fn uptime_going_backwards_is_a_reboot() {
+        let mut state = ChannelState::new();
+        let info = |secs| SystemInfo {
+            system_uptime: Some(Duration::from_secs(secs)),
+            ..SystemInfo::default()
+        };
+
+        assert!(check_system_info(&info(86_400), &mut state).is_none());
+        // Hour-granular uptime that hasn't ticked over isn't a reboot
+        assert!(check_system_info(&info(86_400), &mut state).is_none());
+        assert!(matches!(
+            check_system_info(&info(300), &mut state),
+            Some(ChannelAnomaly::ModemRebooted { uptime, previous })
+                if uptime == Duration::from_secs(300) && previous == Duration::from_secs(86_400)
+        ));
+        assert!(check_system_info(&info(360), &mut state).is_none());
+        // Unreported uptime neither alerts nor forgets the last reading
+        assert!(check_system_info(&SystemInfo::default(), &mut state).is_none());
+        assert!(check_system_info(&info(60), &mut state).is_some());
+    }
+
+    #[test]
+    fn repeated_alerts_are_suppressed_until_cooldown_expires() {
+        let mut cooldown = AlertCooldown::new(Duration::from_secs(900));
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(cooldown.check(&upstream_out_of_range(2), at(0), false), Some(0));
+        assert_eq!(cooldown.check(&upstream_out_of_range(2), at(60), false), None);
+        assert_eq!(cooldown.check(&upstream_out_of_range(2), at(120), false), None);
+        // A different channel has its own window
+        assert_eq!(cooldown.check(&upstream_out_of_range(3), at(120), false), Some(0));
+        // Forced alerts go out, carrying the suppressed count
+        assert_eq!(cooldown.check(&upstream_out_of_range(3), at(180), true), Some(0));
+        assert_eq!(cooldown.check(&upstream_out_of_range(2), at(900), false), Some(2));
+        // Gone quiet for longer than the period, so a recurrence starts over
+        assert_eq!(cooldown.check(&upstream_out_of_range(2), at(2000), false), Some(0));
+    }
+
+    #[test]
+    fn zero_cooldown_never_suppresses() {
+        let mut cooldown = AlertCooldown::new(Duration::ZERO);
+        let now = Instant::now();
+        assert_eq!(cooldown.check(&low_snr(1), now, false), Some(0));
+        assert_eq!(cooldown.check(&low_snr(1), now, false), Some(0));
+    }
+
+    const BOTH: &[Direction] = &[Direction::Downstream, Direction::Upstream];
+
+    #[test]
+    fn consecutive_anomalies_are_counted_per_channel() {
+        let mut state = ChannelState::new();
+        state.record_anomalies(&[low_snr(1), low_snr(2)], BOTH);
+        state.record_anomalies(&[low_snr(1)], BOTH);
+        state.record_anomalies(&[low_snr(1), low_snr(2)], BOTH);
+
+        assert_eq!(state.consecutive_count(&low_snr(1)), 3);
+        // Channel 2 cleared for a poll, so its streak restarted
+        assert_eq!(state.consecutive_count(&low_snr(2)), 1);
+        assert_eq!(state.consecutive_count(&ChannelAnomaly::Grouped(vec![low_snr(1), low_snr(2)])), 3);
+    }
+
+    #[test]
+    fn anomalies_wait_for_confirmation() {
+        let mut state = ChannelState::new();
+        state.record_anomalies(&[low_snr(1)], BOTH);
+        assert!(!state.is_confirmed(&low_snr(1), 2));
+        assert!(state.is_confirmed(&low_snr(1), 1));
+
+        state.record_anomalies(&[low_snr(1)], BOTH);
+        assert!(state.is_confirmed(&low_snr(1), 2));
+
+        // A recovery starts the wait over
+        state.record_anomalies(&[], BOTH);
+        state.record_anomalies(&[low_snr(1)], BOTH);
+        assert!(!state.is_confirmed(&low_snr(1), 2));
+
+        let changed = ChannelAnomaly::WanAddressChanged { from: "10.0.0.2".into(), to: "10.0.0.7".into() };
+        assert!(state.is_confirmed(&changed, 3));
+    }
+
+    #[test]
+    fn group_waits_for_its_newest_member() {
+        let mut state = ChannelState::new();
+        let grouped = ChannelAnomaly::Grouped(vec![low_snr(1), low_snr(2)]);
+        state.record_anomalies(&[low_snr(1)], BOTH);
+        state.record_anomalies(&[low_snr(1), low_snr(2)], BOTH);
+
+        // Channel 1 alone has held long enough, but channel 2 hasn't
+        assert!(state.is_confirmed(&low_snr(1), 2));
+        assert!(!state.is_confirmed(&grouped, 2));
+
+        state.record_anomalies(&[low_snr(1), low_snr(2)], BOTH);
+        assert!(state.is_confirmed(&grouped, 2));
+    }
+
+    #[test]
+    fn unchecked_direction_keeps_its_streak() {
+        let mut state = ChannelState::new();
+        state.record_anomalies(&[upstream_out_of_range(3)], BOTH);
+        // Upstream fetch failed this poll
+        state.record_anomalies(&[], &[Direction::Downstream]);
+        state.record_anomalies(&[upstream_out_of_range(3)], BOTH);
+
+        assert_eq!(state.consecutive_count(&upstream_out_of_range(3)), 2);
+    }
+
+    fn primary_on(channel_id: Option<u32>) -> Vec<DownstreamChannel> {
+        [9, 10]
+            .into_iter()
+            .map(|id| DownstreamChannel {
+                channel_id: id,
+                primary: Some(id) == channel_id,
+                ..downstream(0, 0)
+            })
+            .collect()
+    }
+
+    fn primary_anomalies(polls: &[Option<u32>]) -> Vec<ChannelAnomaly> {
+        let thresholds = ChannelThresholds::default();
+        let mut state = ChannelState::new();
+        let mut anomalies = Vec::new();
+        for primary in polls {
+            for event in check_downstream_channels(&primary_on(*primary), &mut state, &thresholds) {
+                if let ChannelEvent::Raised(anomaly) = event {
+                    anomalies.push(anomaly);
+                }
+            }
+        }
+        anomalies
+    }
+
+    #[test]
+    fn primary_moving_is_a_change() {
+        let anomalies = primary_anomalies(&[Some(9), Some(9), Some(10)]);
+        assert!(matches!(anomalies[..], [ChannelAnomaly::PrimaryChannelChanged { from: 9, to: 10 }]));
+    }
+
+    #[test]
+    fn ignored_channels_are_not_checked() {
+        let reserved = DownstreamChannel {
+            channel_id: 33,
+            snr: 0.0,
+            ..downstream(0, 0)
+        };
+        let mut thresholds = ChannelThresholds::default();
+        let events = check_downstream_channels(&[downstream(0, 0), reserved.clone()], &mut ChannelState::new(), &thresholds);
+        assert!(matches!(events[..], [ChannelEvent::Raised(ChannelAnomaly::DownstreamLowSNR { channel_id: 33, .. })]));
+
+        thresholds.channels.ignore.insert(33);
+        let mut state = ChannelState::new();
+        assert!(check_downstream_channels(&[downstream(0, 0), reserved.clone()], &mut state, &thresholds).is_empty());
+        assert!(!state.previous_downstream.contains_key(&33));
+
+        thresholds.channels = ChannelFilter {
+            only: Some(HashSet::from([9])),
+            ignore: HashSet::new(),
+        };
+        assert!(check_downstream_channels(&[downstream(0, 0), reserved], &mut ChannelState::new(), &thresholds).is_empty());
+    }
+
+    #[test]
+    fn modulation_drop_is_a_renegotiation() {
+        let thresholds = ChannelThresholds::default();
+        let mut state = ChannelState::new();
+        let qam256 = DownstreamChannel {
+            modulation: "256QAM".to_string(),
+            ..downstream(0, 0)
+        };
+        let qam64 = DownstreamChannel {
+            modulation: "64QAM".to_string(),
+            ..downstream(0, 0)
+        };
+
+        assert!(check_downstream_channels(std::slice::from_ref(&qam256), &mut state, &thresholds).is_empty());
+        assert!(check_downstream_channels(&[qam256], &mut state, &thresholds).is_empty());
+        let events = check_downstream_channels(std::slice::from_ref(&qam64), &mut state, &thresholds);
+        let [ChannelEvent::Raised(anomaly)] = &events[..] else {
+            panic!("expected one renegotiation, got {:?}", events);
+        };
+        assert_eq!(anomaly.to_string(), "Downstream channel 9 renegotiated from 591.0 MHz 256QAM to 591.0 MHz 64QAM");
+        // A one-off change, so nothing clears on the next poll
+        assert!(check_downstream_channels(&[qam64], &mut state, &thresholds).is_empty());
+    }
+
+    #[test]
+    fn primary_disappearing_is_partial_service() {
+        let anomalies = primary_anomalies(&[Some(9), None]);
+        assert!(matches!(anomalies[..], [ChannelAnomaly::PartialService { last_primary: 9 }]));
+    }
+
+    #[test]
+    fn firmware_without_primary_flag_never_alerts() {
+        assert!(primary_anomalies(&[None, None, None]).is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+use crate::api::EventLog;
+use crate::digest::DigestStats;
+use crate::discord::{self, DiscordNotifier, Mentions};
+use crate::monitor::ChannelAnomaly;
+use crate::slack::SlackNotifier;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Put the title prefix and label, whichever are set, in front of a title
+pub fn decorate_title(prefix: Option<&str>, label: Option<&str>, title: &str) -> String {
+    let title = match label {
+        Some(label) => format!("[{}] {}", label, title),
+        None => title.to_string(),
+    };
+    match prefix {
+        Some(prefix) => format!("{} {}", prefix, title),
+        None => title,
+    }
+}
+
+/// Times a rate-limited webhook post is resent before the alert is given up
+pub const RATE_LIMIT_RETRIES: u32 = 3;
+
+/// Where notifications are posted, and so which backend posts them
+#[derive(Debug, Clone)]
+pub enum Destination {
+    Discord(discord::Destination),
+    /// A Slack incoming webhook URL
+    Slack(String),
+}
+
+impl Destination {
+    /// Pick the backend for an incoming webhook URL by its host: Slack's
+    /// webhooks live on hooks.slack.com, anything else is taken for Discord.
+    /// A thread can only be given for Discord.
+    pub fn from_webhook_url(url: &str, thread_id: Option<u64>) -> Result<Self> {
+        let slack = reqwest::Url::parse(url)
+            .ok()
+            .is_some_and(|url| url.host_str() == Some("hooks.slack.com"));
+        if !slack {
+            return Ok(Destination::Discord(discord::Destination::Webhook {
+                url: url.to_string(),
+                thread_id,
+            }));
+        }
+        if thread_id.is_some() {
+            bail!("--thread-id only applies to Discord webhooks");
+        }
+        Ok(Destination::Slack(url.to_string()))
+    }
+}
+
+/// A chat backend that alerts are posted to
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Set a footer shown on every message, e.g. to identify the modem
+    fn set_footer(&mut self, footer: String);
+
+    /// Prefix every message title with this label, e.g. "[failover]"
+    fn set_label(&mut self, label: String);
+
+    /// Prefix every message title with this text as is, ahead of any label
+    fn set_title_prefix(&mut self, prefix: String);
+
+    /// Only ping for channel alerts once the same anomaly has persisted for
+    /// more than this many consecutive polls, and show them as critical
+    fn set_escalate_after(&mut self, polls: Option<u32>);
+
+    /// Consecutive polls after which a channel alert is escalated
+    fn escalate_after(&self) -> Option<u32>;
+
+    /// Whether an anomaly seen on `consecutive` polls in a row has just
+    /// crossed the escalation threshold
+    fn escalates_at(&self, consecutive: u32) -> bool {
+        self.escalate_after().is_some_and(|after| consecutive == after + 1)
+    }
+
+    /// Send an event log entry. Urgent events are shown and pinged as
+    /// critical whatever priority the modem assigned them.
+    async fn send_event(&self, event: &EventLog, urgent: bool) -> Result<()>;
+
+    /// Send a channel anomaly alert. `consecutive` is how many polls in a
+    /// row the anomaly has been seen, for escalation, and `suppressed` how
+    /// many identical alerts the cooldown held back.
+    async fn send_channel_alert(&self, anomaly: &ChannelAnomaly, consecutive: u32, suppressed: u32) -> Result<()>;
+
+    /// Send a notice that a channel condition has cleared
+    async fn send_recovery(&self, anomaly: &ChannelAnomaly) -> Result<()>;
+
+    /// Send an informational notice that the modem's firmware changed
+    /// since the last run
+    async fn send_firmware_change(&self, old: &str, new: &str) -> Result<()>;
+
+    /// Send the daily summary of readings and anomalies
+    async fn send_digest(&self, stats: &DigestStats) -> Result<()>;
+
+    /// Send a harmless message, pinging the role and user if set, to check
+    /// the destination works
+    async fn send_test(&self) -> Result<()>;
+}
+
+/// Create the notifier for a destination, sending all but critical
+/// notifications to `digest_webhook` if given (Discord only). A dry-run
+/// notifier logs what it would send instead of sending it.
+pub async fn create(
+    destination: Destination,
+    digest_webhook: Option<String>,
+    mentions: Mentions,
+    dry_run: bool,
+) -> Result<Box<dyn Notifier>> {
+    Ok(match destination {
+        Destination::Discord(destination) => {
+            Box::new(DiscordNotifier::new(destination, digest_webhook, mentions, dry_run).await?)
+        }
+        Destination::Slack(_) if digest_webhook.is_some() => bail!("--digest-webhook only applies to Discord"),
+        Destination::Slack(url) => Box::new(SlackNotifier::new(url, dry_run)),
+    })
+}
+
+/// Log a fully rendered message in place of sending it, for `--dry-run`
+pub fn log_dry_run(title: &str, description: &str, mention: Option<&str>) {
+    match mention {
+        Some(mention) => info!("Dry run, would send (mentioning {}): {}\n{}", mention, title, description),
+        None => info!("Dry run, would send: {}\n{}", title, description),
+    }
+}
+
+/// POST a JSON body to an incoming webhook. When the service rate-limits
+/// the post (HTTP 429), wait as long as it asks and resend, up to
+/// `RATE_LIMIT_RETRIES` times.
+pub async fn post_webhook(client: &reqwest::Client, url: &str, body: &serde_json::Value) -> Result<()> {
+    let mut retries = 0;
+    loop {
+        let response = client.post(url).json(body).send().await?;
+        if response.status() != StatusCode::TOO_MANY_REQUESTS || retries == RATE_LIMIT_RETRIES {
+            response.error_for_status()?;
+            return Ok(());
+        }
+
+        let delay = retry_after(response).await;
+        retries += 1;
+        warn!(
+            "Webhook post was rate limited, resending in {:.1}s ({}/{})",
+            delay.as_secs_f64(),
+            retries,
+            RATE_LIMIT_RETRIES
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// How long a 429 response asks us to wait: the `retry_after` seconds in
+/// its JSON body (Discord), else the Retry-After header (Slack), else a
+/// second
+async fn retry_after(response: reqwest::Response) -> Duration {
+    let header = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<f64>().ok());
+    let body = response
+        .json::<serde_json::Value>()
+        .await
+        .ok()
+        .and_then(|body| body["retry_after"].as_f64());
+
+    body.or(header)
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map_or(Duration::from_secs(1), Duration::from_secs_f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhook_backend_is_picked_by_host() {
+        assert!(matches!(
+            Destination::from_webhook_url("https://hooks.slack.com/services/T000/B000/XXXX", None),
+            Ok(Destination::Slack(_))
+        ));
+        assert!(matches!(
+            Destination::from_webhook_url("https://discord.com/api/webhooks/1/abc", Some(99)),
+            Ok(Destination::Discord(discord::Destination::Webhook { thread_id: Some(99), .. }))
+        ));
+        assert!(Destination::from_webhook_url("https://hooks.slack.com/services/T000/B000/XXXX", Some(99)).is_err());
+    }
+}
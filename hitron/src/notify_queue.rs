@@ -0,0 +1,231 @@
+//! Hands notifications from the poll loop to a task that sends them, so a
+//! slow or rate-limited webhook doesn't hold up the next poll
+
+use crate::api::{EventLog, EventPriority};
+use crate::digest::DigestStats;
+use crate::monitor::{ChannelAnomaly, Severity};
+use crate::notifier::Notifier;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{error, warn};
+
+/// Notifications waiting to be sent past which one is dropped to make room
+pub const CAPACITY: usize = 50;
+
+/// A notification waiting to be sent
+#[derive(Debug)]
+enum Message {
+    Event {
+        event: EventLog,
+        urgent: bool,
+    },
+    ChannelAlert {
+        anomaly: ChannelAnomaly,
+        consecutive: u32,
+        suppressed: u32,
+        escalating: bool,
+    },
+    Recovery(ChannelAnomaly),
+    Digest(DigestStats),
+}
+
+impl Message {
+    /// Whether this message may be dropped ahead of others when the queue
+    /// is full: anything but critical or urgent events and critical or
+    /// escalating alerts
+    fn is_low_priority(&self) -> bool {
+        match self {
+            Message::Event { event, urgent } => !urgent && event.priority != EventPriority::Critical,
+            Message::ChannelAlert { anomaly, escalating, .. } => {
+                !escalating && anomaly.severity() < Severity::Critical
+            }
+            Message::Recovery(_) | Message::Digest(_) => true,
+        }
+    }
+
+    /// What kind of notification this is, for logging
+    fn kind(&self) -> &'static str {
+        match self {
+            Message::Event { .. } => "event",
+            Message::ChannelAlert { .. } => "channel alert",
+            Message::Recovery(_) => "recovery notice",
+            Message::Digest(_) => "daily digest",
+        }
+    }
+
+    async fn send(&self, notifier: &dyn Notifier) {
+        let result = match self {
+            Message::Event { event, urgent } => notifier.send_event(event, *urgent).await,
+            Message::ChannelAlert { anomaly, consecutive, suppressed, .. } => {
+                notifier.send_channel_alert(anomaly, *consecutive, *suppressed).await
+            }
+            Message::Recovery(anomaly) => notifier.send_recovery(anomaly).await,
+            Message::Digest(stats) => notifier.send_digest(stats).await,
+        };
+        if let Err(e) = result {
+            error!("Failed to send {}: {}", self.kind(), e);
+        }
+    }
+}
+
+/// Messages waiting to be sent, shared between the queue and its task
+#[derive(Default)]
+struct Backlog {
+    messages: VecDeque<Message>,
+    /// Set once the queue stops taking notifications
+    closed: bool,
+}
+
+/// The backlog and a wake-up for the task when something is added to it
+#[derive(Default)]
+struct Shared {
+    backlog: Mutex<Backlog>,
+    ready: Notify,
+}
+
+/// Queues notifications for a task that sends them one at a time, in order
+pub struct NotifyQueue {
+    notifier: Arc<dyn Notifier>,
+    shared: Arc<Shared>,
+    task: JoinHandle<()>,
+}
+
+impl NotifyQueue {
+    /// Start the task sending through `notifier`, which waits out rate
+    /// limits itself
+    pub fn spawn(notifier: Box<dyn Notifier>) -> Self {
+        let notifier: Arc<dyn Notifier> = Arc::from(notifier);
+        let shared = Arc::new(Shared::default());
+        let task = tokio::spawn(run(notifier.clone(), shared.clone()));
+        Self { notifier, shared, task }
+    }
+
+    /// Whether an anomaly seen on `consecutive` polls in a row has just
+    /// crossed the notifier's escalation threshold
+    pub fn escalates_at(&self, consecutive: u32) -> bool {
+        self.notifier.escalates_at(consecutive)
+    }
+
+    pub fn send_event(&self, event: &EventLog, urgent: bool) {
+        self.push(Message::Event { event: event.clone(), urgent });
+    }
+
+    pub fn send_channel_alert(&self, anomaly: &ChannelAnomaly, consecutive: u32, suppressed: u32) {
+        self.push(Message::ChannelAlert {
+            anomaly: anomaly.clone(),
+            consecutive,
+            suppressed,
+            escalating: self.escalates_at(consecutive),
+        });
+    }
+
+    pub fn send_recovery(&self, anomaly: &ChannelAnomaly) {
+        self.push(Message::Recovery(anomaly.clone()));
+    }
+
+    pub fn send_digest(&self, stats: &DigestStats) {
+        self.push(Message::Digest(stats.clone()));
+    }
+
+    /// Add a message to the capped backlog, dropping one if it is full
+    fn push(&self, message: Message) {
+        if self.task.is_finished() {
+            error!("Notifier task has stopped, dropping {}", message.kind());
+            return;
+        }
+        enqueue(&mut self.shared.backlog.lock().unwrap().messages, message, CAPACITY);
+        self.shared.ready.notify_one();
+    }
+
+    /// Stop taking notifications and wait for the queued ones to be sent
+    pub async fn close(self) {
+        self.shared.backlog.lock().unwrap().closed = true;
+        self.shared.ready.notify_one();
+        if let Err(e) = self.task.await {
+            error!("Notifier task failed: {}", e);
+        }
+    }
+}
+
+/// Send queued messages until the queue is closed and drained
+async fn run(notifier: Arc<dyn Notifier>, shared: Arc<Shared>) {
+    loop {
+        let message = {
+            let mut backlog = shared.backlog.lock().unwrap();
+            match backlog.messages.pop_front() {
+                Some(message) => Some(message),
+                None if backlog.closed => return,
+                None => None,
+            }
+        };
+        match message {
+            Some(message) => message.send(notifier.as_ref()).await,
+            None => shared.ready.notified().await,
+        }
+    }
+}
+
+/// Add a message to the backlog. When it is full, the oldest low-priority
+/// message is dropped to make room, or the new one if it is low priority
+/// and nothing queued is, or else the oldest message.
+fn enqueue(backlog: &mut VecDeque<Message>, message: Message, capacity: usize) {
+    if backlog.len() >= capacity {
+        let dropped = match backlog.iter().position(Message::is_low_priority) {
+            Some(index) => backlog.remove(index),
+            None if message.is_low_priority() => {
+                warn!("Notification queue is full, dropping a {}", message.kind());
+                return;
+            }
+            None => backlog.pop_front(),
+        };
+        if let Some(dropped) = dropped {
+            warn!("Notification queue is full, dropping a {}", dropped.kind());
+        }
+    }
+    backlog.push_back(message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::Direction;
+
+    fn alert(anomaly: ChannelAnomaly) -> Message {
+        Message::ChannelAlert {
+            anomaly,
+            consecutive: 1,
+            suppressed: 0,
+            escalating: false,
+        }
+    }
+
+    fn critical() -> Message {
+        alert(ChannelAnomaly::NoChannelsReported { direction: Direction::Downstream, last_count: 24 })
+    }
+
+    fn warning() -> Message {
+        alert(ChannelAnomaly::DownstreamLowSNR { channel_id: 1, snr: 30.0, threshold: 33.0 })
+    }
+
+    #[test]
+    fn full_queue_drops_the_oldest_low_priority_message() {
+        let mut backlog = VecDeque::new();
+        enqueue(&mut backlog, critical(), 2);
+        enqueue(&mut backlog, warning(), 2);
+        enqueue(&mut backlog, critical(), 2);
+        assert!(backlog.iter().all(|m| !m.is_low_priority()));
+
+        // Nothing queued can make way for a low-priority message
+        enqueue(&mut backlog, Message::Recovery(ChannelAnomaly::Grouped(Vec::new())), 2);
+        assert_eq!(backlog.len(), 2);
+        assert!(backlog.iter().all(|m| !m.is_low_priority()));
+
+        // An escalation is never low priority, so it pushes out the oldest
+        let Message::ChannelAlert { anomaly, .. } = warning() else { unreachable!() };
+        enqueue(&mut backlog, Message::ChannelAlert { anomaly, consecutive: 4, suppressed: 0, escalating: true }, 2);
+        assert_eq!(backlog.len(), 2);
+        assert!(matches!(backlog.back(), Some(Message::ChannelAlert { escalating: true, .. })));
+    }
+}
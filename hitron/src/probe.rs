@@ -0,0 +1,29 @@
+use crate::api::{self, ApiClient};
+use anyhow::Result;
+use tracing::{info, warn};
+
+/// Log the outcome of probing one endpoint
+fn report(endpoint: &str, result: Result<usize>) {
+    match result {
+        Ok(0) => warn!("Probe: {} parsed but returned no entries", endpoint),
+        Ok(count) => info!("Probe: {} OK ({} entr{})", endpoint, count, if count == 1 { "y" } else { "ies" }),
+        Err(e) => warn!("Probe: {} failed: {}", endpoint, e),
+    }
+}
+
+/// Hit every endpoint once and log whether it parsed and how many entries
+/// it returned, so a modem the monitor can't talk to is obvious before the
+/// first poll interval elapses. Never fails; problems are only logged.
+pub async fn run(client: &ApiClient) {
+    info!("Probing modem endpoints");
+
+    report("system_model.asp", api::get_system_model(client).await.map(|_| 1));
+    report("getSysInfo.asp", api::get_system_info(client).await.map(|v| v.len()));
+    report("getLinkStatus.asp", api::get_link_status(client).await.map(|v| v.len()));
+    report("getCmDocsisWan.asp", api::get_docsis_wan(client).await.map(|v| v.len()));
+    report("dsinfo.asp", api::get_downstream_info(client).await.map(|v| v.len()));
+    report("dsofdminfo.asp", api::get_downstream_ofdm(client).await.map(|v| v.len()));
+    report("usinfo.asp", api::get_upstream_info(client).await.map(|v| v.len()));
+    report("usofdminfo.asp", api::get_upstream_ofdm(client).await.map(|v| v.len()));
+    report("status_log.asp", api::get_event_log(client).await.map(|v| v.len()));
+}
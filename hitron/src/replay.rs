@@ -0,0 +1,128 @@
+//! Responses recorded from the modem, read back in place of its own so a
+//! captured outage can be run through the checks again, e.g. to tune
+//! thresholds
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use hitron_monitor::api::{self, Source};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::info;
+
+/// Recorded polls, each a set of files named TIMESTAMP.ENDPOINT.json with
+/// the same timestamp, where ENDPOINT is a `dump` endpoint name
+pub struct Replay {
+    /// Each poll's timestamp and its recorded files by endpoint, e.g.
+    /// "dsinfo.asp", oldest first
+    polls: Vec<(String, HashMap<&'static str, PathBuf>)>,
+    /// Polls started so far; the latest is the one being read
+    started: AtomicUsize,
+}
+
+impl Replay {
+    /// Find the recorded polls in `dir`, ordered by timestamp. Files not
+    /// ending in .json are skipped.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let entries = std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+        let mut polls: BTreeMap<String, HashMap<&'static str, PathBuf>> = BTreeMap::new();
+        for entry in entries {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let Some(stem) = name.strip_suffix(".json") else {
+                continue;
+            };
+            let Some((timestamp, short)) = stem.rsplit_once('.') else {
+                bail!("Recorded response {} is not named TIMESTAMP.ENDPOINT.json", name);
+            };
+            let endpoint = api::ENDPOINTS
+                .iter()
+                .find(|(name, _)| *name == short)
+                .map(|(_, endpoint)| *endpoint)
+                .ok_or_else(|| anyhow!("Recorded response {} is for an unknown endpoint '{}'", name, short))?;
+            let timestamp = timestamp.to_string();
+            polls.entry(timestamp).or_default().insert(endpoint, path);
+        }
+        if polls.is_empty() {
+            bail!("No recorded responses in {}", dir.display());
+        }
+        Ok(Self {
+            polls: polls.into_iter().collect(),
+            started: AtomicUsize::new(0),
+        })
+    }
+
+    /// Number of recorded polls
+    pub fn polls(&self) -> usize {
+        self.polls.len()
+    }
+
+    /// The poll being read: the first until one is started, and the last
+    /// once they have run out
+    fn current(&self) -> &(String, HashMap<&'static str, PathBuf>) {
+        let index = self.started.load(Ordering::Relaxed).saturating_sub(1);
+        &self.polls[index.min(self.polls.len() - 1)]
+    }
+}
+
+#[async_trait]
+impl Source for Replay {
+    fn location(&self, endpoint: &str) -> String {
+        let (timestamp, files) = self.current();
+        match files.get(endpoint) {
+            Some(path) => path.display().to_string(),
+            None => format!("{} at {}", endpoint, timestamp),
+        }
+    }
+
+    async fn get(&self, endpoint: &str) -> Result<String> {
+        let (timestamp, files) = self.current();
+        let path = files
+            .get(endpoint)
+            .ok_or_else(|| anyhow!("No response from {} was recorded at {}", endpoint, timestamp))?;
+        tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    fn next_poll(&self) -> bool {
+        let started = self.started.fetch_add(1, Ordering::Relaxed) + 1;
+        if started > self.polls.len() {
+            return false;
+        }
+        info!("Replaying poll {}/{} recorded at {}", started, self.polls.len(), self.polls[started - 1].0);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn polls_are_replayed_in_timestamp_order() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("20260627T1524.dsinfo.json"), "second").unwrap();
+        std::fs::write(dir.path().join("20260627T1523.dsinfo.json"), "first").unwrap();
+        std::fs::write(dir.path().join("20260627T1523.status_log.json"), "[]").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "skipped").unwrap();
+
+        let replay = Replay::load(dir.path()).unwrap();
+        assert_eq!(replay.polls(), 2);
+        // Read before the first poll, e.g. for the modem's identity
+        assert_eq!(replay.get("dsinfo.asp").await.unwrap(), "first");
+
+        assert!(replay.next_poll());
+        assert_eq!(replay.get("dsinfo.asp").await.unwrap(), "first");
+        assert_eq!(replay.get("status_log.asp").await.unwrap(), "[]");
+        assert!(replay.next_poll());
+        assert_eq!(replay.get("dsinfo.asp").await.unwrap(), "second");
+        assert!(replay.get("status_log.asp").await.is_err());
+        assert!(!replay.next_poll());
+
+        std::fs::write(dir.path().join("20260627T1525.bogus.json"), "{}").unwrap();
+        assert!(Replay::load(dir.path()).is_err());
+    }
+}
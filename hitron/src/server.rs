@@ -0,0 +1,132 @@
+use crate::metrics;
+use crate::status::SharedStatus;
+use anyhow::Result;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{Html, Json};
+use axum::routing::get;
+use axum::Router;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Self-contained dashboard page; it polls `/status` for its data
+const DASHBOARD_HTML: &str = include_str!("dashboard.html");
+
+async fn status(State(status): State<SharedStatus>) -> Json<crate::status::Status> {
+    Json(status.read().expect("status lock poisoned").clone())
+}
+
+async fn dashboard() -> Html<&'static str> {
+    Html(DASHBOARD_HTML)
+}
+
+/// Serve `/status` as JSON, plus the HTML dashboard at `/` if enabled
+pub async fn serve(addr: SocketAddr, status_state: SharedStatus, with_dashboard: bool) -> Result<()> {
+    let mut app = Router::new().route("/status", get(status));
+    if with_dashboard {
+        app = app.route("/", get(dashboard));
+    }
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Serving status on http://{}", addr);
+    axum::serve(listener, app.with_state(status_state)).await?;
+    Ok(())
+}
+
+/// Each modem's label (if any) and status, as scraped for metrics
+pub type ModemStatuses = Vec<(Option<String>, SharedStatus)>;
+
+async fn metrics(State(modems): State<Arc<ModemStatuses>>) -> ([(&'static str, &'static str); 1], String) {
+    let snapshots: Vec<_> = modems
+        .iter()
+        .map(|(label, status)| (label.clone(), status.read().expect("status lock poisoned").clone()))
+        .collect();
+    ([("content-type", "text/plain; version=0.0.4")], metrics::render(&snapshots))
+}
+
+/// Serve every modem's latest channel stats at `/metrics` for Prometheus
+pub async fn serve_metrics(addr: SocketAddr, modems: ModemStatuses) -> Result<()> {
+    let app = Router::new().route("/metrics", get(metrics)).with_state(Arc::new(modems));
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Serving metrics on http://{}/metrics", addr);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+/// What the health check needs: every modem's status, how recent a
+/// successful poll must be, and when the monitor started
+struct Health {
+    modems: ModemStatuses,
+    max_age: Duration,
+    started: Instant,
+}
+
+impl Health {
+    /// The modems whose last successful poll, or the monitor's start if
+    /// there hasn't been one, is older than the limit
+    fn stale(&self, now: Instant) -> Vec<String> {
+        self.modems
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, status))| {
+                let last = status.read().expect("status lock poisoned").last_successful_poll;
+                now.duration_since(last.unwrap_or(self.started)) > self.max_age
+            })
+            .map(|(i, (label, _))| label.clone().unwrap_or_else(|| format!("modem {}", i + 1)))
+            .collect()
+    }
+}
+
+async fn healthz(State(health): State<Arc<Health>>) -> (StatusCode, String) {
+    let stale = health.stale(Instant::now());
+    if stale.is_empty() {
+        (StatusCode::OK, "ok\n".to_string())
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("no successful poll within {}s: {}\n", health.max_age.as_secs(), stale.join(", ")),
+        )
+    }
+}
+
+/// Serve `/healthz` for container orchestration, failing once any modem
+/// has gone `max_age` without a successful poll
+pub async fn serve_health(addr: SocketAddr, modems: ModemStatuses, max_age: Duration) -> Result<()> {
+    let health = Health {
+        modems,
+        max_age,
+        started: Instant::now(),
+    };
+    let app = Router::new().route("/healthz", get(healthz)).with_state(Arc::new(health));
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Serving health check on http://{}/healthz", addr);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::Status;
+
+    #[test]
+    fn modems_without_a_recent_poll_are_stale() {
+        let started = Instant::now();
+        let polled = SharedStatus::default();
+        polled.write().unwrap().last_successful_poll = Some(started + Duration::from_secs(100));
+        let health = Health {
+            modems: vec![(Some("primary".to_string()), polled), (None, Arc::new(std::sync::RwLock::new(Status::default())))],
+            max_age: Duration::from_secs(120),
+            started,
+        };
+
+        // Until the limit has passed since startup, nothing is stale yet
+        assert!(health.stale(started + Duration::from_secs(60)).is_empty());
+        assert_eq!(health.stale(started + Duration::from_secs(200)), ["modem 2"]);
+        assert_eq!(health.stale(started + Duration::from_secs(300)), ["primary", "modem 2"]);
+    }
+}
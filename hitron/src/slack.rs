@@ -0,0 +1,193 @@
+use crate::api::EventLog;
+use crate::digest::DigestStats;
+use crate::discord::alert_title;
+use crate::monitor::ChannelAnomaly;
+use crate::notifier::{self, Notifier};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::json;
+
+/// Posts to a Slack incoming webhook as Block Kit messages. Slack has no
+/// equivalent of a Discord role, so nobody is mentioned; escalated alerts
+/// are marked in the text instead.
+pub struct SlackNotifier {
+    url: String,
+    client: reqwest::Client,
+    footer: Option<String>,
+    /// Prefixed to every title, to tell modems apart when monitoring several
+    label: Option<String>,
+    /// Prefixed to every title ahead of the label, e.g. to name the site
+    title_prefix: Option<String>,
+    escalate_after: Option<u32>,
+    /// Log messages instead of posting them
+    dry_run: bool,
+}
+
+/// A notification ready to send; `text` is Slack mrkdwn
+struct Message {
+    title: String,
+    text: String,
+}
+
+impl SlackNotifier {
+    pub fn new(url: String, dry_run: bool) -> Self {
+        Self {
+            url,
+            client: reqwest::Client::new(),
+            footer: None,
+            label: None,
+            title_prefix: None,
+            escalate_after: None,
+            dry_run,
+        }
+    }
+
+    /// Lay a message out as a header, a text section and, if there is a
+    /// footer, a context line
+    fn blocks(&self, message: Message) -> serde_json::Value {
+        let title = notifier::decorate_title(self.title_prefix.as_deref(), self.label.as_deref(), &message.title);
+        let mut blocks = vec![
+            json!({ "type": "header", "text": { "type": "plain_text", "text": title, "emoji": true } }),
+            json!({ "type": "section", "text": { "type": "mrkdwn", "text": message.text } }),
+        ];
+        if let Some(footer) = &self.footer {
+            blocks.push(json!({ "type": "context", "elements": [{ "type": "plain_text", "text": footer }] }));
+        }
+        // `text` is the fallback shown in notifications
+        json!({ "text": title, "blocks": blocks })
+    }
+
+    async fn execute(&self, message: Message) -> Result<()> {
+        if self.dry_run {
+            let body = self.blocks(message);
+            notifier::log_dry_run(
+                body["text"].as_str().unwrap_or_default(),
+                body["blocks"][1]["text"]["text"].as_str().unwrap_or_default(),
+                None,
+            );
+            return Ok(());
+        }
+        notifier::post_webhook(&self.client, &self.url, &self.blocks(message)).await
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn set_footer(&mut self, footer: String) {
+        self.footer = Some(footer);
+    }
+
+    fn set_label(&mut self, label: String) {
+        self.label = Some(label);
+    }
+
+    fn set_title_prefix(&mut self, prefix: String) {
+        self.title_prefix = Some(prefix);
+    }
+
+    fn set_escalate_after(&mut self, polls: Option<u32>) {
+        self.escalate_after = polls;
+    }
+
+    fn escalate_after(&self) -> Option<u32> {
+        self.escalate_after
+    }
+
+    async fn send_event(&self, event: &EventLog, urgent: bool) -> Result<()> {
+        self.execute(event_message(event, urgent)).await
+    }
+
+    async fn send_channel_alert(&self, anomaly: &ChannelAnomaly, consecutive: u32, suppressed: u32) -> Result<()> {
+        self.execute(channel_alert_message(anomaly, consecutive, suppressed, self.escalate_after))
+            .await
+    }
+
+    async fn send_recovery(&self, anomaly: &ChannelAnomaly) -> Result<()> {
+        self.execute(Message {
+            title: "✅ Recovered".to_string(),
+            text: format!("No longer detected:\n{}", anomaly),
+        })
+        .await
+    }
+
+    async fn send_firmware_change(&self, old: &str, new: &str) -> Result<()> {
+        self.execute(Message {
+            title: "ℹ️ Modem Firmware Changed".to_string(),
+            text: format!("*Previous:* {}\n*Current:* {}", old, new),
+        })
+        .await
+    }
+
+    async fn send_digest(&self, stats: &DigestStats) -> Result<()> {
+        self.execute(Message {
+            title: "📊 Daily Modem Summary".to_string(),
+            text: stats.to_string(),
+        })
+        .await
+    }
+
+    async fn send_test(&self) -> Result<()> {
+        self.execute(Message {
+            title: "Hitron monitor test message".to_string(),
+            text: "Notifications are set up correctly.".to_string(),
+        })
+        .await
+    }
+}
+
+/// Build the message for an event log entry
+fn event_message(event: &EventLog, urgent: bool) -> Message {
+    let title = if urgent {
+        format!("🔴 Modem Event: {} (urgent)", event.priority)
+    } else {
+        format!("Modem Event: {}", event.priority)
+    };
+    Message {
+        title,
+        text: format!("*Time:* {}\n*Type:* {}\n*Event:* {}", event.time, event.event_type, event.event),
+    }
+}
+
+/// Build the message for a channel anomaly seen on `consecutive` polls in
+/// a row, `suppressed` times since it was last sent
+fn channel_alert_message(
+    anomaly: &ChannelAnomaly,
+    consecutive: u32,
+    suppressed: u32,
+    escalate_after: Option<u32>,
+) -> Message {
+    let title = alert_title(anomaly);
+    let mut text = anomaly.to_string();
+
+    if escalate_after.is_some_and(|after| consecutive > after) {
+        text = format!("{}\n\n*Seen on {} consecutive polls*", text.trim_end(), consecutive);
+    }
+    if suppressed > 0 {
+        text = format!("{}\n\n(still active, {} occurrences suppressed)", text.trim_end(), suppressed);
+    }
+
+    Message { title, text }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_is_laid_out_as_blocks() {
+        let mut notifier = SlackNotifier::new("https://hooks.slack.com/services/T000/B000/XXXX".to_string(), false);
+        notifier.set_label("failover".to_string());
+        notifier.set_footer("CODA-4582".to_string());
+
+        let anomaly = ChannelAnomaly::DownstreamLowSNR { channel_id: 1, snr: 30.0, threshold: 33.0 };
+        let body = notifier.blocks(channel_alert_message(&anomaly, 4, 2, Some(2)));
+
+        assert_eq!(body["text"], "[failover] ⚠️ Low SNR Detected");
+        assert_eq!(body["blocks"][0]["text"]["text"], "[failover] ⚠️ Low SNR Detected");
+        let text = body["blocks"][1]["text"]["text"].as_str().unwrap();
+        assert!(text.starts_with("Channel 1 has low SNR"));
+        assert!(text.contains("*Seen on 4 consecutive polls*"));
+        assert!(text.ends_with("(still active, 2 occurrences suppressed)"));
+        assert_eq!(body["blocks"][2]["elements"][0]["text"], "CODA-4582");
+    }
+}
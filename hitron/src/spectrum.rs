@@ -0,0 +1,107 @@
+use crate::api::{DownstreamChannel, UpstreamChannel};
+use crate::monitor::Direction;
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+/// One channel's level at its frequency, for plotting signal/SNR against
+/// frequency as a crude spectrum view
+#[derive(Debug, Serialize)]
+struct SpectrumPoint<'a> {
+    timestamp: &'a str,
+    direction: Direction,
+    channel_id: u32,
+    frequency: f64,
+    signal_strength: f64,
+    snr: Option<f64>,
+}
+
+/// Append one JSON line per channel from this poll, ordered by frequency
+pub async fn append(path: &Path, downstream: &[DownstreamChannel], upstream: &[UpstreamChannel]) -> Result<()> {
+    let timestamp = chrono::Local::now().to_rfc3339();
+
+    let mut points: Vec<SpectrumPoint> = downstream
+        .iter()
+        .map(|c| SpectrumPoint {
+            timestamp: &timestamp,
+            direction: Direction::Downstream,
+            channel_id: c.channel_id,
+            frequency: c.frequency,
+            signal_strength: c.signal_strength,
+            snr: Some(c.snr),
+        })
+        .chain(upstream.iter().map(|c| SpectrumPoint {
+            timestamp: &timestamp,
+            direction: Direction::Upstream,
+            channel_id: c.channel_id,
+            frequency: c.frequency,
+            signal_strength: c.signal_strength,
+            snr: c.snr,
+        }))
+        .collect();
+    points.sort_by(|a, b| a.frequency.total_cmp(&b.frequency));
+
+    let mut lines = String::new();
+    for point in &points {
+        lines.push_str(&serde_json::to_string(point)?);
+        lines.push('\n');
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path).await?;
+    file.write_all(lines.as_bytes()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn downstream(snr: f64) -> DownstreamChannel {
+        DownstreamChannel {
+            port_id: 1,
+            frequency: 591_000_000.0,
+            modulation: "2".to_string(),
+            signal_strength: 3.0,
+            snr,
+            correcteds: 0,
+            uncorrect: 0,
+            channel_id: 9,
+            primary: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn points_are_ordered_by_frequency() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spectrum.jsonl");
+        let high = DownstreamChannel {
+            channel_id: 10,
+            frequency: 597_000_000.0,
+            ..downstream(40.0)
+        };
+        let upstream = UpstreamChannel {
+            port_id: 1,
+            frequency: 38_700_000.0,
+            bandwidth: "6400000".to_string(),
+            modulation_type: "ATDMA".to_string(),
+            signal_strength: 42.0,
+            channel_id: 3,
+            snr: None,
+        };
+
+        append(&path, &[high, downstream(39.0)], &[upstream]).await.unwrap();
+
+        let text = fs::read_to_string(&path).await.unwrap();
+        let points: Vec<serde_json::Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        let channels: Vec<_> = points.iter().map(|p| p["channel_id"].as_u64().unwrap()).collect();
+        assert_eq!(channels, [3, 9, 10]);
+        assert_eq!(points[0]["direction"], "upstream");
+        assert!(points[0]["snr"].is_null());
+        assert_eq!(points[1]["snr"], 39.0);
+    }
+}
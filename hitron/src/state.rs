@@ -0,0 +1,334 @@
+use crate::dedup::{self, EventKey};
+use crate::monitor::ChannelState;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::{fs, time};
+use tracing::{debug, info, warn};
+
+/// Attempts made to write the state file within one poll
+const SAVE_ATTEMPTS: u32 = 3;
+
+/// On-disk layout of the state file. Event keys are sorted for stable output.
+#[derive(Debug, Serialize, Deserialize)]
+struct StateFile {
+    events: Vec<EventKey>,
+    #[serde(default)]
+    firmware: Option<String>,
+}
+
+/// What the monitor remembers between runs. Reporting is driven by the
+/// in-memory copy, so a failed state-file write never causes a re-send
+/// within this process; only the write is retried until it lands.
+#[derive(Debug, Default)]
+pub struct State {
+    /// Events already reported; `None` until the first poll after a fresh start
+    pub seen: Option<HashSet<EventKey>>,
+    /// Firmware version the modem reported on the last run
+    pub firmware: Option<String>,
+    /// Whether the fields above match what is in the state file
+    persisted: bool,
+}
+
+impl State {
+    /// Load state from the state file, starting fresh if there is none
+    pub async fn load(state_file: &Option<PathBuf>) -> Self {
+        let Some(path) = state_file else {
+            return Self::default();
+        };
+
+        match fs::read_to_string(path).await {
+            Ok(contents) => {
+                let state = parse(&contents);
+                match &state.seen {
+                    Some(keys) => debug!("Loaded {} seen event(s) from state file", keys.len()),
+                    // Covers the legacy format, which held a bare timestamp
+                    None => info!("State file is not a seen-event list, starting fresh"),
+                }
+                state
+            }
+            Err(e) => {
+                debug!("Could not read state file ({}), starting fresh", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Replace the seen-event set with a new snapshot
+    pub fn set_seen(&mut self, keys: HashSet<EventKey>) {
+        if self.seen.as_ref() != Some(&keys) {
+            self.seen = Some(keys);
+            self.persisted = false;
+        }
+    }
+
+    /// Record the modem's firmware version, returning the previous one if
+    /// it changed
+    pub fn set_firmware(&mut self, firmware: String) -> Option<String> {
+        if self.firmware.as_ref() == Some(&firmware) {
+            return None;
+        }
+        self.persisted = false;
+        self.firmware.replace(firmware)
+    }
+
+    /// Write the state file if anything changed since the last successful
+    /// write, retrying briefly
+    pub async fn save(&mut self, state_file: &Option<PathBuf>) -> Result<()> {
+        let Some(path) = state_file else {
+            return Ok(());
+        };
+        if self.persisted {
+            return Ok(());
+        }
+
+        let mut attempt = 1;
+        loop {
+            match self.write(path).await {
+                Ok(()) => {
+                    self.persisted = true;
+                    return Ok(());
+                }
+                Err(e) if attempt < SAVE_ATTEMPTS => {
+                    warn!("Failed to save state ({}), retrying ({}/{})", e, attempt, SAVE_ATTEMPTS - 1);
+                    attempt += 1;
+                    time::sleep(Duration::from_millis(250)).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn write(&self, path: &Path) -> Result<()> {
+        write_atomic(path, &self.serialize()).await?;
+        debug!("Saved {} seen event(s)", self.seen.as_ref().map_or(0, HashSet::len));
+        Ok(())
+    }
+
+    fn serialize(&self) -> String {
+        let mut events: Vec<EventKey> = self.seen.iter().flatten().cloned().collect();
+        events.sort();
+        let file = StateFile {
+            events,
+            firmware: self.firmware.clone(),
+        };
+        serde_json::to_string_pretty(&file).expect("state serializes to JSON")
+    }
+}
+
+/// `path` with `label` worked into the file name, so per-modem files don't
+/// clobber each other: `state.json` becomes `state.failover.json`
+pub fn namespaced_path(path: &Path, label: &str) -> PathBuf {
+    let label: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    match path.extension() {
+        Some(extension) => path.with_extension(format!("{}.{}", label, extension.to_string_lossy())),
+        None => path.with_extension(label),
+    }
+}
+
+/// Where channel readings are kept, next to the state file: `state.json`
+/// becomes `state.channels.json`. Only a `.json` extension is replaced;
+/// anything else, such as the label of an extensionless per-modem state
+/// file, is kept so modems don't share a file.
+pub fn channel_state_path(state_file: &Path) -> PathBuf {
+    let base = match state_file.extension() {
+        Some(extension) if extension == "json" => state_file.with_extension(""),
+        _ => state_file.to_path_buf(),
+    };
+    let mut name = base.into_os_string();
+    name.push(".channels.json");
+    PathBuf::from(name)
+}
+
+/// Load the last channel readings saved beside the state file, starting
+/// fresh if there are none
+pub async fn load_channel_state(state_file: &Option<PathBuf>) -> ChannelState {
+    let Some(path) = state_file.as_deref().map(channel_state_path) else {
+        return ChannelState::new();
+    };
+
+    let contents = match fs::read_to_string(&path).await {
+        Ok(contents) => contents,
+        Err(e) => {
+            debug!("Could not read channel state ({}), starting fresh", e);
+            return ChannelState::new();
+        }
+    };
+    match serde_json::from_str::<ChannelState>(&contents) {
+        Ok(state) => {
+            debug!(
+                "Loaded readings for {} downstream and {} upstream channel(s)",
+                state.previous_downstream.len(),
+                state.previous_upstream.len()
+            );
+            state
+        }
+        Err(e) => {
+            info!("Channel state file is unreadable ({}), starting fresh", e);
+            ChannelState::new()
+        }
+    }
+}
+
+/// Save the latest channel readings beside the state file
+pub async fn save_channel_state(state_file: &Option<PathBuf>, state: &ChannelState) -> Result<()> {
+    let Some(path) = state_file.as_deref().map(channel_state_path) else {
+        return Ok(());
+    };
+    write_atomic(&path, &serde_json::to_string(state)?).await
+}
+
+/// Replace a file's contents via a temporary file and a rename, so a crash
+/// or kill mid-write never leaves a half-written file behind
+async fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    // Create parent directory if it doesn't exist
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+
+    let mut file = fs::File::create(&temp).await?;
+    file.write_all(contents.as_bytes()).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    fs::rename(&temp, path).await?;
+    Ok(())
+}
+
+/// Parse state-file contents. Older state files held a bare array of event
+/// keys (or, before that, a bare timestamp); anything unparseable is treated
+/// as no state so the monitor starts fresh.
+fn parse(contents: &str) -> State {
+    if let Ok(file) = serde_json::from_str::<StateFile>(contents) {
+        return State {
+            seen: Some(file.events.into_iter().collect()),
+            firmware: file.firmware,
+            persisted: true,
+        };
+    }
+
+    State {
+        seen: dedup::parse_state(contents),
+        firmware: None,
+        persisted: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(time: &str, event: &str) -> EventKey {
+        EventKey {
+            time: time.to_string(),
+            event_type: "82001100".to_string(),
+            event: event.to_string(),
+            repeat: 0,
+        }
+    }
+
+    #[test]
+    fn state_round_trips() {
+        let mut state = State::default();
+        state.set_seen([key("06/27/26 15:23:34", "RNG-RSP"), key("12/31/69 19:01:07", "POWER_ON")].into());
+        state.set_firmware("7.1.1.2.2b9".to_string());
+
+        let parsed = parse(&state.serialize());
+        assert_eq!(parsed.seen, state.seen);
+        assert_eq!(parsed.firmware, state.firmware);
+    }
+
+    #[test]
+    fn repeated_event_keys_round_trip() {
+        let mut state = State::default();
+        let repeat = EventKey { repeat: 2, ..key("06/27/26 15:23:34", "T3 time-out") };
+        state.set_seen([key("06/27/26 15:23:34", "T3 time-out"), repeat].into());
+
+        let text = state.serialize();
+        assert_eq!(text.matches("\"repeat\"").count(), 1);
+        assert_eq!(parse(&text).seen, state.seen);
+    }
+
+    #[test]
+    fn legacy_event_list_is_read() {
+        let legacy = r#"[{"time":"06/27/26 15:23:34","type":"82001100","event":"RNG-RSP"}]"#;
+        let parsed = parse(legacy);
+        assert_eq!(parsed.seen, Some([key("06/27/26 15:23:34", "RNG-RSP")].into()));
+        assert_eq!(parsed.firmware, None);
+    }
+
+    #[test]
+    fn channel_state_round_trips() {
+        let channels: Vec<crate::api::DownstreamChannel> =
+            serde_json::from_str(include_str!("../tests/fixtures/dsinfo.json")).unwrap();
+        let mut state = ChannelState::new();
+        for channel in channels {
+            state.previous_downstream.insert(channel.channel_id, channel);
+        }
+
+        let parsed: ChannelState = serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+        assert_eq!(parsed.previous_downstream.len(), 3);
+        assert_eq!(parsed.previous_downstream[&2].uncorrect, 2);
+        assert_eq!(parsed.previous_downstream[&2].snr, state.previous_downstream[&2].snr);
+    }
+
+    #[tokio::test]
+    async fn atomic_write_replaces_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+
+        write_atomic(&path, "first").await.unwrap();
+        write_atomic(&path, "second").await.unwrap();
+        assert_eq!(fs::read_to_string(&path).await.unwrap(), "second");
+        assert!(!dir.path().join("state.json.tmp").exists());
+    }
+
+    #[test]
+    fn channel_state_sits_beside_state_file() {
+        assert_eq!(
+            channel_state_path(Path::new("/var/lib/hitron-monitor/state.json")),
+            PathBuf::from("/var/lib/hitron-monitor/state.channels.json")
+        );
+        assert_eq!(channel_state_path(Path::new("state")), PathBuf::from("state.channels.json"));
+    }
+
+    #[test]
+    fn extensionless_per_modem_channel_states_are_apart() {
+        let state_file = Path::new("/var/lib/hitron-monitor/state");
+        let primary = channel_state_path(&namespaced_path(state_file, "primary"));
+        let failover = channel_state_path(&namespaced_path(state_file, "failover"));
+        assert_eq!(primary, PathBuf::from("/var/lib/hitron-monitor/state.primary.channels.json"));
+        assert_eq!(failover, PathBuf::from("/var/lib/hitron-monitor/state.failover.channels.json"));
+    }
+
+    #[test]
+    fn per_modem_paths_are_namespaced() {
+        assert_eq!(
+            namespaced_path(Path::new("/var/lib/hitron-monitor/state.json"), "failover"),
+            PathBuf::from("/var/lib/hitron-monitor/state.failover.json")
+        );
+        assert_eq!(namespaced_path(Path::new("state"), "192.168.0.1"), PathBuf::from("state.192_168_0_1"));
+        assert_eq!(
+            channel_state_path(&namespaced_path(Path::new("state.json"), "failover")),
+            PathBuf::from("state.failover.channels.json")
+        );
+    }
+
+    #[test]
+    fn firmware_change_reports_previous_version() {
+        let mut state = State::default();
+        assert_eq!(state.set_firmware("1.0".to_string()), None);
+        assert_eq!(state.set_firmware("1.0".to_string()), None);
+        assert_eq!(state.set_firmware("2.0".to_string()), Some("1.0".to_string()));
+    }
+}
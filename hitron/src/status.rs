@@ -0,0 +1,114 @@
+use crate::api::{DownstreamChannel, EventLog, UpstreamChannel};
+use crate::monitor::{self, ChannelAnomaly, ChannelThresholds, ChannelWeights};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+/// Number of most recent events kept in the status snapshot
+const RECENT_EVENTS: usize = 20;
+
+/// Snapshot of the monitor's latest view of the modem, served as JSON
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Status {
+    pub updated_at: Option<String>,
+    pub modem: Option<String>,
+    pub health_score: Option<f64>,
+    pub downstream: Vec<DownstreamStatus>,
+    pub upstream: Vec<UpstreamStatus>,
+    pub anomalies: Vec<String>,
+    pub recent_events: Vec<EventStatus>,
+    /// When the event log and channel info were last all fetched, for the
+    /// health check
+    #[serde(skip)]
+    pub last_successful_poll: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownstreamStatus {
+    pub channel_id: u32,
+    pub frequency: f64,
+    pub modulation: String,
+    pub signal_strength: f64,
+    pub snr: f64,
+    /// Exponentially smoothed SNR, with --snr-smoothing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smoothed_snr: Option<f64>,
+    pub snr_margin: f64,
+    pub correcteds: i64,
+    pub uncorrect: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpstreamStatus {
+    pub channel_id: u32,
+    pub frequency: f64,
+    pub modulation_type: String,
+    pub signal_strength: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventStatus {
+    pub time: String,
+    pub priority: String,
+    pub event_type: String,
+    pub event: String,
+}
+
+pub type SharedStatus = Arc<RwLock<Status>>;
+
+impl Status {
+    /// Keep the most recent events, newest first, from a log in
+    /// chronological order
+    pub fn set_events(&mut self, events: &[EventLog]) {
+        self.recent_events = events
+            .iter()
+            .rev()
+            .take(RECENT_EVENTS)
+            .map(|e| EventStatus {
+                time: e.time.clone(),
+                priority: e.priority.to_string(),
+                event_type: e.event_type.clone(),
+                event: e.event.clone(),
+            })
+            .collect();
+    }
+
+    /// Record the channels and anomalies from one poll
+    pub fn set_poll(
+        &mut self,
+        downstream: &[DownstreamChannel],
+        upstream: &[UpstreamChannel],
+        anomalies: &[ChannelAnomaly],
+        thresholds: &ChannelThresholds,
+        weights: &ChannelWeights,
+        smoothed_snr: &HashMap<u32, f64>,
+    ) {
+        self.updated_at = Some(chrono::Local::now().to_rfc3339());
+        self.health_score = monitor::health_score(downstream, upstream, anomalies, weights);
+        self.downstream = downstream
+            .iter()
+            .map(|c| DownstreamStatus {
+                channel_id: c.channel_id,
+                frequency: c.frequency,
+                modulation: c.modulation.clone(),
+                signal_strength: c.signal_strength,
+                snr: c.snr,
+                smoothed_snr: smoothed_snr.get(&c.channel_id).copied(),
+                snr_margin: monitor::snr_margin(c, thresholds),
+                correcteds: c.correcteds,
+                uncorrect: c.uncorrect,
+            })
+            .collect();
+        self.upstream = upstream
+            .iter()
+            .map(|c| UpstreamStatus {
+                channel_id: c.channel_id,
+                frequency: c.frequency,
+                modulation_type: c.modulation_type.clone(),
+                signal_strength: c.signal_strength,
+            })
+            .collect();
+        self.anomalies = anomalies.iter().map(ToString::to_string).collect();
+    }
+}
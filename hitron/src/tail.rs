@@ -0,0 +1,63 @@
+use crate::api::{self, ApiClient, EventLog, EventPriority};
+use crate::dedup::{self, EventKey};
+use anyhow::Result;
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::time;
+use tracing::error;
+
+/// ANSI color for an event priority
+fn priority_color(priority: &EventPriority) -> &'static str {
+    match priority {
+        EventPriority::Critical => "\x1b[31m", // Red
+        EventPriority::Warning => "\x1b[33m",  // Yellow
+        EventPriority::Notice => "\x1b[34m",   // Blue
+        EventPriority::Other(_) => "\x1b[90m",    // Gray
+    }
+}
+
+/// Format an event as a single terminal line, colored by priority if enabled
+fn format_event(event: &EventLog, color: bool) -> String {
+    let line = format!(
+        "{} [{:<8}] {} - {}",
+        event.time,
+        event.priority.to_string(),
+        event.event_type,
+        event.event
+    );
+    if color {
+        format!("{}{}\x1b[0m", priority_color(&event.priority), line)
+    } else {
+        line
+    }
+}
+
+/// Poll the event log and print new events to stdout, oldest first. The
+/// current log is printed on startup, then only events not seen before.
+pub async fn run(client: &ApiClient, interval: Duration, color: bool) -> Result<()> {
+    let mut seen: Option<HashSet<EventKey>> = None;
+    let mut interval_timer = time::interval(interval);
+
+    loop {
+        interval_timer.tick().await;
+
+        match api::get_event_log(client).await {
+            Ok(mut events) => {
+                dedup::normalize(&mut events);
+                let new_events: Vec<&EventLog> = match &seen {
+                    Some(keys) => dedup::new_events(&events, keys),
+                    None => events.iter().collect(),
+                };
+
+                for event in new_events {
+                    println!("{}", format_event(event, color));
+                }
+
+                seen = Some(dedup::snapshot(&events));
+            }
+            Err(e) => {
+                error!("Failed to fetch event log: {}", e);
+            }
+        }
+    }
+}
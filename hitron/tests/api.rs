@@ -0,0 +1,97 @@
+use axum::routing::get;
+use axum::Router;
+use hitron_monitor::api::{self, ApiError, ConnectRetry, EventPriority, RetryPolicy, SignalScale, TlsVerification};
+use std::time::Duration;
+
+/// Serve `app` on a local port and point a client at it, with retries off
+/// so failures surface at once
+async fn client_for(app: Router) -> api::ApiClient {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let host = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let connect_retry = ConnectRetry { attempts: 0, delay: Duration::ZERO };
+    let scale = SignalScale { factor: 1.0, offset: 0.0 };
+    let mut client =
+        api::create_client(&host, Duration::from_secs(5), connect_retry, scale, &TlsVerification::System).unwrap();
+    client.set_retry(RetryPolicy { max_retries: 0, base_delay: Duration::ZERO });
+    client
+}
+
+/// A modem serving the captured CODA56 payloads
+fn modem() -> Router {
+    Router::new()
+        .route("/data/dsinfo.asp", get(|| async { include_str!("fixtures/dsinfo.json") }))
+        .route("/data/usinfo.asp", get(|| async { include_str!("fixtures/usinfo.json") }))
+        .route("/data/status_log.asp", get(|| async { include_str!("fixtures/status_log.json") }))
+}
+
+#[tokio::test]
+async fn downstream_channels_parse_from_strings() {
+    let client = client_for(modem()).await;
+
+    let channels = api::get_downstream_info(&client).await.unwrap();
+    assert_eq!(channels.len(), 3);
+    let first = &channels[0];
+    assert_eq!(first.channel_id, 9);
+    assert_eq!(first.port_id, 1);
+    assert_eq!(first.frequency, 591_000_000.0);
+    assert_eq!(first.modulation, "2");
+    assert_eq!(first.signal_strength, 3.1);
+    assert_eq!(first.snr, 40.366);
+    assert_eq!((first.correcteds, first.uncorrect), (12, 0));
+    assert_eq!((channels[2].correcteds, channels[2].uncorrect), (7, 2));
+}
+
+#[tokio::test]
+async fn upstream_channels_parse_without_snr() {
+    let client = client_for(modem()).await;
+
+    let channels = api::get_upstream_info(&client).await.unwrap();
+    assert_eq!(channels.len(), 2);
+    assert_eq!(channels[0].channel_id, 3);
+    assert_eq!(channels[0].frequency, 38_600_000.0);
+    assert_eq!(channels[0].modulation_type, "64QAM");
+    assert_eq!(channels[0].signal_strength, 44.25);
+    assert_eq!(channels[0].snr, None);
+}
+
+#[tokio::test]
+async fn event_log_parses_with_timestamps() {
+    let client = client_for(modem()).await;
+
+    let events = api::get_event_log(&client).await.unwrap();
+    let priorities: Vec<&EventPriority> = events.iter().map(|e| &e.priority).collect();
+    assert_eq!(priorities, [&EventPriority::Critical, &EventPriority::Warning, &EventPriority::Notice]);
+    assert_eq!(events[1].index, 2);
+    assert_eq!(events[1].event_type, "82001100");
+    assert!(events[0].event.starts_with("No Ranging Response received - T3 time-out"));
+    let timestamp = chrono::NaiveDate::from_ymd_opt(2026, 6, 27).unwrap().and_hms_opt(15, 23, 34).unwrap();
+    assert_eq!(events[1].timestamp, Some(timestamp));
+}
+
+#[tokio::test]
+async fn malformed_payloads_are_errors() {
+    let app = Router::new()
+        // A field the firmware left as text
+        .route(
+            "/data/dsinfo.asp",
+            get(|| async {
+                r#"[{"portId":"1","frequency":"N/A","modulation":"2","signalStrength":"3.1","snr":"40.3","correcteds":"0","uncorrect":"0","channelId":"9"}]"#
+            }),
+        )
+        // A login page in place of the data
+        .route("/data/usinfo.asp", get(|| async { "<html><body>Please log in</body></html>" }))
+        // Not a channel list at all
+        .route("/data/status_log.asp", get(|| async { r#"{"error":"busy"}"# }));
+    let client = client_for(app).await;
+
+    let err = api::get_downstream_info(&client).await.unwrap_err();
+    assert!(err.downcast_ref::<ApiError>().is_none(), "{:?}", err);
+
+    let err = api::get_upstream_info(&client).await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<ApiError>(), Some(ApiError::NotJson { .. })));
+
+    let err = api::get_event_log(&client).await.unwrap_err();
+    assert!(err.downcast_ref::<ApiError>().is_none(), "{:?}", err);
+}